@@ -1,9 +1,24 @@
+use crate::commands::git::{ensure_idle, worktrees};
 use crate::error::{AppError, Result};
 use crate::models::config::Config;
-use crate::models::events::StreamEvent;
-use std::io::{BufRead, BufReader};
-use std::process::{Command, Stdio, Child};
-use tauri::{Emitter, Window};
+use crate::models::delivery::DeliveryMetrics;
+use crate::models::scheduler::{RunLimits, RunQueueStatus};
+use crate::models::events::{ParseStats, StreamEvent};
+use crate::models::shell::ShellJobEvent;
+use crate::services::file_index::edited_paths;
+use crate::services::delivery::{spawn_delivery, DeliveryConfig, DeliveryHandle, EventSender};
+use crate::services::partial::{DeltaCoalescer, PartialAssembler};
+use crate::services::scheduler::Admission;
+use crate::services::session_lock::{self, SessionLocks, StaleSession};
+use crate::services::recorder::{self, RecordEntry, RecordStream, Recording, RecordingInfo, SessionRecorder};
+use std::future::Future;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::{Emitter, Manager, Window};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 #[cfg(windows)]
@@ -13,75 +28,95 @@ use std::os::windows::process::CommandExt;
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+/// 异常退出时附带的 stderr 行数
+const STDERR_TAIL_LINES: usize = 20;
+
+/// 进程退出后等待输出管道关闭的最长时间（子进程可能继承了管道）
+const PIPE_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 中断时等待进程自行退出的时间，超时后强制终止
+const TERMINATE_GRACE: Duration = Duration::from_secs(2);
+
+/// 应用退出时等待全部会话结束的最长时间
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 回放倍速范围（0 表示不等待）
+pub const REPLAY_SPEED_RANGE: std::ops::RangeInclusive<f64> = 0.1..=100.0;
+
+/// 回放时两行之间的最长等待
+const MAX_REPLAY_GAP: Duration = Duration::from_secs(60);
+
 /// Claude 聊天会话
 pub struct ChatSession {
     pub id: String,
     pub child: Child,
+    /// 可选的输出录制器
+    pub recorder: Option<SessionRecorder>,
+    /// 中断信号
+    cancel: CancellationToken,
 }
 
 impl ChatSession {
     /// 创建ChatSession实例（用于continue_chat）
     pub fn with_id_and_child(id: String, child: Child) -> Self {
-        Self { id, child }
+        Self { id, child, recorder: None, cancel: CancellationToken::new() }
+    }
+
+    /// 中断令牌，取消后终止进程并结束读取
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// 录制本次会话的原始输出
+    pub fn with_recorder(mut self, recorder: SessionRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
     }
 }
 
 impl ChatSession {
-    /// 启动新的聊天会话
-    pub fn start(config: &Config, message: &str) -> Result<Self> {
-        eprintln!("[ChatSession::start] 启动 Claude 会话");
-        eprintln!("[ChatSession::start] claude_cmd: {}", config.claude_cmd);
-        eprintln!("[ChatSession::start] message: {}", message);
-        eprintln!("[ChatSession::start] permission_mode: {}", config.permission_mode);
+    /// 构建 Claude CLI 命令
+    ///
+    /// `extra_args` 插在公共参数之前（例如 `--resume <id>`），消息总是最后一个参数。
+    fn build_command(config: &Config, extra_args: &[&str], message: &str) -> Command {
+        let mut cmd = claude_command(config);
 
-        // 在 Windows 上，.cmd 文件需要通过 cmd.exe 执行
-        // 参数必须分别传递，不能合并为一个字符串
-        #[cfg(windows)]
-        let mut cmd = Command::new("cmd");
-        #[cfg(windows)]
-        cmd.args([
-            "/c",
-            &config.claude_cmd,
-            "--print",
-            "--verbose",
-            "--output-format",
-            "stream-json",
-            "--permission-mode",
-            &config.permission_mode,
-            message,
-        ]);
-
-        #[cfg(not(windows))]
-        let mut cmd = Command::new(&config.claude_cmd);
-        #[cfg(not(windows))]
+        cmd.args(extra_args);
         cmd.args([
             "--print",
             "--verbose",
             "--output-format",
             "stream-json",
+            "--include-partial-messages",
             "--permission-mode",
             &config.permission_mode,
             message,
         ]);
 
         cmd.stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
 
-        // Windows 上隐藏 CMD 窗口
-        #[cfg(windows)]
-        cmd.creation_flags(CREATE_NO_WINDOW);
+        // 独立进程组，中断时工具子进程一并终止
+        #[cfg(unix)]
+        cmd.process_group(0);
 
-        // 设置工作目录
-        if let Some(ref work_dir) = config.work_dir {
-            eprintln!("[ChatSession::start] work_dir: {:?}", work_dir);
-            cmd.current_dir(work_dir);
-        }
+        cmd
+    }
 
-        // 设置 Git Bash 环境变量 (Windows 需要)
-        if let Some(ref git_bash_path) = config.git_bin_path {
-            eprintln!("[ChatSession::start] 设置 CLAUDE_CODE_GIT_BASH_PATH: {}", git_bash_path);
-            cmd.env("CLAUDE_CODE_GIT_BASH_PATH", git_bash_path);
-        }
+    /// 启动新的聊天会话
+    pub fn start(config: &Config, message: &str) -> Result<Self> {
+        Self::start_with_id(config, &Uuid::new_v4().to_string(), message)
+    }
+
+    /// 使用指定的本地会话 ID 启动新的聊天会话（排队时 ID 需要提前分配）
+    pub fn start_with_id(config: &Config, session_id: &str, message: &str) -> Result<Self> {
+        eprintln!("[ChatSession::start] 启动 Claude 会话");
+        eprintln!("[ChatSession::start] claude_cmd: {}", config.claude_cmd);
+        eprintln!("[ChatSession::start] message: {}", message);
+        eprintln!("[ChatSession::start] permission_mode: {}", config.permission_mode);
+
+        let mut cmd = Self::build_command(config, &[], message);
 
         eprintln!("[ChatSession::start] 执行命令: {:?}", cmd);
 
@@ -90,132 +125,742 @@ impl ChatSession {
 
         eprintln!("[ChatSession::start] 进程 PID: {:?}", child.id());
 
-        Ok(Self {
-            id: Uuid::new_v4().to_string(),
-            child,
-        })
+        Ok(Self::with_id_and_child(session_id.to_string(), child))
+    }
+
+    /// 使用 Claude CLI 原生的 --resume 参数恢复会话
+    pub fn resume(config: &Config, session_id: &str, message: &str) -> Result<Self> {
+        eprintln!("[ChatSession::resume] 恢复会话: {}", session_id);
+
+        let mut cmd = Self::build_command(config, &["--resume", session_id], message);
+
+        eprintln!("[ChatSession::resume] 执行命令: {:?}", cmd);
+
+        let child = cmd.spawn()
+            .map_err(|e| AppError::ProcessError(format!("继续 Claude 会话失败: {}", e)))?;
+
+        eprintln!("[ChatSession::resume] 进程 PID: {:?}", child.id());
+
+        Ok(Self::with_id_and_child(session_id.to_string(), child))
     }
 
     /// 读取输出并解析事件
-    pub fn read_events<F>(self, mut callback: F)
+    ///
+    /// 同时等待 stdout、stderr、进程退出和中断信号。
+    /// 非零退出码会以 `Error` 事件报告，中断时不报告错误。
+    pub async fn read_events<F, Fut>(mut self, mut callback: F)
     where
-        F: FnMut(StreamEvent) + Send + 'static,
+        F: FnMut(StreamEvent) -> Fut,
+        Fut: Future<Output = ()>,
     {
         eprintln!("[ChatSession::read_events] 开始读取输出");
 
-        let stdout = self.child.stdout
-            .ok_or_else(|| AppError::ProcessError("无法获取 stdout".to_string()));
+        let Some(stdout) = self.child.stdout.take() else {
+            eprintln!("[ChatSession::read_events] 无法获取 stdout");
+            return;
+        };
 
-        if stdout.is_err() {
+        let Some(stderr) = self.child.stderr.take() else {
+            eprintln!("[ChatSession::read_events] 无法获取 stderr");
             return;
+        };
+
+        let mut stdout_lines = BufReader::new(stdout).lines();
+        let mut stderr_lines = BufReader::new(stderr).lines();
+        let mut pipeline = EventPipeline::default();
+        let mut ready: Vec<StreamEvent> = Vec::new();
+        let mut stderr_tail: Vec<String> = Vec::new();
+
+        let mut stdout_open = true;
+        let mut stderr_open = true;
+        let mut cancelled = false;
+        let mut killed = false;
+        let mut exit_status = None;
+        let drain_deadline = tokio::time::sleep(Duration::MAX);
+        tokio::pin!(drain_deadline);
+        let kill_deadline = tokio::time::sleep(Duration::MAX);
+        tokio::pin!(kill_deadline);
+
+        while stdout_open || stderr_open {
+            let flush_at = pipeline.flush_deadline();
+            tokio::select! {
+                _ = self.cancel.cancelled(), if !cancelled => {
+                    eprintln!("[ChatSession::read_events] 收到中断，通知进程退出");
+                    cancelled = true;
+                    self.terminate(false);
+                    kill_deadline.as_mut().reset(tokio::time::Instant::now() + TERMINATE_GRACE);
+                }
+                _ = &mut kill_deadline, if cancelled && !killed && exit_status.is_none() => {
+                    eprintln!("[ChatSession::read_events] 进程未在限定时间内退出，强制终止");
+                    killed = true;
+                    self.terminate(true);
+                }
+                line = stdout_lines.next_line(), if stdout_open => match line {
+                    Ok(Some(line)) => {
+                        if let Some(ref recorder) = self.recorder {
+                            recorder.record_line(RecordStream::Stdout, &line);
+                        }
+                        pipeline.push_line(&line, &mut |event| ready.push(event));
+                    }
+                    Ok(None) => stdout_open = false,
+                    Err(e) => {
+                        eprintln!("[ChatSession::read_events] 读取行错误: {}", e);
+                        stdout_open = false;
+                    }
+                },
+                line = stderr_lines.next_line(), if stderr_open => match line {
+                    Ok(Some(line)) => {
+                        eprintln!("[stderr] {}", line);
+                        if let Some(ref recorder) = self.recorder {
+                            recorder.record_line(RecordStream::Stderr, &line);
+                        }
+                        push_stderr_tail(&mut stderr_tail, line);
+                    }
+                    Ok(None) | Err(_) => stderr_open = false,
+                },
+                status = self.child.wait(), if exit_status.is_none() => {
+                    eprintln!("[ChatSession::read_events] 进程退出: {:?}", status);
+                    exit_status = Some(status);
+                    drain_deadline.as_mut().reset(tokio::time::Instant::now() + PIPE_DRAIN_TIMEOUT);
+                }
+                _ = tokio::time::sleep_until(flush_at.unwrap_or_else(tokio::time::Instant::now)), if flush_at.is_some() => {
+                    // 没有新输出时也按时发送缓冲的增量
+                    pipeline.finish(&mut |event| ready.push(event));
+                }
+                _ = &mut drain_deadline => {
+                    eprintln!("[ChatSession::read_events] 进程已退出但输出管道未关闭，停止读取");
+                    break;
+                }
+            }
+
+            for event in ready.drain(..) {
+                callback(event).await;
+            }
         }
 
-        let stderr = self.child.stderr
-            .ok_or_else(|| AppError::ProcessError("无法获取 stderr".to_string()));
+        pipeline.finish(&mut |event| ready.push(event));
+        for event in ready.drain(..) {
+            callback(event).await;
+        }
+
+        eprintln!("[ChatSession::read_events] 读取结束，共处理 {} 行，未识别 {} 个，解析失败 {} 个",
+            pipeline.line_count, pipeline.stats.unknown, pipeline.stats.parse_errors);
+
+        let status = match exit_status {
+            Some(status) => status,
+            None => self.child.wait().await,
+        };
 
-        if stderr.is_err() {
+        match status {
+            Ok(status) => {
+                if let Some(ref recorder) = self.recorder {
+                    recorder.record_exit(status.code(), cancelled);
+                }
+                // 中断后进程可能以非零退出码结束（如 130、143），不视为异常
+                if !cancelled {
+                    if let Some(event) = exit_error(status.code(), &stderr_tail) {
+                        callback(event).await;
+                    }
+                }
+            }
+            Err(e) => eprintln!("[ChatSession::read_events] 等待进程失败: {}", e),
+        }
+    }
+
+    /// 终止进程（含工具子进程），`force` 为 false 时先让进程自行清理
+    fn terminate(&mut self, force: bool) {
+        let Some(pid) = self.child.id() else {
             return;
+        };
+
+        let signalled = session_lock::signal_process_group(pid, force);
+        if force || !signalled {
+            if let Err(e) = self.child.start_kill() {
+                eprintln!("[ChatSession::terminate] 终止进程失败: {}", e);
+            }
         }
+    }
+
+    /// 回放录制的会话，不启动 CLI
+    ///
+    /// 与 `read_events` 使用相同的解析流程。`speed` 为回放倍速（限制在 [`REPLAY_SPEED_RANGE`] 内），
+    /// 0 表示不等待；两行之间最多等待 [`MAX_REPLAY_GAP`]。
+    pub async fn replay<F, Fut>(recording: &Recording, speed: f64, mut callback: F)
+    where
+        F: FnMut(StreamEvent) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let speed = match speed {
+            s if s.is_finite() && s > 0.0 => s.clamp(*REPLAY_SPEED_RANGE.start(), *REPLAY_SPEED_RANGE.end()),
+            _ => 0.0,
+        };
+        eprintln!("[ChatSession::replay] 回放会话: {}, 倍速: {}", recording.session_id, speed);
+
+        let mut last_t = 0;
+        let mut pipeline = EventPipeline::default();
+        let mut ready: Vec<StreamEvent> = Vec::new();
+        let mut stderr_tail: Vec<String> = Vec::new();
 
-        let stdout = stdout.unwrap();
-        let stderr = stderr.unwrap();
-
-        // 启动单独的线程读取 stderr
-        std::thread::spawn(move || {
-            eprintln!("[stderr_reader] 开始读取 stderr");
-            let reader = BufReader::new(stderr);
-            for line in reader.lines() {
-                match line {
-                    Ok(l) => eprintln!("[stderr] {}", l),
-                    Err(_) => break,
+        for entry in &recording.entries {
+            let t = match entry {
+                RecordEntry::Line { t, .. } | RecordEntry::Exit { t, .. } => *t,
+                RecordEntry::Header { .. } => continue,
+            };
+            if speed > 0.0 && t > last_t {
+                let gap = Duration::try_from_secs_f64((t - last_t) as f64 / 1000.0 / speed)
+                    .map_or(MAX_REPLAY_GAP, |gap| gap.min(MAX_REPLAY_GAP));
+                let next_at = tokio::time::Instant::now() + gap;
+                // 与 read_events 一致，等待期间到期的增量先行发送
+                if let Some(flush_at) = pipeline.flush_deadline().filter(|at| *at < next_at) {
+                    tokio::time::sleep_until(flush_at).await;
+                    pipeline.finish(&mut |event| ready.push(event));
+                    for event in ready.drain(..) {
+                        callback(event).await;
+                    }
                 }
+                tokio::time::sleep_until(next_at).await;
             }
-            eprintln!("[stderr_reader] stderr 结束");
-        });
+            last_t = t;
 
-        let reader = BufReader::new(stdout);
-        let mut line_count = 0;
-
-        for line in reader.lines() {
-            let line = match line {
-                Ok(l) => l,
-                Err(e) => {
-                    eprintln!("[ChatSession::read_events] 读取行错误: {}", e);
-                    break;
+            match entry {
+                RecordEntry::Line { stream: RecordStream::Stdout, line, .. } => {
+                    pipeline.push_line(line, &mut |event| ready.push(event));
                 }
-            };
+                RecordEntry::Line { stream: RecordStream::Stderr, line, .. } => {
+                    eprintln!("[stderr] {}", line);
+                    push_stderr_tail(&mut stderr_tail, line.clone());
+                }
+                RecordEntry::Exit { code, cancelled, .. } => {
+                    pipeline.finish(&mut |event| ready.push(event));
+                    // 与 read_events 一致，中断导致的退出不视为异常
+                    if !*cancelled {
+                        ready.extend(exit_error(*code, &stderr_tail));
+                    }
+                }
+                RecordEntry::Header { .. } => {}
+            }
+
+            for event in ready.drain(..) {
+                callback(event).await;
+            }
+        }
+
+        pipeline.finish(&mut |event| ready.push(event));
+        for event in ready.drain(..) {
+            callback(event).await;
+        }
+
+        eprintln!("[ChatSession::replay] 回放结束，共处理 {} 行，未识别 {} 个，解析失败 {} 个",
+            pipeline.line_count, pipeline.stats.unknown, pipeline.stats.parse_errors);
+    }
+}
+
+/// stdout 行处理流水线（直播与回放共用）
+///
+/// 解析 → 组装部分消息 → 合并增量 → 回调。
+#[derive(Default)]
+struct EventPipeline {
+    line_count: usize,
+    stats: ParseStats,
+    partial: PartialAssembler,
+    coalescer: DeltaCoalescer,
+}
+
+impl EventPipeline {
+    /// 处理一行 stdout
+    fn push_line<F>(&mut self, line: &str, callback: &mut F)
+    where
+        F: FnMut(StreamEvent),
+    {
+        self.line_count += 1;
+        let line_trimmed = line.trim();
+
+        // 使用 StreamEvent::parse_line 解析，只有空行返回 None
+        let Some(event) = StreamEvent::parse_line(line_trimmed) else {
+            return;
+        };
 
-            line_count += 1;
-            let line_trimmed = line.trim();
+        self.stats.record(&event);
 
-            if line_trimmed.is_empty() {
-                continue;
+        match &event {
+            StreamEvent::Partial { event: inner, .. } => {
+                let envelope = serde_json::to_value(&event).unwrap_or_default();
+                let converted = self.partial.convert(inner, &envelope);
+                if converted.is_empty() {
+                    // 内容块结束等边界事件，及时输出缓冲的增量
+                    self.coalescer.flush(callback);
+                }
+                for event in converted {
+                    self.coalescer.push(event, callback);
+                }
+                return;
+            }
+            StreamEvent::ParseError { error, .. } => {
+                eprintln!("[ChatSession::read_events] 行 {} 解析失败: {}，原始内容: {}", self.line_count, error, line_trimmed.chars().take(200).collect::<String>());
+            }
+            StreamEvent::Unknown { .. } => {
+                eprintln!("[ChatSession::read_events] 行 {} 未识别的事件类型，原样转发: {}", self.line_count, line_trimmed.chars().take(100).collect::<String>());
+            }
+            _ => {
+                eprintln!("[ChatSession::read_events] 行 {} 解析成功事件: {}", self.line_count, event.kind());
             }
+        }
+
+        self.coalescer.push(event, callback);
+    }
+
+    /// 缓冲增量的输出期限
+    fn flush_deadline(&self) -> Option<tokio::time::Instant> {
+        self.coalescer.deadline()
+    }
+
+    /// 输出尚未发送的增量
+    fn finish<F>(&mut self, callback: &mut F)
+    where
+        F: FnMut(StreamEvent),
+    {
+        self.coalescer.flush(callback);
+    }
+}
+
+/// 保留 stderr 最后几行
+fn push_stderr_tail(tail: &mut Vec<String>, line: String) {
+    if tail.len() == STDERR_TAIL_LINES {
+        tail.remove(0);
+    }
+    tail.push(line);
+}
+
+/// 进程非零退出时生成错误事件
+///
+/// 被信号终止时没有退出码，不视为异常；被中断的会话不会调用此函数。
+fn exit_error(code: Option<i32>, stderr_tail: &[String]) -> Option<StreamEvent> {
+    let code = code.filter(|c| *c != 0)?;
+    let mut error = format!("Claude 进程异常退出，退出码: {}", code);
+    if !stderr_tail.is_empty() {
+        error.push('\n');
+        error.push_str(&stderr_tail.join("\n"));
+    }
+    Some(StreamEvent::Error { error })
+}
 
-            eprintln!("[ChatSession::read_events] 行 {}: {}", line_count, line_trimmed.chars().take(100).collect::<String>());
+/// 终止进程
+pub fn kill_process(process_id: u32) -> Result<()> {
+    // 使用系统API终止进程
+    #[cfg(windows)]
+    let result = std::process::Command::new("taskkill")
+        .args(["/F", "/PID", &process_id.to_string()])
+        .output();
 
-            // 使用 StreamEvent::parse_line 解析
-            if let Some(event) = StreamEvent::parse_line(line_trimmed) {
-                eprintln!("[ChatSession::read_events] 解析成功事件: {:?}", std::mem::discriminant(&event));
-                callback(event);
+    #[cfg(not(windows))]
+    let result = std::process::Command::new("kill")
+        .arg("-9")
+        .arg(process_id.to_string())
+        .output();
+
+    match result {
+        Ok(output) => {
+            if output.status.success() {
+                eprintln!("[kill_process] 成功终止进程 {}", process_id);
+                Ok(())
             } else {
-                eprintln!("[ChatSession::read_events] 解析失败，原始内容: {}", line_trimmed.chars().take(200).collect::<String>());
+                eprintln!("[kill_process] 终止进程失败: {}", String::from_utf8_lossy(&output.stderr));
+                Err(AppError::ProcessError(format!("无法终止进程: {}", String::from_utf8_lossy(&output.stderr))))
+            }
+        }
+        Err(e) => {
+            eprintln!("[kill_process] 执行终止命令失败: {}", e);
+            Err(AppError::ProcessError(format!("无法终止进程: {}", e)))
+        }
+    }
+}
+
+/// 创建 Claude CLI 进程命令（不含参数），设置工作目录和平台相关的环境
+pub(crate) fn claude_command(config: &Config) -> Command {
+    // 在 Windows 上，.cmd 文件需要通过 cmd.exe 执行
+    // 参数必须分别传递，不能合并为一个字符串
+    #[cfg(windows)]
+    let mut cmd = Command::new("cmd");
+    #[cfg(windows)]
+    cmd.args(["/c", &config.claude_cmd]);
+
+    #[cfg(not(windows))]
+    let mut cmd = Command::new(&config.claude_cmd);
+
+    // Windows 上隐藏 CMD 窗口
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    // 设置工作目录
+    if let Some(ref work_dir) = config.work_dir {
+        eprintln!("[claude_command] work_dir: {:?}", work_dir);
+        cmd.current_dir(work_dir);
+    }
+
+    // 设置 Git Bash 环境变量 (Windows 需要)
+    if let Some(ref git_bash_path) = config.git_bin_path {
+        eprintln!("[claude_command] 设置 CLAUDE_CODE_GIT_BASH_PATH: {}", git_bash_path);
+        cmd.env("CLAUDE_CODE_GIT_BASH_PATH", git_bash_path);
+    }
+
+    cmd
+}
+
+/// 中断全部会话并等待其结束（应用退出时调用）
+///
+/// 排队中的会话直接取消，运行中的会话发送中断信号，终端一并关闭。
+/// 返回超时后仍未结束的会话数。
+pub fn shutdown_sessions(state: &crate::AppState, timeout: Duration) -> usize {
+    if let Ok(mut scheduler) = state.scheduler.lock() {
+        let cancelled = scheduler.cancel_all();
+        if cancelled > 0 {
+            eprintln!("[shutdown_sessions] 取消排队中的会话: {}", cancelled);
+        }
+    }
+
+    if let Ok(sessions) = state.sessions.lock() {
+        eprintln!("[shutdown_sessions] 中断运行中的会话: {}", sessions.len());
+        for cancel in sessions.values() {
+            cancel.cancel();
+        }
+        if let Ok(mut tracker) = state.shell_tracker.lock() {
+            sessions.keys().for_each(|session_id| tracker.remove_session(session_id));
+        }
+    }
+
+    if let Ok(mut pty_manager) = state.pty_manager.lock() {
+        pty_manager.close_all();
+    }
+
+    // 读取任务结束时会移除 sessions 中的记录并删除锁文件
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        let remaining = state.sessions.lock().map(|s| s.len()).unwrap_or(0);
+        if remaining == 0 || std::time::Instant::now() >= deadline {
+            if remaining > 0 {
+                eprintln!("[shutdown_sessions] 等待超时，仍有 {} 个会话未结束", remaining);
             }
+            return remaining;
         }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
 
-        eprintln!("[ChatSession::read_events] 读取结束，共处理 {} 行", line_count);
+/// 更新 Bash 任务追踪并推送变化
+fn track_shell_jobs(window: &Window, session_id: &str, cwd: Option<&str>, event: &StreamEvent) {
+    let state = window.state::<crate::AppState>();
+    let updates = match state.shell_tracker.lock() {
+        Ok(mut tracker) => tracker.observe(session_id, cwd, event),
+        Err(e) => {
+            eprintln!("[track_shell_jobs] 获取锁失败: {}", e);
+            return;
+        }
+    };
+
+    for update in updates {
+        let _ = window.emit("shell-job", ShellJobEvent {
+            session_id: session_id.to_string(),
+            ..update
+        });
     }
 }
 
+/// 记录 Agent 编辑过的文件（用于文件搜索排序）
+fn track_agent_edits(window: &Window, cwd: Option<&str>, event: &StreamEvent) {
+    let paths = edited_paths(event, cwd.map(Path::new));
+    if paths.is_empty() {
+        return;
+    }
+    match window.state::<crate::AppState>().recent_edits.lock() {
+        Ok(mut recent) => paths.into_iter().for_each(|path| recent.touch(path)),
+        Err(e) => eprintln!("[track_agent_edits] 获取锁失败: {}", e),
+    }
+}
+
+/// 隔离会话收到 system 事件时记录 Claude 会话 ID
+///
+/// 前端之后用 Claude 会话 ID 继续、合并和删除会话，需要据此找到工作树。
+fn track_worktree_alias(window: &Window, session_id: &str, event: &StreamEvent) {
+    let StreamEvent::System { extra, .. } = event else {
+        return;
+    };
+    let Some(claude_id) = extra.get("session_id").and_then(|v| v.as_str()) else {
+        return;
+    };
+    if claude_id == session_id {
+        return;
+    }
+    let state = window.state::<crate::AppState>();
+    if let Err(e) = worktrees(&state).and_then(|manager| manager.alias(session_id, claude_id)) {
+        eprintln!("[track_worktree_alias] 记录工作树别名失败: {}", e);
+    }
+}
+
+/// 会话事件转发器：更新追踪与统计，并交给推送通道
+#[derive(Clone)]
+struct EventForwarder {
+    window: Window,
+    session_id: String,
+    cwd: Option<String>,
+    sender: EventSender,
+    source: &'static str,
+    /// 回放模式：只推送事件，不更新追踪与统计
+    replay: bool,
+}
+
+impl EventForwarder {
+    /// 为会话创建推送通道，批量推送 `chat-events`
+    fn open(window: &Window, session_id: &str, cwd: Option<String>, source: &'static str) -> Result<Self> {
+        let state = window.state::<crate::AppState>();
+        let handle = {
+            let mut deliveries = state.deliveries.lock()
+                .map_err(|e| AppError::Unknown(e.to_string()))?;
+            deliveries.entry(session_id.to_string()).or_default().clone()
+        };
+
+        let emit_window = window.clone();
+        let (sender, _) = spawn_delivery(session_id.to_string(), handle, DeliveryConfig::default(), move |batch| {
+            emit_window.emit("chat-events", batch).is_ok()
+        });
+
+        Ok(Self {
+            window: window.clone(),
+            session_id: session_id.to_string(),
+            cwd,
+            sender,
+            source,
+            replay: false,
+        })
+    }
+
+    /// 为回放创建推送通道（`session_id` 需与实时会话区分）
+    fn open_replay(window: &Window, session_id: &str) -> Result<Self> {
+        Ok(Self { replay: true, ..Self::open(window, session_id, None, "replay_session")? })
+    }
+
+    /// 将事件转发到前端
+    async fn forward(self, event: StreamEvent) {
+        if !self.replay {
+            track_shell_jobs(&self.window, &self.session_id, self.cwd.as_deref(), &event);
+            track_agent_edits(&self.window, self.cwd.as_deref(), &event);
+            track_worktree_alias(&self.window, &self.session_id, &event);
+
+            if let Ok(mut stats) = self.window.state::<crate::AppState>().parse_stats.lock() {
+                stats.entry(self.session_id.clone()).or_default().record(&event);
+            }
+        }
+
+        eprintln!("[{}] 发送事件: {}", self.source, event.kind());
+        if !self.sender.send(event).await {
+            eprintln!("[{}] 推送通道已关闭，事件被丢弃", self.source);
+        }
+    }
+}
+
+/// 按配置为会话创建录制器
+fn attach_recorder(session: ChatSession, config: &Config, recordings_dir: Option<&Path>) -> ChatSession {
+    if !config.record_sessions {
+        return session;
+    }
+    let Some(dir) = recordings_dir else {
+        return session;
+    };
+
+    match SessionRecorder::create(dir, &session.id) {
+        Ok(recorder) => {
+            eprintln!("[attach_recorder] 录制到: {:?}", recorder.path());
+            session.with_recorder(recorder)
+        }
+        Err(e) => {
+            eprintln!("[attach_recorder] 创建录制失败: {}", e);
+            session
+        }
+    }
+}
+
+/// 配置中的会话运行限制
+pub fn run_limits(config: &Config) -> RunLimits {
+    RunLimits {
+        max_concurrent: config.max_concurrent_sessions,
+        max_per_workspace: config.max_sessions_per_workspace,
+    }
+}
+
+/// 会话启动参数（排队时延后启动）
+struct RunRequest {
+    session_id: String,
+    message: String,
+    /// 是否使用 --resume 继续已有会话
+    resume: bool,
+    config: Config,
+    recordings_dir: Option<std::path::PathBuf>,
+    /// 调度时所属的工作区（隔离会话为原工作目录），None 时使用 `config.work_dir`
+    workspace: Option<String>,
+}
+
+impl RunRequest {
+    fn spawn(&self) -> Result<ChatSession> {
+        let session = if self.resume {
+            ChatSession::resume(&self.config, &self.session_id, &self.message)?
+        } else {
+            ChatSession::start_with_id(&self.config, &self.session_id, &self.message)?
+        };
+        Ok(attach_recorder(session, &self.config, self.recordings_dir.as_deref()))
+    }
+}
+
+/// 释放调度名额
+fn release_run(window: &Window, session_id: &str) {
+    match window.state::<crate::AppState>().scheduler.lock() {
+        Ok(mut scheduler) => scheduler.finish(session_id),
+        Err(e) => eprintln!("[release_run] 获取锁失败: {}", e),
+    }
+}
+
+/// 读取会话输出直到结束，然后释放名额
+async fn run_session(window: Window, session: ChatSession, forwarder: EventForwarder) {
+    let session_id = session.id.clone();
+    eprintln!("[run_session] 会话 ID: {}, 进程 ID: {:?}", session_id, session.child.id());
+
+    let state = window.state::<crate::AppState>();
+
+    // 将中断令牌存储到全局sessions中
+    if let Ok(mut sessions) = state.sessions.lock() {
+        sessions.insert(session_id.clone(), session.cancel_token());
+    }
+
+    // 写入锁文件，应用崩溃后可据此清理遗留进程
+    let locks = state.config_store.lock()
+        .ok()
+        .and_then(|store| store.locks_dir().ok())
+        .map(SessionLocks::new);
+    if let (Some(locks), Some(pid)) = (&locks, session.child.id()) {
+        if let Err(e) = locks.acquire(&session_id, pid, forwarder.cwd.as_deref()) {
+            eprintln!("[run_session] 写入锁文件失败: {}", e);
+        }
+    }
+
+    session.read_events(move |event| forwarder.clone().forward(event)).await;
+
+    if let Some(locks) = &locks {
+        locks.release(&session_id);
+    }
+    if let Ok(mut sessions) = state.sessions.lock() {
+        sessions.remove(&session_id);
+    }
+    release_run(&window, &session_id);
+    eprintln!("[run_session] 会话 {} 结束", session_id);
+}
+
+/// 提交会话到调度器
+///
+/// 名额充足时立即启动，启动失败直接返回错误；
+/// 否则推送 `queued` 事件，在后台等待名额，启动前可被 interrupt_chat 取消。
+async fn submit_run(
+    window: &Window,
+    request: RunRequest,
+    priority: i32,
+    source: &'static str,
+) -> Result<()> {
+    let cwd = request.config.work_dir.as_ref().map(|p| p.to_string_lossy().to_string());
+    let workspace = request.workspace.clone().or_else(|| cwd.clone()).unwrap_or_default();
+    let session_id = request.session_id.clone();
+    let forwarder = EventForwarder::open(window, &session_id, cwd, source)?;
+
+    let admission = {
+        let state = window.state::<crate::AppState>();
+        let mut scheduler = state.scheduler.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        scheduler.set_limits(run_limits(&request.config));
+        scheduler.admit(&session_id, &workspace, priority)
+    };
+
+    match admission {
+        Admission::Started => {
+            let session = request.spawn().inspect_err(|_| release_run(window, &session_id))?;
+            tokio::spawn(run_session(window.clone(), session, forwarder));
+        }
+        Admission::Queued { position, ready } => {
+            eprintln!("[{}] 会话 {} 排队中，位置: {}", source, session_id, position);
+            forwarder.clone().forward(StreamEvent::Queued { session_id: session_id.clone(), position }).await;
+
+            let window = window.clone();
+            tokio::spawn(async move {
+                let cancelled = ready.await.is_err();
+                forwarder.clone().forward(StreamEvent::Dequeued { session_id: session_id.clone(), cancelled }).await;
+                if cancelled {
+                    eprintln!("[submit_run] 排队中的会话 {} 已取消", session_id);
+                    return;
+                }
+
+                match request.spawn() {
+                    Ok(session) => run_session(window, session, forwarder).await,
+                    Err(e) => {
+                        release_run(&window, &session_id);
+                        forwarder.forward(StreamEvent::Error { error: e.to_string() }).await;
+                    }
+                }
+            });
+        }
+    }
+
+    Ok(())
+}
+
 // ============================================================================
 // Tauri Commands
 // ============================================================================
 
 /// 启动聊天会话（后台异步执行）
+///
+/// 达到并发上限时会话进入队列，返回的会话 ID 可用于取消。
 #[tauri::command]
 pub async fn start_chat(
     message: String,
+    priority: Option<i32>,
+    isolated: Option<bool>,
     window: Window,
     state: tauri::State<'_, crate::AppState>,
 ) -> Result<String> {
     eprintln!("[start_chat] 收到消息: {}", message);
 
     // 从 AppState 获取实际配置
-    let config_store = state.config_store.lock()
-        .map_err(|e| crate::error::AppError::Unknown(e.to_string()))?;
-    let config = config_store.get().clone();
-
-    // 启动 Claude 会话
-    let session = ChatSession::start(&config, &message)?;
-
-    let session_id = session.id.clone();
-    let window_clone = window.clone();
-    let process_id = session.child.id();
+    let (mut config, recordings_dir) = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| crate::error::AppError::Unknown(e.to_string()))?;
+        (config_store.get().clone(), config_store.recordings_dir().ok())
+    };
 
-    eprintln!("[start_chat] 会话 ID: {}, 进程 ID: {}", session_id, process_id);
+    let session_id = Uuid::new_v4().to_string();
 
-    // 将进程ID存储到全局sessions中
-    {
-        let mut sessions = state.sessions.lock()
-            .map_err(|e| crate::error::AppError::Unknown(e.to_string()))?;
-        sessions.insert(session_id.clone(), process_id);
+    // 隔离模式：在独立的 git 工作树中运行，避免与其他会话互相覆盖修改
+    let isolated = isolated.unwrap_or(false);
+    let mut workspace = None;
+    if isolated {
+        let work_dir = config.work_dir.clone()
+            .ok_or_else(|| AppError::ConfigError("未设置工作目录".to_string()))?;
+        let worktree = worktrees(&state)?.create(&work_dir, &session_id)?;
+        config.work_dir = Some(worktree.path.into());
+        workspace = Some(worktree.work_dir);
     }
 
-    
-
-    // 在后台线程中读取输出
-    std::thread::spawn(move || {
-        eprintln!("[start_chat] 后台线程开始");
-        session.read_events(move |event| {
-            // 发送事件到前端 - 直接序列化为 JSON 字符串
-            let event_json = serde_json::to_string(&event)
-                .unwrap_or_else(|_| "{}".to_string());
-            eprintln!("[start_chat] 发送事件: {}", event_json);
-            let _ = window_clone.emit("chat-event", event_json);
-        });
-        
-        eprintln!("[start_chat] 后台线程结束");
-    });
+    let request = RunRequest {
+        session_id: session_id.clone(),
+        message,
+        resume: false,
+        config,
+        recordings_dir,
+        workspace,
+    };
+    if let Err(e) = submit_run(&window, request, priority.unwrap_or(0), "start_chat").await {
+        // 会话没有启动，工作树和分支不会再被使用
+        if isolated {
+            if let Err(discard_error) = worktrees(&state).and_then(|manager| manager.discard(&session_id)) {
+                eprintln!("[start_chat] 删除会话 {} 的工作树失败: {}", session_id, discard_error);
+            }
+        }
+        return Err(e);
+    }
 
     Ok(session_id)
 }
@@ -225,6 +870,7 @@ pub async fn start_chat(
 pub async fn continue_chat(
     session_id: String,
     message: String,
+    priority: Option<i32>,
     window: Window,
     state: tauri::State<'_, crate::AppState>,
 ) -> Result<()> {
@@ -232,96 +878,98 @@ pub async fn continue_chat(
     eprintln!("[continue_chat] 消息: {}", message);
 
     // 从 AppState 获取实际配置
-    let config_store = state.config_store.lock()
-        .map_err(|e| crate::error::AppError::Unknown(e.to_string()))?;
-    let config = config_store.get().clone();
-
-    // 使用 Claude CLI 原生的 --resume 参数恢复会话
-    eprintln!("[continue_chat] 使用 --resume 参数恢复会话");
-
-    // 在 Windows 上，.cmd 文件需要通过 cmd.exe 执行
-    #[cfg(windows)]
-    let mut cmd = Command::new("cmd");
-    #[cfg(windows)]
-    cmd.args([
-        "/c",
-        &config.claude_cmd,
-        "--resume",            // ✅ 使用真正的会话恢复
-        &session_id,          // ✅ 传递会话ID
-        "--print",
-        "--verbose",
-        "--output-format", "stream-json",
-        "--permission-mode", &config.permission_mode,
-        &message,
-    ]);
+    let (mut config, recordings_dir) = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| crate::error::AppError::Unknown(e.to_string()))?;
+        (config_store.get().clone(), config_store.recordings_dir().ok())
+    };
 
-    #[cfg(not(windows))]
-    let mut cmd = Command::new(&config.claude_cmd);
-    #[cfg(not(windows))]
-    cmd.args([
-        "--resume",            // ✅ 使用真正的会话恢复
-        &session_id,          // ✅ 传递会话ID
-        "--print",
-        "--verbose",
-        "--output-format", "stream-json",
-        "--permission-mode", &config.permission_mode,
-        &message,
-    ]);
-
-    cmd.stdout(Stdio::piped())
-        .stderr(Stdio::piped());
+    // 隔离会话继续在自己的工作树中运行（session_id 通常是 Claude 会话 ID，按别名查找），
+    // 并与原工作目录共用工作区并发名额
+    let mut workspace = None;
+    if let Some(worktree) = worktrees(&state)?.get(&session_id)? {
+        config.work_dir = Some(worktree.path.into());
+        workspace = Some(if worktree.work_dir.is_empty() { worktree.repo_path } else { worktree.work_dir });
+    }
 
-    // Windows 上隐藏 CMD 窗口
-    #[cfg(windows)]
-    cmd.creation_flags(CREATE_NO_WINDOW);
+    let request = RunRequest {
+        session_id,
+        message,
+        resume: true,
+        config,
+        recordings_dir,
+        workspace,
+    };
+    submit_run(&window, request, priority.unwrap_or(0), "continue_chat").await
+}
 
-    // 设置工作目录
-    if let Some(ref work_dir) = config.work_dir {
-        eprintln!("[continue_chat] work_dir: {:?}", work_dir);
-        cmd.current_dir(work_dir);
+/// 删除会话的后端状态，同时回收其工作树
+#[tauri::command]
+pub fn delete_session(session_id: String, state: tauri::State<'_, crate::AppState>) -> Result<()> {
+    ensure_idle(&state, &session_id)?;
+    if let Ok(mut scheduler) = state.scheduler.lock() {
+        scheduler.cancel(&session_id);
     }
-
-    // 设置 Git Bash 环境变量 (Windows 需要)
-    if let Some(ref git_bash_path) = config.git_bin_path {
-        eprintln!("[continue_chat] 设置 CLAUDE_CODE_GIT_BASH_PATH: {}", git_bash_path);
-        cmd.env("CLAUDE_CODE_GIT_BASH_PATH", git_bash_path);
+    if let Ok(mut deliveries) = state.deliveries.lock() {
+        deliveries.remove(&session_id);
+    }
+    if let Ok(mut parse_stats) = state.parse_stats.lock() {
+        parse_stats.remove(&session_id);
+    }
+    if let Ok(mut tracker) = state.shell_tracker.lock() {
+        tracker.remove_session(&session_id);
     }
 
-    eprintln!("[continue_chat] 执行命令: {:?}", cmd);
-
-    let child = cmd.spawn()
-        .map_err(|e| AppError::ProcessError(format!("继续 Claude 会话失败: {}", e)))?;
-
-    eprintln!("[continue_chat] 进程 PID: {:?}", child.id());
+    if worktrees(&state)?.discard(&session_id)? {
+        eprintln!("[delete_session] 已回收会话 {} 的工作树", session_id);
+    }
+    Ok(())
+}
 
-    let process_id = child.id();
-    let session = ChatSession::with_id_and_child(session_id.clone(), child);
-    let window_clone = window.clone();
+/// 获取会话调度状态（运行中与排队中的会话）
+#[tauri::command]
+pub fn get_run_queue(state: tauri::State<'_, crate::AppState>) -> Result<RunQueueStatus> {
+    let scheduler = state.scheduler.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    Ok(scheduler.status())
+}
 
-    eprintln!("[continue_chat] 会话 ID: {}, 进程 ID: {}", session_id, process_id);
+/// 获取会话的事件解析统计（未识别 / 解析失败的行数）
+#[tauri::command]
+pub fn get_parse_stats(
+    session_id: String,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<ParseStats> {
+    let stats = state.parse_stats.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    Ok(stats.get(&session_id).cloned().unwrap_or_default())
+}
 
-    // 将进程ID存储到全局sessions中
-    {
-        let mut sessions = state.sessions.lock()
-            .map_err(|e| crate::error::AppError::Unknown(e.to_string()))?;
-        sessions.insert(session_id.clone(), process_id);
-    }
-
-    // 在后台线程中读取输出
-    std::thread::spawn(move || {
-        eprintln!("[continue_chat] 后台线程开始");
-        session.read_events(move |event| {
-            // 发送事件到前端
-            let event_json = serde_json::to_string(&event)
-                .unwrap_or_else(|_| "{}".to_string());
-            eprintln!("[continue_chat] 发送事件: {}", event_json);
-            let _ = window_clone.emit("chat-event", event_json);
-        });
-        
-        eprintln!("[continue_chat] 后台线程结束");
-    });
+/// 获取被截断事件的完整内容（JSON 字符串）
+#[tauri::command]
+pub fn fetch_event_payload(
+    session_id: String,
+    seq: u64,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<String> {
+    let deliveries = state.deliveries.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    deliveries
+        .get(&session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.clone()))?
+        .payload(seq)
+        .ok_or_else(|| AppError::Unknown(format!("事件内容已过期: {}#{}", session_id, seq)))
+}
 
-    Ok(())
+/// 获取会话的事件推送统计
+#[tauri::command]
+pub fn get_delivery_metrics(
+    session_id: String,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<DeliveryMetrics> {
+    let deliveries = state.deliveries.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    Ok(deliveries.get(&session_id).map(DeliveryHandle::metrics).unwrap_or_default())
 }
 
 /// 中断聊天会话
@@ -331,65 +979,97 @@ pub async fn interrupt_chat(
     state: tauri::State<'_, crate::AppState>,
 ) -> Result<()> {
     eprintln!("[interrupt_chat] 中断会话: {}", session_id);
+
+    // 尚未启动的会话直接移出队列
+    {
+        let mut scheduler = state.scheduler.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        if scheduler.cancel(&session_id) {
+            eprintln!("[interrupt_chat] 已取消排队中的会话: {}", session_id);
+            return Ok(());
+        }
+    }
     
-    // 从sessions中获取并移除进程ID
-    let mut sessions = state.sessions.lock()
+    // 只触发中断令牌，读取任务终止进程后由 run_session 移除记录（关闭应用时据此等待清理完成）
+    let sessions = state.sessions.lock()
         .map_err(|e| crate::error::AppError::Unknown(e.to_string()))?;
     
-    if let Some(process_id) = sessions.remove(&session_id) {
-        eprintln!("[interrupt_chat] 找到进程 PID: {}", process_id);
-        
-        // 使用系统API终止进程
-        #[cfg(windows)]
-        {
-            use std::process::Command;
-            let result = Command::new("taskkill")
-                .args(["/F", "/PID", &process_id.to_string()])
-                .output();
-                
-            match result {
-                Ok(output) => {
-                    if output.status.success() {
-                        eprintln!("[interrupt_chat] 成功终止进程");
-                    } else {
-                        eprintln!("[interrupt_chat] 终止进程失败: {}", String::from_utf8_lossy(&output.stderr));
-                        return Err(AppError::ProcessError(format!("无法终止进程: {}", String::from_utf8_lossy(&output.stderr))));
-                    }
-                }
-                Err(e) => {
-                    eprintln!("[interrupt_chat] 执行taskkill命令失败: {}", e);
-                    return Err(AppError::ProcessError(format!("无法终止进程: {}", e)));
-                }
-            }
-        }
-        
-        #[cfg(not(windows))]
-        {
-            use std::process::Command;
-            let result = Command::new("kill")
-                .arg("-9")
-                .arg(process_id.to_string())
-                .output();
-                
-            match result {
-                Ok(output) => {
-                    if output.status.success() {
-                        eprintln!("[interrupt_chat] 成功终止进程");
-                    } else {
-                        eprintln!("[interrupt_chat] 终止进程失败: {}", String::from_utf8_lossy(&output.stderr));
-                        return Err(AppError::ProcessError(format!("无法终止进程: {}", String::from_utf8_lossy(&output.stderr))));
-                    }
-                }
-                Err(e) => {
-                    eprintln!("[interrupt_chat] 执行kill命令失败: {}", e);
-                    return Err(AppError::ProcessError(format!("无法终止进程: {}", e)));
-                }
-            }
-        }
+    if let Some(cancel) = sessions.get(&session_id) {
+        cancel.cancel();
     } else {
         eprintln!("[interrupt_chat] 未找到会话: {}", session_id);
         return Err(AppError::ProcessError(format!("未找到会话: {}", session_id)));
     }
-    
+
     Ok(())
 }
+
+/// 回放录制的会话（不启动 CLI）
+///
+/// `name` 为录制 id 或文件名，只在录制目录中查找。
+#[tauri::command]
+pub async fn replay_session(
+    name: String,
+    speed: Option<f64>,
+    window: Window,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<String> {
+    eprintln!("[replay_session] 回放录制: {}", name);
+
+    let dir = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        config_store.recordings_dir()?
+    };
+    let recording = Recording::load(&recorder::resolve_recording(&dir, &name)?)?;
+    // 使用独立 id，避免与原会话共用推送序号和内容缓存
+    let session_id = format!("replay-{}", Uuid::new_v4());
+    let speed = match speed.unwrap_or(1.0) {
+        speed if speed == 0.0 => speed,
+        speed if speed.is_finite() && speed > 0.0 => speed.clamp(*REPLAY_SPEED_RANGE.start(), *REPLAY_SPEED_RANGE.end()),
+        speed => return Err(AppError::ConfigError(format!("无效的回放倍速: {}", speed))),
+    };
+    let forwarder = EventForwarder::open_replay(&window, &session_id)?;
+
+    tokio::spawn(async move {
+        ChatSession::replay(&recording, speed, move |event| forwarder.clone().forward(event)).await;
+    });
+
+    Ok(session_id)
+}
+
+/// 列出已录制的会话
+#[tauri::command]
+pub fn list_recordings(state: tauri::State<'_, crate::AppState>) -> Result<Vec<RecordingInfo>> {
+    let dir = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        config_store.recordings_dir()?
+    };
+    recorder::list_recordings(&dir)
+}
+
+/// 列出上次异常退出遗留的会话
+#[tauri::command]
+pub fn list_stale_sessions(state: tauri::State<'_, crate::AppState>) -> Result<Vec<StaleSession>> {
+    let dir = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        config_store.locks_dir()?
+    };
+    Ok(SessionLocks::new(dir).stale())
+}
+
+/// 清理遗留会话，`kill` 为 true 时终止仍在运行的进程
+#[tauri::command]
+pub fn cleanup_stale_sessions(
+    kill: bool,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<Vec<StaleSession>> {
+    let dir = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        config_store.locks_dir()?
+    };
+    Ok(SessionLocks::new(dir).cleanup(kill))
+}
@@ -1,5 +1,15 @@
 use crate::error::{AppError, Result};
-use std::path::Path;
+use crate::commands::file_ops::{record_op, record_write};
+use crate::commands::trash::move_to_trash;
+use crate::models::file::{FileContent, FilePreview, FileVersion, FsChangeBatch, ReadRange};
+use crate::models::file_ops::FileOpKind;
+use crate::services::file_index::{FileIndex, IndexMatch};
+use crate::services::file_io::{mtime_ms, read_document, write_checked};
+use crate::services::preview::{render_preview, DEFAULT_PREVIEW_DIM, MAX_PREVIEW_DIM, MIN_PREVIEW_DIM};
+use crate::services::file_ops::{created_root, MAX_CONTENT_BYTES};
+use crate::services::sandbox::{validate_file_name, WorkspaceSandbox};
+use crate::services::walker::WalkRules;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::time::SystemTime;
 
@@ -12,6 +22,9 @@ pub struct FileMatch {
     pub full_path: String,
     pub is_dir: bool,
     pub extension: Option<String>,
+    pub score: i64,
+    /// `relative_path` 中命中的区间 `[start, end)`（UTF-16 偏移）
+    pub highlights: Vec<[usize; 2]>,
 }
 
 /// 命令文件结构（从 .claude/commands/ 读取）
@@ -41,12 +54,39 @@ pub struct FileInfo {
     pub modified: Option<String>,
     pub extension: Option<String>,
     pub children: Option<Vec<FileInfo>>,
+    /// 被 .gitignore 等规则或排除列表忽略（列表中仍显示，用于标记）
+    pub ignored: bool,
+}
+
+/// 当前工作区的沙箱（工作目录 + 额外允许的目录）
+pub(crate) fn sandbox(state: &crate::AppState) -> Result<WorkspaceSandbox> {
+    let config_store = state.config_store.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    WorkspaceSandbox::from_config(config_store.get())
+}
+
+/// 校验路径位于工作区沙箱内，返回传入的路径本身
+///
+/// 不替换为规范化后的路径，返回给前端的路径才与其目录树一致（例如工作目录位于符号链接下）。
+fn checked_path(state: &crate::AppState, path: &str) -> Result<PathBuf> {
+    let resolved = sandbox(state)?.resolve(path)?;
+    Ok(if Path::new(path).is_absolute() { PathBuf::from(path) } else { resolved })
+}
+
+/// 工作区遍历规则：排除模式相对工作目录匹配，未设置工作目录时相对 `fallback_root`
+fn walk_rules(state: &crate::AppState, fallback_root: &Path) -> Result<WalkRules> {
+    let config_store = state.config_store.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    let config = config_store.get();
+    let root = config.work_dir.as_deref().unwrap_or(fallback_root);
+    Ok(WalkRules::new(root, &config.file_excludes))
 }
 
 /// 读取目录内容（只读取直接子项，不递归）
 #[tauri::command]
-pub async fn read_directory(path: String) -> Result<Vec<FileInfo>> {
-    let path_obj = Path::new(&path);
+pub async fn read_directory(path: String, state: tauri::State<'_, crate::AppState>) -> Result<Vec<FileInfo>> {
+    let checked = checked_path(&state, &path)?;
+    let path_obj = checked.as_path();
     
     if !path_obj.exists() {
         return Err(AppError::InvalidPath("路径不存在".to_string()));
@@ -57,6 +97,7 @@ pub async fn read_directory(path: String) -> Result<Vec<FileInfo>> {
     }
     
     let mut files = Vec::new();
+    let visible = walk_rules(&state, path_obj)?.visible_children(path_obj);
     
     let entries = fs::read_dir(path_obj)?;
     
@@ -88,6 +129,8 @@ pub async fn read_directory(path: String) -> Result<Vec<FileInfo>> {
             None
         };
         
+        let ignored = !visible.contains(&file_path);
+        
         let file_info = FileInfo {
             name,
             path: file_path.to_string_lossy().to_string(),
@@ -96,6 +139,7 @@ pub async fn read_directory(path: String) -> Result<Vec<FileInfo>> {
             modified,
             extension,
             children: None, // 子目录内容预留，需要懒加载
+            ignored,
         };
         
         files.push(file_info);
@@ -113,10 +157,18 @@ pub async fn read_directory(path: String) -> Result<Vec<FileInfo>> {
     Ok(files)
 }
 
-/// 获取文件内容（限制大小）
+/// 获取文件内容，同时返回用于保存时检测冲突的版本
+///
+/// 自动检测编码（UTF-8/UTF-16/GBK/Shift_JIS 等）和二进制文件（返回十六进制预览）。
+/// 不传 `range` 时完整读取，超过 10MB 的文件需按字节或行范围分段读取。
 #[tauri::command]
-pub async fn get_file_content(path: String) -> Result<String> {
-    let path_obj = Path::new(&path);
+pub async fn get_file_content(
+    path: String,
+    range: Option<ReadRange>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<FileContent> {
+    let resolved = sandbox(&state)?.resolve(&path)?;
+    let path_obj = resolved.as_path();
     
     if !path_obj.exists() {
         return Err(AppError::InvalidPath("文件不存在".to_string()));
@@ -126,22 +178,93 @@ pub async fn get_file_content(path: String) -> Result<String> {
         return Err(AppError::InvalidPath("是目录，不是文件".to_string()));
     }
     
-    // 检查文件大小，限制为1MB
-    let metadata = fs::metadata(path_obj)?;
+    read_document(path_obj, range.as_ref())
+}
+
+/// 获取图片预览：MIME 类型、原图尺寸和缩略图（PNG/JPEG/GIF/WebP/SVG）
+///
+/// 缩略图最长边不超过 `max_dim`（默认 256，范围 16~2048），不会返回原始分辨率的大图。
+/// 结果按路径和修改时间缓存。
+#[tauri::command]
+pub async fn get_file_preview(
+    path: String,
+    max_dim: Option<u32>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<FilePreview> {
+    let resolved = sandbox(&state)?.resolve(&path)?;
+    if !resolved.is_file() {
+        return Err(AppError::InvalidPath("文件不存在".to_string()));
+    }
+    let max_dim = max_dim.unwrap_or(DEFAULT_PREVIEW_DIM).clamp(MIN_PREVIEW_DIM, MAX_PREVIEW_DIM);
+    let mtime = mtime_ms(&resolved)?;
+
+    let cached = state.preview_cache.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?
+        .get(&resolved, max_dim, mtime);
+    if let Some(preview) = cached {
+        return Ok(preview);
+    }
+
+    let target = resolved.clone();
+    let preview = tokio::task::spawn_blocking(move || render_preview(&target, max_dim))
+        .await
+        .map_err(|e| AppError::Unknown(e.to_string()))??;
+    eprintln!("[get_file_preview] {:?}: {}x{} -> {}x{}", resolved, preview.width, preview.height,
+              preview.thumbnail_width, preview.thumbnail_height);
+
+    state.preview_cache.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?
+        .insert(resolved, max_dim, mtime, preview.clone());
+    Ok(preview)
+}
+
+/// 保存文件（原子写入）
+///
+/// 传入读取时的 `expected_hash` 或 `expected_mtime`，磁盘上的文件已被修改（例如 Claude
+/// 刚编辑过）时返回冲突错误并携带当前版本，不会覆盖。返回写入后的新版本。
+#[tauri::command]
+pub async fn write_file(
+    path: String,
+    content: String,
+    expected_hash: Option<String>,
+    expected_mtime: Option<u64>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<FileVersion> {
+    let resolved = sandbox(&state)?.resolve(&path)?;
+    let path_obj = resolved.as_path();
     
-    if metadata.len() > 1024 * 1024 {
-        return Err(AppError::InvalidPath("文件过大，超过1MB限制".to_string()));
+    if path_obj.is_dir() {
+        return Err(AppError::InvalidPath("是目录，不是文件".to_string()));
     }
     
-    let content = fs::read_to_string(path_obj)?;
+    let created = created_root(path_obj);
+    // 给出期望版本时文件应当已存在，父目录缺失由 write_checked 报告冲突，不能先创建目录
+    if expected_hash.is_none() && expected_mtime.is_none() {
+        if let Some(parent) = path_obj.parent() {
+            fs::create_dir_all(parent)?;
+        }
+    }
     
-    Ok(content)
+    let outcome = write_checked(path_obj, &content, expected_hash.as_deref(), expected_mtime)?;
+    record_write(&state, path_obj, created, outcome.previous.as_deref(), &outcome.written);
+    
+    Ok(outcome.version)
 }
 
-/// 创建文件
+/// 创建文件（已存在时覆盖，覆盖前的内容记入操作日志以便撤销）
 #[tauri::command]
-pub async fn create_file(path: String, content: Option<String>) -> Result<()> {
-    let path_obj = Path::new(&path);
+pub async fn create_file(
+    path: String,
+    content: Option<String>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<()> {
+    let resolved = sandbox(&state)?.resolve(&path)?;
+    let path_obj = resolved.as_path();
+    let created = created_root(path_obj);
+    let previous = match fs::metadata(path_obj) {
+        Ok(metadata) if metadata.is_file() && metadata.len() <= MAX_CONTENT_BYTES => fs::read(path_obj).ok(),
+        _ => None,
+    };
     
     // 检查父目录是否存在
     if let Some(parent) = path_obj.parent() {
@@ -151,79 +274,127 @@ pub async fn create_file(path: String, content: Option<String>) -> Result<()> {
     }
     
     // 创建文件
-    if let Some(content) = content {
-        fs::write(path_obj, content)?;
-    } else {
-        fs::File::create(path_obj)?;
-    }
+    let content = content.unwrap_or_default();
+    fs::write(path_obj, &content)?;
+    
+    record_write(&state, path_obj, created, previous.as_deref(), content.as_bytes());
     
     Ok(())
 }
 
 /// 创建目录
 #[tauri::command]
-pub async fn create_directory(path: String) -> Result<()> {
-    fs::create_dir_all(&path)?;
+pub async fn create_directory(path: String, state: tauri::State<'_, crate::AppState>) -> Result<()> {
+    let resolved = sandbox(&state)?.resolve(&path)?;
+    let created = created_root(&resolved);
+    fs::create_dir_all(&resolved)?;
+    
+    if let Some(created) = created {
+        let op = FileOpKind::Create {
+            path: created.to_string_lossy().to_string(),
+            is_dir: true,
+            trash_id: None,
+        };
+        record_op(&state, op, None);
+    }
     
     Ok(())
 }
 
-/// 删除文件或目录
+/// 删除文件或目录（移入回收站，可通过 `restore_from_trash` 恢复）
 #[tauri::command]
-pub async fn delete_file(path: String) -> Result<()> {
-    let path_obj = Path::new(&path);
+pub async fn delete_file(path: String, state: tauri::State<'_, crate::AppState>) -> Result<()> {
+    // 不跟随符号链接：删除链接本身而不是其目标
+    let resolved = sandbox(&state)?.resolve_entry(&path)?;
     
-    if !path_obj.exists() {
+    if resolved.symlink_metadata().is_err() {
         return Err(AppError::InvalidPath("路径不存在".to_string()));
     }
     
-    if path_obj.is_dir() {
-        fs::remove_dir_all(path_obj)?;
-    } else {
-        fs::remove_file(path_obj)?;
-    }
+    let item = move_to_trash(&state, &resolved)?;
+    record_op(&state, FileOpKind::Delete { path: item.original_path, trash_id: item.id }, None);
     
     Ok(())
 }
 
 /// 重命名文件或目录
 #[tauri::command]
-pub async fn rename_file(old_path: String, new_name: String) -> Result<()> {
-    let old_path_obj = Path::new(&old_path);
+pub async fn rename_file(
+    old_path: String,
+    new_name: String,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<()> {
+    // 新名称只能是同一目录下的文件名
+    validate_file_name(&new_name)?;
+
+    let resolved = sandbox(&state)?.resolve_entry(&old_path)?;
+    let old_path_obj = resolved.as_path();
     
-    if !old_path_obj.exists() {
+    if old_path_obj.symlink_metadata().is_err() {
         return Err(AppError::InvalidPath("文件不存在".to_string()));
     }
     
     // 构建新路径
-    let new_path = if let Some(parent) = old_path_obj.parent() {
-        parent.join(&new_name)
-    } else {
-        Path::new(&new_name).to_path_buf()
+    let new_path = match old_path_obj.parent() {
+        Some(parent) => parent.join(&new_name),
+        None => return Err(AppError::PermissionDenied("不允许重命名根目录".to_string())),
     };
+
+    // 目标已存在时拒绝，否则会被覆盖且撤销后无法恢复（只改大小写的同一文件除外）
+    if new_path.symlink_metadata().is_ok() && !same_entry(old_path_obj, &new_path) {
+        return Err(AppError::InvalidPath(format!("目标位置已存在: {}", new_name)));
+    }
     
     fs::rename(old_path_obj, &new_path)?;
     
+    let op = FileOpKind::Rename {
+        from: old_path_obj.to_string_lossy().to_string(),
+        to: new_path.to_string_lossy().to_string(),
+    };
+    record_op(&state, op, None);
+    
     Ok(())
 }
 
-/// 检查路径是否存在
+/// 两个路径是否是同一个目录项（大小写不敏感的文件系统上只改大小写的重命名）
+fn same_entry(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (a.symlink_metadata(), b.symlink_metadata()) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        a.to_string_lossy().eq_ignore_ascii_case(&b.to_string_lossy())
+    }
+}
+
+/// 检查工作区内的路径是否存在
 #[tauri::command]
-pub async fn path_exists(path: String) -> Result<bool> {
-    Ok(Path::new(&path).exists())
+pub async fn path_exists(path: String, state: tauri::State<'_, crate::AppState>) -> Result<bool> {
+    Ok(sandbox(&state)?.resolve(&path)?.exists())
 }
 
 /// 读取工作区中的自定义命令
-/// 从 .claude/commands/ 目录读取 .md 文件
+/// 从 .claude/commands/ 目录读取 .md 文件（`work_dir` 为空时使用当前工作目录）
 #[tauri::command]
-pub async fn read_commands(work_dir: Option<String>) -> Result<Vec<CommandFile>> {
+pub async fn read_commands(
+    work_dir: Option<String>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<Vec<CommandFile>> {
     let mut commands = Vec::new();
 
-    let work_path = work_dir.unwrap_or_else(|| String::from("."));
-    let base_path = Path::new(&work_path);
-
     // 构建 .claude/commands/ 路径
-    let commands_dir = base_path.join(".claude").join("commands");
+    let relative = Path::new(".claude").join("commands");
+    let commands_dir = match work_dir {
+        Some(work_dir) => Path::new(&work_dir).join(relative),
+        None => relative,
+    };
+    let commands_dir = sandbox(&state)?.resolve(&commands_dir.to_string_lossy())?;
 
     if !commands_dir.exists() {
         return Ok(commands);
@@ -342,120 +513,86 @@ fn parse_simple_params(params_str: &str) -> Vec<CommandParam> {
 }
 
 /// 搜索文件（用于 @file 引用）
-/// 支持模糊匹配文件名，并返回相对路径
+/// fzf 风格的模糊匹配（路径分段边界、文件名和 Agent 最近编辑的文件优先），返回相对路径和命中区间
+///
+/// 使用内存索引（遵循 .gitignore / .ignore / 全局排除规则和配置中的 `file_excludes`），
+/// 当前工作目录的索引随文件监听增量更新，其他目录的索引过期后重建。
 #[tauri::command]
 pub async fn search_files(
     work_dir: String,
     query: String,
-    max_results: Option<usize>
+    max_results: Option<usize>,
+    state: tauri::State<'_, crate::AppState>,
 ) -> Result<Vec<FileMatch>> {
-    let base_path = Path::new(&work_dir);
+    let checked = checked_path(&state, &work_dir)?;
+    let base_path = checked.as_path();
     let max_results = max_results.unwrap_or(20);
 
     if !base_path.exists() {
         return Ok(Vec::new());
     }
 
-    let query_lower = query.to_lowercase();
-    let mut results = Vec::new();
-
-    // 解析查询：可能是 "path/file" 格式
-    let query_parts: Vec<String> = query_lower.split('/').map(|s| s.to_string()).collect();
-    let name_query = query_parts.last().map(|s| s.as_str()).unwrap_or(&query_lower);
-    let path_filters: Vec<String> = if query_parts.len() > 1 {
-        query_parts[..query_parts.len() - 1].to_vec()
-    } else {
-        Vec::new()
-    };
-
-    // 递归搜索
-    search_recursive(
-        base_path,
-        base_path,
-        name_query,
-        &path_filters.iter().map(|s| s.as_str()).collect::<Vec<_>>(),
-        0,
-        &mut results,
-        max_results,
-    )?;
-
-    Ok(results)
-}
-
-/// 递归搜索文件
-fn search_recursive(
-    base_path: &Path,
-    current_path: &Path,
-    name_query: &str,
-    path_filters: &[&str],
-    depth: usize,
-    results: &mut Vec<FileMatch>,
-    max_results: usize,
-) -> Result<()> {
-    // 达到最大结果数或深度限制
-    if results.len() >= max_results || depth > 5 {
-        return Ok(());
-    }
-
-    let entries = fs::read_dir(current_path)?;
-
-    for entry in entries {
-        if results.len() >= max_results {
-            break;
+    let matches = search_index(&state, base_path, &query, max_results).await?;
+    Ok(matches.into_iter().map(|m| {
+        let path = base_path.join(&m.path);
+        FileMatch {
+            name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            extension: path.extension().map(|e| e.to_string_lossy().to_lowercase()),
+            full_path: path.to_string_lossy().to_string(),
+            relative_path: m.path,
+            is_dir: false,
+            score: m.score,
+            highlights: m.highlights,
         }
+    }).collect())
+}
 
-        let entry = entry?;
-        let path = entry.path();
-        let name = path.file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("");
-
-        // 跳过隐藏文件和特殊目录
-        if name.starts_with('.') || name == "node_modules" || name == "target" {
-            continue;
+/// 在 `root` 的索引中搜索，索引不存在或已过期时重建
+///
+/// 重建在阻塞线程中进行且不持有索引锁，文件监听的增量更新不会被阻塞，
+/// 期间的变更由 [`IndexCache`](crate::services::file_index::IndexCache) 暂存后补上。
+async fn search_index(state: &crate::AppState, root: &Path, query: &str, limit: usize) -> Result<Vec<IndexMatch>> {
+    let excludes = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        config_store.get().file_excludes.clone()
+    };
+    let watched_root = state.fs_watcher.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?
+        .as_ref()
+        .map(|watcher| watcher.root().to_path_buf());
+    let watched = watched_root.as_deref() == Some(root);
+    let recent = state.recent_edits.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?
+        .bonuses();
+
+    {
+        let mut cache = state.file_index.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        if let Some(index) = cache.get_fresh(root, &excludes, watched) {
+            return Ok(index.search(query, limit, &recent));
         }
+        cache.begin_build(root);
+    }
 
-        let is_dir = path.is_dir();
-        let name_lower = name.to_lowercase();
-
-        // 计算相对路径
-        let relative_path = pathdiff::diff_paths(&path, base_path)
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| path.to_string_lossy().to_string());
-
-        // 检查路径过滤器
-        let relative_path_lower = relative_path.to_lowercase();
-        let passes_path_filter = path_filters.is_empty()
-            || path_filters.iter().all(|filter| relative_path_lower.contains(filter));
-
-        // 如果是文件，检查名称匹配
-        if !is_dir && name_lower.contains(name_query) && passes_path_filter {
-            let extension = path.extension()
-                .and_then(|e| e.to_str())
-                .map(|s| s.to_lowercase());
-
-            results.push(FileMatch {
-                name: name.to_string(),
-                relative_path: relative_path.clone(),
-                full_path: path.to_string_lossy().to_string(),
-                is_dir: false,
-                extension,
-            });
-        }
+    let build_root = root.to_path_buf();
+    let built = tokio::task::spawn_blocking(move || FileIndex::build(&build_root, &excludes)).await;
 
-        // 如果是目录，递归搜索
-        if is_dir {
-            search_recursive(
-                base_path,
-                &path,
-                name_query,
-                path_filters,
-                depth + 1,
-                results,
-                max_results,
-            )?;
+    let mut cache = state.file_index.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    match built {
+        Ok(index) => Ok(cache.insert(index, watched_root.as_deref()).search(query, limit, &recent)),
+        Err(e) => {
+            cache.abort_build(root);
+            Err(AppError::Unknown(e.to_string()))
         }
     }
+}
 
-    Ok(())
-}
\ No newline at end of file
+/// 将文件监听的变更应用到对应目录的索引
+pub(crate) fn update_file_index(state: &crate::AppState, batch: &FsChangeBatch) {
+    match state.file_index.lock() {
+        Ok(mut cache) => cache.apply(Path::new(&batch.root), &batch.changes),
+        Err(e) => eprintln!("[update_file_index] 获取锁失败: {}", e),
+    }
+}
@@ -0,0 +1,85 @@
+use crate::commands::trash::trash;
+use crate::error::{AppError, Result};
+use crate::models::file_ops::{FileOp, FileOpHistory, FileOpKind};
+use crate::services::file_ops::{FileOpJournal, MAX_CONTENT_BYTES};
+use std::path::{Path, PathBuf};
+
+/// 在当前工作区的操作日志上执行操作
+fn with_journal<T>(
+    state: &crate::AppState,
+    f: impl FnOnce(&mut FileOpJournal) -> Result<T>,
+) -> Result<T> {
+    let (root, workspace) = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        let workspace = config_store.get().work_dir.clone()
+            .ok_or_else(|| AppError::ConfigError("未设置工作目录".to_string()))?;
+        (config_store.file_ops_dir()?, workspace)
+    };
+
+    let mut journals = state.file_ops.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    let journal = journals
+        .entry(workspace.clone())
+        .or_insert_with(|| FileOpJournal::open(&root, &workspace));
+    f(journal)
+}
+
+/// 记录文件浏览器的修改操作；记录失败只影响撤销，不影响操作本身
+pub(crate) fn record_op(state: &crate::AppState, op: FileOpKind, contents: Option<(&[u8], &[u8])>) {
+    if let Err(e) = with_journal(state, |journal| journal.record(op, contents)) {
+        eprintln!("[record_op] 记录文件操作失败: {}", e);
+    }
+}
+
+/// 记录文件写入：新建的路径记为创建，覆盖已有文件时记录前后内容
+pub(crate) fn record_write(
+    state: &crate::AppState,
+    path: &Path,
+    created: Option<PathBuf>,
+    previous: Option<&[u8]>,
+    written: &[u8],
+) {
+    if let Some(created) = created {
+        let op = FileOpKind::Create {
+            path: created.to_string_lossy().to_string(),
+            is_dir: created != path,
+            trash_id: None,
+        };
+        record_op(state, op, None);
+    } else if let Some(previous) = previous {
+        if previous.len() as u64 > MAX_CONTENT_BYTES || written.len() as u64 > MAX_CONTENT_BYTES {
+            eprintln!("[record_write] 文件过大，不记录撤销内容: {:?}", path);
+            return;
+        }
+        let op = FileOpKind::Write { path: path.to_string_lossy().to_string() };
+        record_op(state, op, Some((previous, written)));
+    }
+}
+
+/// 记录批量替换（`files` 为路径和替换前后的内容）；记录失败只影响撤销
+pub(crate) fn record_replace(state: &crate::AppState, files: &[(String, Vec<u8>, Vec<u8>)]) {
+    if let Err(e) = with_journal(state, |journal| journal.record_replace(files)) {
+        eprintln!("[record_replace] 记录替换操作失败: {}", e);
+    }
+}
+
+/// 获取当前工作区的撤销 / 重做栈
+#[tauri::command]
+pub fn list_file_ops(state: tauri::State<'_, crate::AppState>) -> Result<FileOpHistory> {
+    with_journal(&state, |journal| Ok(journal.history().clone()))
+}
+
+/// 撤销最近的文件操作，没有可撤销的操作时返回空
+#[tauri::command]
+pub fn undo_file_op(state: tauri::State<'_, crate::AppState>) -> Result<Option<FileOp>> {
+    let (trash, _, _) = trash(&state)?;
+    with_journal(&state, |journal| journal.undo(&trash))
+}
+
+/// 重做最近撤销的文件操作，没有可重做的操作时返回空
+#[tauri::command]
+pub fn redo_file_op(state: tauri::State<'_, crate::AppState>) -> Result<Option<FileOp>> {
+    let (trash, _, _) = trash(&state)?;
+    with_journal(&state, |journal| journal.redo(&trash))
+}
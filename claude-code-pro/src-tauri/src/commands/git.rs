@@ -0,0 +1,326 @@
+use crate::commands::chat::claude_command;
+use crate::error::{AppError, Result};
+use crate::models::config::Config;
+use crate::models::git::{
+    BranchInfo, CommitInfo, CommitPage, FileBlame, FileDiff, FileHistoryPage, GitStatus, MergeReport,
+    SessionWorktree, StashInfo,
+};
+use crate::services::git::GitRepo;
+use crate::services::scheduler::RunScheduler;
+use crate::services::worktree::WorktreeManager;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
+
+/// 默认每页提交数
+const DEFAULT_LOG_LIMIT: usize = 50;
+
+/// 生成提交说明时传给 Claude 的差异上限
+const DRAFT_MAX_DIFF_BYTES: usize = 60 * 1024;
+
+/// 生成提交说明的超时时间
+const DRAFT_TIMEOUT: Duration = Duration::from_secs(90);
+
+const DRAFT_PROMPT: &str = "Write a git commit message for the staged diff provided on stdin. \
+Use a concise imperative summary line under 72 characters, then a blank line and a short body \
+only if the change needs explanation. Match the language and style of typical commit messages. \
+Output only the commit message, without code fences or commentary.";
+
+/// 获取当前工作目录
+pub(crate) fn work_dir(state: &crate::AppState) -> Result<PathBuf> {
+    let config_store = state.config_store.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    config_store.get().work_dir.clone()
+        .ok_or_else(|| AppError::ConfigError("未设置工作目录".to_string()))
+}
+
+/// 打开工作目录所在的仓库
+pub(crate) fn open_repo(state: &crate::AppState) -> Result<GitRepo> {
+    GitRepo::open(&work_dir(state)?)
+}
+
+/// 会话工作树管理器
+pub(crate) fn worktrees(state: &crate::AppState) -> Result<WorktreeManager> {
+    let config_store = state.config_store.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    Ok(WorktreeManager::new(config_store.worktrees_dir()?))
+}
+
+/// 获取工作区 Git 状态
+#[tauri::command]
+pub fn git_status(state: tauri::State<'_, crate::AppState>) -> Result<GitStatus> {
+    open_repo(&state)?.status()
+}
+
+/// 获取差异
+///
+/// `staged` 为 true 时返回已暂存的差异，`path` 为空时返回整个工作区的差异。
+#[tauri::command]
+pub fn git_diff(
+    staged: bool,
+    path: Option<String>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<Vec<FileDiff>> {
+    open_repo(&state)?.diff(staged, path.as_deref())
+}
+
+/// 列出分支
+#[tauri::command]
+pub fn git_branches(state: tauri::State<'_, crate::AppState>) -> Result<Vec<BranchInfo>> {
+    open_repo(&state)?.branches()
+}
+
+/// 获取当前分支（分离 HEAD 时为空）
+#[tauri::command]
+pub fn git_current_branch(state: tauri::State<'_, crate::AppState>) -> Result<Option<String>> {
+    open_repo(&state)?.current_branch()
+}
+
+/// 获取提交历史
+#[tauri::command]
+pub fn git_log(
+    skip: Option<usize>,
+    limit: Option<usize>,
+    path: Option<String>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<CommitPage> {
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT).max(1);
+    open_repo(&state)?.log(skip.unwrap_or(0), limit, path.as_deref())
+}
+
+/// 逐行追溯文件（工作区版本，未提交的行单独标记）
+#[tauri::command]
+pub fn git_blame(path: String, state: tauri::State<'_, crate::AppState>) -> Result<FileBlame> {
+    open_repo(&state)?.blame(&path)
+}
+
+/// 获取修改过文件的提交（跟随重命名）
+#[tauri::command]
+pub fn git_file_history(
+    path: String,
+    skip: Option<usize>,
+    limit: Option<usize>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<FileHistoryPage> {
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT).max(1);
+    open_repo(&state)?.file_history(&path, skip.unwrap_or(0), limit)
+}
+
+/// 暂存文件或目录
+#[tauri::command]
+pub fn git_stage(paths: Vec<String>, state: tauri::State<'_, crate::AppState>) -> Result<()> {
+    open_repo(&state)?.stage(&paths)
+}
+
+/// 取消暂存文件或目录
+#[tauri::command]
+pub fn git_unstage(paths: Vec<String>, state: tauri::State<'_, crate::AppState>) -> Result<()> {
+    open_repo(&state)?.unstage(&paths)
+}
+
+/// 暂存单个差异块（序号来自 `git_diff(false, path)`）
+#[tauri::command]
+pub fn git_stage_hunk(
+    path: String,
+    hunk_index: usize,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<()> {
+    open_repo(&state)?.stage_hunk(&path, hunk_index)
+}
+
+/// 取消暂存单个差异块（序号来自 `git_diff(true, path)`）
+#[tauri::command]
+pub fn git_unstage_hunk(
+    path: String,
+    hunk_index: usize,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<()> {
+    open_repo(&state)?.unstage_hunk(&path, hunk_index)
+}
+
+/// 提交已暂存的变更
+#[tauri::command]
+pub fn git_commit(message: String, state: tauri::State<'_, crate::AppState>) -> Result<CommitInfo> {
+    open_repo(&state)?.commit(&message)
+}
+
+/// 创建分支
+#[tauri::command]
+pub fn git_create_branch(
+    name: String,
+    start_point: Option<String>,
+    checkout: bool,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<()> {
+    open_repo(&state)?.create_branch(&name, start_point.as_deref(), checkout)
+}
+
+/// 切换分支
+#[tauri::command]
+pub fn git_checkout_branch(name: String, state: tauri::State<'_, crate::AppState>) -> Result<()> {
+    open_repo(&state)?.checkout_branch(&name)
+}
+
+/// 储藏本地修改
+#[tauri::command]
+pub fn git_stash_save(
+    message: Option<String>,
+    include_untracked: bool,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<String> {
+    open_repo(&state)?.stash_save(message.as_deref(), include_untracked)
+}
+
+/// 储藏列表
+#[tauri::command]
+pub fn git_stash_list(state: tauri::State<'_, crate::AppState>) -> Result<Vec<StashInfo>> {
+    open_repo(&state)?.stash_list()
+}
+
+/// 应用储藏，`pop` 为 true 时应用后删除
+#[tauri::command]
+pub fn git_stash_apply(index: usize, pop: bool, state: tauri::State<'_, crate::AppState>) -> Result<()> {
+    open_repo(&state)?.stash_apply(index, pop)
+}
+
+/// 删除储藏
+#[tauri::command]
+pub fn git_stash_drop(index: usize, state: tauri::State<'_, crate::AppState>) -> Result<()> {
+    open_repo(&state)?.stash_drop(index)
+}
+
+/// 根据已暂存的差异生成提交说明
+///
+/// 以非交互方式运行 `claude --print`，只返回建议的说明，不会提交。
+#[tauri::command]
+pub async fn draft_commit_message(state: tauri::State<'_, crate::AppState>) -> Result<String> {
+    let config = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        config_store.get().clone()
+    };
+    let work_dir = config.work_dir.clone()
+        .ok_or_else(|| AppError::ConfigError("未设置工作目录".to_string()))?;
+
+    let patch = GitRepo::open(&work_dir)?.staged_patch(DRAFT_MAX_DIFF_BYTES)?;
+    if patch.trim().is_empty() {
+        return Err(AppError::GitError("没有已暂存的变更".to_string()));
+    }
+
+    draft_message(&config, &patch).await
+}
+
+/// 调用 Claude 为差异生成提交说明
+///
+/// 差异是不可信内容（可能夹带指令），因此通过 `--tools ""` 不提供任何内置工具（新增工具也不例外）、
+/// 不加载 MCP 服务器，并且只运行一轮。
+pub async fn draft_message(config: &Config, patch: &str) -> Result<String> {
+    let mut cmd = claude_command(config);
+    // --tools 接受多个值，放在最后以免吞掉后面的参数
+    cmd.args(["--print", DRAFT_PROMPT, "--max-turns", "1", "--permission-mode", "default", "--strict-mcp-config"])
+        .args(["--tools", ""])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = cmd.spawn()
+        .map_err(|e| AppError::ProcessError(format!("启动 Claude 失败: {}", e)))?;
+
+    // 差异通过 stdin 传入，避免命令行长度限制；写入与读取输出并行，CLI 不读取时也受超时限制
+    let stdin = child.stdin.take();
+    let write = async move {
+        if let Some(mut stdin) = stdin {
+            stdin.write_all(patch.as_bytes()).await?;
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    let (written, output) = tokio::time::timeout(DRAFT_TIMEOUT, async {
+        tokio::join!(write, child.wait_with_output())
+    })
+    .await
+    .map_err(|_| AppError::Timeout)?;
+    let output = output?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::ProcessError(format!(
+            "生成提交说明失败 ({:?}): {}",
+            output.status.code(),
+            stderr.trim()
+        )));
+    }
+
+    written?;
+
+    let message = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if message.is_empty() {
+        return Err(AppError::ProcessError("Claude 未返回提交说明".to_string()));
+    }
+    Ok(message)
+}
+
+/// 列出会话工作树（同时回收目录已被删除的记录）
+#[tauri::command]
+pub fn list_session_worktrees(state: tauri::State<'_, crate::AppState>) -> Result<Vec<SessionWorktree>> {
+    let manager = worktrees(&state)?;
+    manager.gc();
+    Ok(manager.list())
+}
+
+/// 将会话工作树的修改合并回主工作区
+///
+/// 存在冲突时返回冲突列表，主工作区不受影响。
+#[tauri::command]
+pub fn merge_session_worktree(
+    session_id: String,
+    message: Option<String>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<MergeReport> {
+    ensure_idle(&state, &session_id)?;
+    worktrees(&state)?.merge(&session_id, message.as_deref())
+}
+
+/// 丢弃会话工作树及其分支
+#[tauri::command]
+pub fn discard_session_worktree(
+    session_id: String,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<bool> {
+    ensure_idle(&state, &session_id)?;
+    worktrees(&state)?.discard(&session_id)
+}
+
+/// 会话运行中时不允许操作其工作树
+pub(crate) fn ensure_idle(state: &crate::AppState, session_id: &str) -> Result<()> {
+    let manager = worktrees(state)?;
+    let sessions = state.sessions.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    let scheduler = state.scheduler.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    check_idle(&manager, &sessions, &scheduler, session_id)
+}
+
+/// 检查会话是否空闲
+///
+/// 会话 ID 可能是本地 ID 或 Claude 会话 ID，按工作树记录的全部 ID 检查运行中与排队中的会话。
+pub fn check_idle(
+    manager: &WorktreeManager,
+    sessions: &HashMap<String, CancellationToken>,
+    scheduler: &RunScheduler,
+    session_id: &str,
+) -> Result<()> {
+    let mut ids = vec![session_id.to_string()];
+    if let Some(worktree) = manager.get(session_id)? {
+        ids.push(worktree.session_id);
+        ids.extend(worktree.aliases);
+    }
+
+    if let Some(id) = ids.iter().find(|id| sessions.contains_key(id.as_str()) || scheduler.is_queued(id)) {
+        return Err(AppError::ProcessError(format!("会话正在运行: {}", id)));
+    }
+    Ok(())
+}
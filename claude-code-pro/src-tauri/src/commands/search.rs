@@ -0,0 +1,255 @@
+use crate::commands::file_explorer::sandbox;
+use crate::commands::file_ops::record_replace;
+use crate::error::{AppError, Result};
+use crate::models::file::FileVersion;
+use crate::models::search::{
+    ContentSearchEvent, ContentSearchOptions, ContentSearchSummary, ReplaceApplied, ReplacePreview,
+    ReplacePreviewResult, ReplaceResult, ReplaceSkip, ReplaceTarget,
+};
+use crate::services::content_search::{search_workspace, ContentMatcher, MAX_SEARCH_FILE_BYTES};
+use crate::services::encoding::{decode, detect, is_binary};
+use crate::services::file_io::{read_with_version, write_checked};
+use crate::services::git::text_diff;
+use crate::services::sandbox::WorkspaceSandbox;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager, Window};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// 在工作目录中搜索文件内容
+///
+/// 立即返回搜索 ID，结果通过 `content-search` 事件分批推送，最后推送一次 `done`。
+/// 可用 `cancel_content_search` 取消。
+#[tauri::command]
+pub async fn search_content(
+    query: String,
+    options: Option<ContentSearchOptions>,
+    window: Window,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<String> {
+    let options = options.unwrap_or_default();
+    let matcher = ContentMatcher::new(&query, &options)?;
+    let (root, excludes) = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        let config = config_store.get();
+        let root = config.work_dir.clone()
+            .ok_or_else(|| AppError::ConfigError("未设置工作目录".to_string()))?;
+        (root, config.file_excludes.clone())
+    };
+
+    let search_id = Uuid::new_v4().to_string();
+    let cancel = CancellationToken::new();
+    state.content_searches.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?
+        .insert(search_id.clone(), cancel.clone());
+
+    eprintln!("[search_content] {} 开始搜索 {:?}: {:?}", search_id, root, query);
+    let id = search_id.clone();
+    let emit_window = window.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        search_workspace(&root, &matcher, &options, &excludes, &cancel, |matches| {
+            let _ = emit_window.emit("content-search", ContentSearchEvent::Matches {
+                search_id: id.clone(),
+                matches,
+            });
+        })
+    });
+
+    let id = search_id.clone();
+    tokio::spawn(async move {
+        match task.await {
+            Ok(summary) => {
+                eprintln!("[search_content] {} 结束: {} 个匹配，耗时 {}ms", id, summary.match_count, summary.elapsed_ms);
+                finish(&window, &id, summary, None);
+            }
+            Err(e) => {
+                eprintln!("[search_content] {} 搜索任务失败: {}", id, e);
+                finish(&window, &id, ContentSearchSummary::default(), Some(e.to_string()));
+            }
+        }
+    });
+    Ok(search_id)
+}
+
+/// 取消正在进行的内容搜索，返回搜索是否仍在进行
+#[tauri::command]
+pub fn cancel_content_search(search_id: String, state: tauri::State<'_, crate::AppState>) -> Result<bool> {
+    let searches = state.content_searches.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    match searches.get(&search_id) {
+        Some(cancel) => {
+            cancel.cancel();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 移除搜索令牌并推送结束事件
+fn finish(window: &Window, search_id: &str, summary: ContentSearchSummary, error: Option<String>) {
+    if let Ok(mut searches) = window.state::<crate::AppState>().content_searches.lock() {
+        searches.remove(search_id);
+    }
+    let _ = window.emit("content-search", ContentSearchEvent::Done {
+        search_id: search_id.to_string(),
+        summary,
+        error,
+    });
+}
+
+/// 预览替换：返回每个文件替换前后的差异，不写入
+///
+/// `targets` 通常来自搜索结果（可只选部分行）；正则模式下 `replacement` 中的 `$1`、`${name}`
+/// 展开为捕获组。无法替换的文件（不存在、二进制、没有匹配）放在 `skipped` 中。
+#[tauri::command]
+pub async fn preview_replace(
+    query: String,
+    replacement: String,
+    options: Option<ContentSearchOptions>,
+    targets: Vec<ReplaceTarget>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<ReplacePreviewResult> {
+    let options = options.unwrap_or_default();
+    let matcher = ContentMatcher::new(&query, &options)?;
+    let (sandbox, work_dir) = replace_context(&state)?;
+
+    let mut result = ReplacePreviewResult::default();
+    for target in &targets {
+        let preview = plan_replace(&sandbox, target, &matcher, &replacement, options.regex).and_then(|plan| {
+            let label = display_path(&plan.path, work_dir.as_deref());
+            let diff = text_diff(&label, plan.text.as_bytes(), plan.replaced.as_bytes()).map_err(|e| e.to_message())?;
+            Ok(ReplacePreview {
+                path: target.path.clone(),
+                version: plan.version,
+                replacements: plan.count,
+                diff,
+            })
+        });
+        match preview {
+            Ok(preview) => result.files.push(preview),
+            Err(reason) => result.skipped.push(ReplaceSkip { path: target.path.clone(), reason }),
+        }
+    }
+    Ok(result)
+}
+
+/// 应用替换：按预览时的版本原子写入各文件，整体记为一次可撤销的操作
+///
+/// 每个目标需带上预览返回的 `expectedHash`，文件在预览后被修改时跳过并在 `skipped` 中说明。
+#[tauri::command]
+pub async fn apply_replace(
+    query: String,
+    replacement: String,
+    options: Option<ContentSearchOptions>,
+    targets: Vec<ReplaceTarget>,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<ReplaceResult> {
+    let options = options.unwrap_or_default();
+    let matcher = ContentMatcher::new(&query, &options)?;
+    let (sandbox, _) = replace_context(&state)?;
+
+    let mut result = ReplaceResult::default();
+    let mut written = Vec::new();
+    for target in &targets {
+        let mut skip = |reason: &str| result.skipped.push(ReplaceSkip {
+            path: target.path.clone(),
+            reason: reason.to_string(),
+        });
+        let Some(expected) = target.expected_hash.as_deref() else {
+            skip("缺少预览时的版本");
+            continue;
+        };
+        let plan = match plan_replace(&sandbox, target, &matcher, &replacement, options.regex) {
+            Ok(plan) if plan.version.hash == expected => plan,
+            Ok(_) => {
+                skip("文件在预览后已被修改");
+                continue;
+            }
+            Err(reason) => {
+                skip(&reason);
+                continue;
+            }
+        };
+
+        match write_checked(&plan.path, &plan.replaced, Some(expected), None) {
+            Ok(outcome) => {
+                result.applied.push(ReplaceApplied {
+                    path: target.path.clone(),
+                    replacements: plan.count,
+                    version: outcome.version,
+                });
+                let previous = outcome.previous.unwrap_or(plan.original);
+                written.push((plan.path.to_string_lossy().to_string(), previous, outcome.written));
+            }
+            Err(AppError::FileConflict { .. }) => skip("文件在预览后已被修改"),
+            Err(e) => skip(&e.to_message()),
+        }
+    }
+
+    if !written.is_empty() {
+        record_replace(&state, &written);
+    }
+    eprintln!("[apply_replace] 已替换 {} 个文件，跳过 {} 个", result.applied.len(), result.skipped.len());
+    Ok(result)
+}
+
+/// 单个文件的替换计划
+struct ReplacePlan {
+    path: PathBuf,
+    original: Vec<u8>,
+    /// 解码后的原内容
+    text: String,
+    replaced: String,
+    version: FileVersion,
+    count: usize,
+}
+
+fn replace_context(state: &crate::AppState) -> Result<(WorkspaceSandbox, Option<PathBuf>)> {
+    let work_dir = state.config_store.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?
+        .get()
+        .work_dir
+        .clone();
+    Ok((sandbox(state)?, work_dir))
+}
+
+/// 读取文件并计算替换结果，不能替换时返回原因
+fn plan_replace(
+    sandbox: &WorkspaceSandbox,
+    target: &ReplaceTarget,
+    matcher: &ContentMatcher,
+    replacement: &str,
+    expand: bool,
+) -> std::result::Result<ReplacePlan, String> {
+    let path = sandbox.resolve(&target.path).map_err(|e| e.to_message())?;
+    let (original, version) = read_with_version(&path)
+        .map_err(|e| e.to_message())?
+        .ok_or_else(|| "文件不存在".to_string())?;
+    if original.len() as u64 > MAX_SEARCH_FILE_BYTES {
+        return Err("文件过大".to_string());
+    }
+    let detected = detect(&original, true);
+    if !detected.is_utf16() && is_binary(&original) {
+        return Err("二进制文件".to_string());
+    }
+    let content = decode(&original, detected);
+
+    let lines: Option<HashSet<usize>> = target.lines.as_ref().map(|lines| lines.iter().copied().collect());
+    let (replaced, count) = matcher.replace(&content, replacement, expand, lines.as_ref());
+    if count == 0 {
+        return Err("没有匹配".to_string());
+    }
+    let text = content.into_owned();
+    Ok(ReplacePlan { path, original, text, replaced, version, count })
+}
+
+/// 差异中显示的路径（工作目录内为相对路径）
+fn display_path(path: &Path, work_dir: Option<&Path>) -> String {
+    work_dir
+        .and_then(|dir| path.strip_prefix(dir).ok())
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
@@ -0,0 +1,13 @@
+use crate::error::{AppError, Result};
+use crate::models::shell::ShellJob;
+
+/// 列出会话中的 Bash 任务（含后台 shell），`shell-job` 事件只推送新增输出，累计输出从这里获取
+#[tauri::command]
+pub fn list_shell_jobs(
+    session_id: String,
+    state: tauri::State<crate::AppState>,
+) -> Result<Vec<ShellJob>> {
+    let tracker = state.shell_tracker.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    Ok(tracker.list(&session_id))
+}
@@ -0,0 +1,121 @@
+use crate::error::{AppError, Result};
+use crate::services::pty::{PtyEvent, TerminalInfo};
+use serde::Serialize;
+use tauri::{Emitter, Manager, Window};
+
+/// 终端输出事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalOutput {
+    terminal_id: String,
+    data: String,
+}
+
+/// 终端退出事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalExit {
+    terminal_id: String,
+    exit_code: Option<u32>,
+}
+
+/// 在当前工作目录中创建终端
+#[tauri::command]
+pub fn create_terminal(
+    name: Option<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    window: Window,
+    state: tauri::State<crate::AppState>,
+) -> Result<TerminalInfo> {
+    let work_dir = {
+        let store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        store.current_work_dir()
+    };
+
+    let mut pty = state.pty_manager.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+
+    let name = name.unwrap_or_else(|| format!("Terminal {}", pty.list(Some(&work_dir.to_string_lossy())).len() + 1));
+
+    pty.spawn(name, &work_dir, None, cols.unwrap_or(80), rows.unwrap_or(24), move |id, event| {
+        match event {
+            PtyEvent::Output(data) => {
+                let _ = window.emit("terminal-output", TerminalOutput {
+                    terminal_id: id.to_string(),
+                    data,
+                });
+            }
+            PtyEvent::Exit(exit_code) => {
+                if let Ok(mut pty) = window.state::<crate::AppState>().pty_manager.lock() {
+                    pty.forget(id);
+                }
+                let _ = window.emit("terminal-exit", TerminalExit {
+                    terminal_id: id.to_string(),
+                    exit_code,
+                });
+            }
+        }
+    })
+}
+
+/// 写入终端输入
+#[tauri::command]
+pub fn write_terminal(
+    terminal_id: String,
+    data: String,
+    state: tauri::State<crate::AppState>,
+) -> Result<()> {
+    // 写入可能阻塞（shell 未读取输入），不能持有管理器的锁，否则退出回调等操作会被卡住
+    let writer = {
+        let pty = state.pty_manager.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        pty.writer(&terminal_id)?
+    };
+    writer.write(data.as_bytes())
+}
+
+/// 调整终端尺寸
+#[tauri::command]
+pub fn resize_terminal(
+    terminal_id: String,
+    cols: u16,
+    rows: u16,
+    state: tauri::State<crate::AppState>,
+) -> Result<()> {
+    let mut pty = state.pty_manager.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    pty.resize(&terminal_id, cols, rows)
+}
+
+/// 关闭终端
+#[tauri::command]
+pub fn close_terminal(
+    terminal_id: String,
+    state: tauri::State<crate::AppState>,
+) -> Result<()> {
+    let mut pty = state.pty_manager.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    pty.close(&terminal_id)
+}
+
+/// 列出终端（默认只列出当前工作目录的终端）
+#[tauri::command]
+pub fn list_terminals(
+    work_dir: Option<String>,
+    state: tauri::State<crate::AppState>,
+) -> Result<Vec<TerminalInfo>> {
+    let work_dir = match work_dir {
+        Some(dir) => dir,
+        None => {
+            let store = state.config_store.lock()
+                .map_err(|e| AppError::Unknown(e.to_string()))?;
+            store.current_work_dir().to_string_lossy().to_string()
+        }
+    };
+
+    let pty = state.pty_manager.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    Ok(pty.list(Some(&work_dir)))
+}
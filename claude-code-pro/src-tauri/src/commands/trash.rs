@@ -0,0 +1,49 @@
+use crate::error::{AppError, Result};
+use crate::models::trash::TrashItem;
+use crate::services::sandbox::WorkspaceSandbox;
+use crate::services::trash::Trash;
+use std::path::Path;
+
+/// 回收站及配置中的清理上限（保留天数、总大小）
+pub(crate) fn trash(state: &crate::AppState) -> Result<(Trash, u64, u64)> {
+    let config_store = state.config_store.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    let config = config_store.get();
+    Ok((Trash::new(config_store.trash_dir()?), config.trash_max_age_days, config.trash_max_bytes))
+}
+
+/// 将已通过沙箱检查的路径移入回收站，并顺带清理过期条目
+pub(crate) fn move_to_trash(state: &crate::AppState, path: &Path) -> Result<TrashItem> {
+    let (trash, max_age_days, max_bytes) = trash(state)?;
+    let item = trash.move_to_trash(path)?;
+    trash.purge(max_age_days, max_bytes);
+    Ok(item)
+}
+
+/// 列出回收站条目（最近删除的在前），只清理超过保留天数的条目
+#[tauri::command]
+pub fn list_trash(state: tauri::State<'_, crate::AppState>) -> Result<Vec<TrashItem>> {
+    let (trash, max_age_days, _) = trash(&state)?;
+    trash.purge(max_age_days, 0);
+    Ok(trash.list())
+}
+
+/// 恢复到原路径（原路径必须位于当前工作区内且不存在）
+#[tauri::command]
+pub fn restore_from_trash(id: String, state: tauri::State<'_, crate::AppState>) -> Result<TrashItem> {
+    let (trash, _, _) = trash(&state)?;
+    let item = trash.get(&id)?;
+    {
+        let config_store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        WorkspaceSandbox::from_config(config_store.get())?.resolve_entry(&item.original_path)?;
+    }
+    trash.restore(&id)
+}
+
+/// 永久删除回收站条目，`ids` 为空时清空整个回收站
+#[tauri::command]
+pub fn empty_trash(ids: Option<Vec<String>>, state: tauri::State<'_, crate::AppState>) -> Result<usize> {
+    let (trash, _, _) = trash(&state)?;
+    trash.empty(ids.as_deref())
+}
@@ -1,5 +1,8 @@
 use crate::error::{AppError, Result};
+use crate::commands::file_explorer::update_file_index;
+use crate::services::fs_watcher::FsWatcher;
 use std::path::Path;
+use tauri::{Emitter, Manager};
 
 /// 验证工作区路径
 #[tauri::command]
@@ -24,6 +27,51 @@ pub fn validate_workspace_path(path: String) -> Result<bool> {
     Ok(true)
 }
 
+/// 让文件监听跟随当前工作目录：目录或排除规则变化时停止旧的监听并重新开始
+///
+/// 变更以 `fs-changed` 事件批量推送给前端。
+pub fn sync_fs_watcher(app: &tauri::AppHandle) -> Result<()> {
+    let state = app.state::<crate::AppState>();
+    let (work_dir, excludes) = {
+        let config_store = state.config_store.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        let config = config_store.get();
+        (config.work_dir.clone(), config.file_excludes.clone())
+    };
+
+    let mut watcher = state.fs_watcher.lock()
+        .map_err(|e| AppError::Unknown(e.to_string()))?;
+    if watcher.as_ref().map(|w| (w.root(), w.excludes())) == work_dir.as_deref().map(|dir| (dir, &excludes[..])) {
+        return Ok(());
+    }
+    if let Some(old) = watcher.take() {
+        old.stop();
+    }
+
+    let Some(root) = work_dir.filter(|dir| dir.is_dir()) else {
+        return Ok(());
+    };
+    let handle = app.clone();
+    *watcher = Some(FsWatcher::start(root, &excludes, move |batch| {
+        update_file_index(&handle.state::<crate::AppState>(), &batch);
+        if let Err(e) = handle.emit("fs-changed", batch) {
+            eprintln!("[sync_fs_watcher] 推送 fs-changed 失败: {}", e);
+        }
+    })?);
+    Ok(())
+}
+
+/// 停止文件监听（应用退出时调用）
+pub fn stop_fs_watcher(state: &crate::AppState) {
+    let watcher = match state.fs_watcher.lock() {
+        Ok(mut watcher) => watcher.take(),
+        Err(e) => e.into_inner().take(),
+    };
+    if let Some(watcher) = watcher {
+        watcher.stop();
+    }
+}
+
 /// 获取目录信息
 #[tauri::command]
 pub fn get_directory_info(path: String) -> Result<DirectoryInfo> {
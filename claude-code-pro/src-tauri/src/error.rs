@@ -1,3 +1,4 @@
+use crate::models::file::FileContent;
 use thiserror::Error;
 use tauri::ipc::InvokeError;
 
@@ -5,9 +6,19 @@ use tauri::ipc::InvokeError;
 pub type Result<T> = std::result::Result<T, AppError>;
 
 /// 将 AppError 转换为 Tauri InvokeError
+///
+/// 保存冲突返回结构化对象（携带磁盘上的当前版本），其余错误返回字符串。
 impl From<AppError> for InvokeError {
     fn from(error: AppError) -> Self {
-        InvokeError::from(error.to_message())
+        match &error {
+            AppError::FileConflict { path, current } => InvokeError(serde_json::json!({
+                "kind": "conflict",
+                "message": error.to_message(),
+                "path": path,
+                "current": current,
+            })),
+            _ => InvokeError::from(error.to_message()),
+        }
     }
 }
 
@@ -18,6 +29,13 @@ impl From<tauri::Error> for AppError {
     }
 }
 
+/// 将 git2 Error 转换为 AppError
+impl From<git2::Error> for AppError {
+    fn from(error: git2::Error) -> Self {
+        AppError::GitError(error.message().to_string())
+    }
+}
+
 /// 应用错误类型
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -57,6 +75,17 @@ pub enum AppError {
     #[error("Invalid path: {0}")]
     InvalidPath(String),
 
+    /// Git 操作错误
+    #[error("Git error: {0}")]
+    GitError(String),
+
+    /// 文件已在磁盘上被修改（`current` 为磁盘上的当前版本，文件已删除时为空）
+    #[error("File changed on disk: {path}")]
+    FileConflict {
+        path: String,
+        current: Option<Box<FileContent>>,
+    },
+
     /// 超时
     #[error("Operation timed out")]
     Timeout,
@@ -79,6 +108,8 @@ impl AppError {
             AppError::SessionNotFound(id) => format!("会话不存在: {}", id),
             AppError::PermissionDenied(e) => format!("权限被拒绝: {}", e),
             AppError::InvalidPath(path) => format!("无效路径: {}", path),
+            AppError::GitError(e) => format!("Git 错误: {}", e),
+            AppError::FileConflict { path, .. } => format!("文件已在磁盘上被修改: {}", path),
             AppError::Timeout => "操作超时".to_string(),
             AppError::Unknown(e) => format!("未知错误: {}", e),
         }
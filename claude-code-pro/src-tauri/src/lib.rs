@@ -1,30 +1,72 @@
-mod error;
-mod models;
-mod services;
-mod commands;
+pub mod error;
+pub mod models;
+pub mod services;
+pub mod commands;
 
 use error::Result;
 use models::config::{Config, HealthStatus};
+use models::events::ParseStats;
 use services::config_store::ConfigStore;
 use services::logger::Logger;
-use commands::chat::{start_chat, continue_chat, interrupt_chat};
+use services::shell_tracker::ShellTracker;
+use services::pty::PtyManager;
+use services::delivery::DeliveryHandle;
+use services::scheduler::RunScheduler;
+use services::file_ops::FileOpJournal;
+use services::fs_watcher::FsWatcher;
+use services::file_index::{IndexCache, RecentEdits};
+use services::preview::PreviewCache;
+use commands::chat::{
+    start_chat, continue_chat, interrupt_chat, replay_session, list_recordings, get_parse_stats,
+    fetch_event_payload, get_delivery_metrics, get_run_queue, list_stale_sessions,
+    cleanup_stale_sessions, delete_session, run_limits, shutdown_sessions, SHUTDOWN_TIMEOUT
+};
 use commands::{validate_workspace_path, get_directory_info};
+use commands::workspace::{sync_fs_watcher, stop_fs_watcher};
 use commands::file_explorer::{
-    read_directory, get_file_content, create_file, create_directory,
-    delete_file, rename_file, path_exists, read_commands, search_files
+    read_directory, get_file_content, write_file, create_file, create_directory,
+    delete_file, rename_file, path_exists, read_commands, search_files, get_file_preview
 };
+use commands::trash::{list_trash, restore_from_trash, empty_trash};
+use commands::file_ops::{list_file_ops, undo_file_op, redo_file_op};
+use commands::search::{search_content, cancel_content_search, preview_replace, apply_replace};
 use commands::logging::{
     get_log_dir, read_logs, clear_logs, open_log_dir,
     set_logging_enabled, is_logging_enabled
 };
+use commands::shell::list_shell_jobs;
+use commands::git::{
+    git_status, git_diff, git_branches, git_current_branch, git_log, git_blame, git_file_history, git_stage, git_unstage,
+    git_stage_hunk, git_unstage_hunk, git_commit, git_create_branch, git_checkout_branch,
+    git_stash_save, git_stash_list, git_stash_apply, git_stash_drop, draft_commit_message,
+    list_session_worktrees, merge_session_worktree, discard_session_worktree
+};
+use commands::terminal::{
+    create_terminal, write_terminal, resize_terminal, close_terminal, list_terminals
+};
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio_util::sync::CancellationToken;
 
 use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::Manager;
 
 /// 全局配置状态
 pub struct AppState {
     pub config_store: Mutex<ConfigStore>,
-    pub sessions: Mutex<HashMap<String, u32>>, // session_id -> process_id
+    pub sessions: Mutex<HashMap<String, CancellationToken>>, // session_id -> 中断令牌
+    pub shell_tracker: Mutex<ShellTracker>,
+    pub pty_manager: Mutex<PtyManager>,
+    pub parse_stats: Mutex<HashMap<String, ParseStats>>, // session_id -> 解析统计
+    pub deliveries: Mutex<HashMap<String, DeliveryHandle>>, // session_id -> 事件推送状态
+    pub scheduler: Mutex<RunScheduler>,
+    pub file_ops: Mutex<HashMap<PathBuf, FileOpJournal>>, // 工作目录 -> 文件操作日志
+    pub fs_watcher: Mutex<Option<FsWatcher>>, // 当前工作目录的文件监听
+    pub file_index: Mutex<IndexCache>, // 文件模糊搜索索引（按根目录）
+    pub recent_edits: Mutex<RecentEdits>, // Agent 最近编辑的文件
+    pub content_searches: Mutex<HashMap<String, CancellationToken>>, // search_id -> 取消令牌
+    pub preview_cache: Mutex<PreviewCache>, // 图片预览缓存
 }
 
 // ============================================================================
@@ -41,19 +83,56 @@ fn get_config(state: tauri::State<AppState>) -> Result<Config> {
 
 /// 更新配置
 #[tauri::command]
-fn update_config(config: Config, state: tauri::State<AppState>) -> Result<()> {
-    let mut store = state.config_store.lock()
+fn update_config(config: Config, app: tauri::AppHandle, state: tauri::State<AppState>) -> Result<()> {
+    let limits = run_limits(&config);
+    let previous = {
+        let mut store = state.config_store.lock()
+            .map_err(|e| error::AppError::Unknown(e.to_string()))?;
+        let previous = store.current_work_dir();
+        store.update(config)?;
+        previous
+    };
+    close_previous_terminals(&state, &previous)?;
+    if let Err(e) = sync_fs_watcher(&app) {
+        eprintln!("[update_config] 重启文件监听失败: {}", e);
+    }
+
+    // 放宽限制时立即启动排队中的会话
+    let mut scheduler = state.scheduler.lock()
         .map_err(|e| error::AppError::Unknown(e.to_string()))?;
-    store.update(config)
+    scheduler.set_limits(limits);
+    Ok(())
 }
 
 /// 设置工作目录
 #[tauri::command]
-fn set_work_dir(path: Option<String>, state: tauri::State<AppState>) -> Result<()> {
-    let mut store = state.config_store.lock()
-        .map_err(|e| error::AppError::Unknown(e.to_string()))?;
-    let path_buf = path.map(|p| p.into());
-    store.set_work_dir(path_buf)
+fn set_work_dir(path: Option<String>, app: tauri::AppHandle, state: tauri::State<AppState>) -> Result<()> {
+    let previous = {
+        let mut store = state.config_store.lock()
+            .map_err(|e| error::AppError::Unknown(e.to_string()))?;
+        let previous = store.current_work_dir();
+        let path_buf = path.map(|p| p.into());
+        store.set_work_dir(path_buf)?;
+        previous
+    };
+    close_previous_terminals(&state, &previous)?;
+    if let Err(e) = sync_fs_watcher(&app) {
+        eprintln!("[set_work_dir] 重启文件监听失败: {}", e);
+    }
+    Ok(())
+}
+
+/// 工作目录变化后关闭原工作目录下的终端
+fn close_previous_terminals(state: &AppState, previous: &std::path::Path) -> Result<()> {
+    let current = state.config_store.lock()
+        .map_err(|e| error::AppError::Unknown(e.to_string()))?
+        .current_work_dir();
+    if current != previous {
+        let mut pty_manager = state.pty_manager.lock()
+            .map_err(|e| error::AppError::Unknown(e.to_string()))?;
+        pty_manager.close_workspace(&previous.to_string_lossy());
+    }
+    Ok(())
 }
 
 /// 设置 Claude 命令路径
@@ -102,7 +181,8 @@ pub fn run() {
 
     // 根据配置初始化日志系统
     let logging_enabled = config_store.get().enable_logging;
-    let _logger_guard = Logger::init(logging_enabled);
+    let mut logger = Some(Logger::init(logging_enabled));
+    let initial_limits = run_limits(config_store.get());
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -110,6 +190,23 @@ pub fn run() {
         .manage(AppState {
             config_store: Mutex::new(config_store),
             sessions: Mutex::new(HashMap::new()),
+            shell_tracker: Mutex::new(ShellTracker::new()),
+            pty_manager: Mutex::new(PtyManager::new()),
+            parse_stats: Mutex::new(HashMap::new()),
+            deliveries: Mutex::new(HashMap::new()),
+            scheduler: Mutex::new(RunScheduler::new(initial_limits)),
+            file_ops: Mutex::new(HashMap::new()),
+            fs_watcher: Mutex::new(None),
+            file_index: Mutex::new(IndexCache::default()),
+            recent_edits: Mutex::new(RecentEdits::default()),
+            content_searches: Mutex::new(HashMap::new()),
+            preview_cache: Mutex::new(PreviewCache::default()),
+        })
+        .setup(|app| {
+            if let Err(e) = sync_fs_watcher(app.handle()) {
+                eprintln!("[setup] 启动文件监听失败: {}", e);
+            }
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             // 配置相关
@@ -125,12 +222,53 @@ pub fn run() {
             start_chat,
             continue_chat,
             interrupt_chat,
+            replay_session,
+            list_recordings,
+            get_parse_stats,
+            fetch_event_payload,
+            get_delivery_metrics,
+            get_run_queue,
+            list_stale_sessions,
+            cleanup_stale_sessions,
+            delete_session,
+            // Bash 任务相关
+            list_shell_jobs,
+            // 终端相关
+            create_terminal,
+            write_terminal,
+            resize_terminal,
+            close_terminal,
+            list_terminals,
+            // Git 相关
+            git_status,
+            git_diff,
+            git_branches,
+            git_current_branch,
+            git_log,
+            git_blame,
+            git_file_history,
+            git_stage,
+            git_unstage,
+            git_stage_hunk,
+            git_unstage_hunk,
+            git_commit,
+            git_create_branch,
+            git_checkout_branch,
+            git_stash_save,
+            git_stash_list,
+            git_stash_apply,
+            git_stash_drop,
+            draft_commit_message,
+            list_session_worktrees,
+            merge_session_worktree,
+            discard_session_worktree,
             // 工作区相关
             validate_workspace_path,
             get_directory_info,
             // 文件浏览器相关
             read_directory,
             get_file_content,
+            write_file,
             create_file,
             create_directory,
             delete_file,
@@ -138,6 +276,20 @@ pub fn run() {
             path_exists,
             read_commands,
             search_files,
+            get_file_preview,
+            // 内容搜索相关
+            search_content,
+            cancel_content_search,
+            preview_replace,
+            apply_replace,
+            // 回收站相关
+            list_trash,
+            restore_from_trash,
+            empty_trash,
+            // 文件操作撤销相关
+            list_file_ops,
+            undo_file_op,
+            redo_file_op,
             // 日志相关
             get_log_dir,
             read_logs,
@@ -146,6 +298,32 @@ pub fn run() {
             set_logging_enabled,
             is_logging_enabled,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(move |app, event| match event {
+            // 退出前中断全部会话，等待进程结束并写完录制文件
+            tauri::RunEvent::ExitRequested { .. } => {
+                shutdown(app);
+            }
+            tauri::RunEvent::Exit => {
+                shutdown(app);
+                // 释放日志写入线程，确保缓冲的日志落盘
+                drop(logger.take());
+            }
+            _ => {}
+        });
+}
+
+/// 应用退出时的清理，只执行一次
+fn shutdown(app: &tauri::AppHandle) {
+    static DONE: AtomicBool = AtomicBool::new(false);
+    if DONE.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    eprintln!("[shutdown] 应用退出，清理会话");
+    let state = app.state::<AppState>();
+    let remaining = shutdown_sessions(&state, SHUTDOWN_TIMEOUT);
+    stop_fs_watcher(&state);
+    eprintln!("[shutdown] 清理完成，未结束会话: {}", remaining);
 }
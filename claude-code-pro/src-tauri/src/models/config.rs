@@ -23,12 +23,60 @@ pub struct Config {
     /// 是否启用日志
     #[serde(default = "default_enable_logging")]
     pub enable_logging: bool,
+
+    /// 是否录制会话原始输出（用于回放调试）
+    #[serde(default)]
+    pub record_sessions: bool,
+
+    /// 同时运行的会话上限（0 表示不限制）
+    #[serde(default = "default_max_concurrent_sessions")]
+    pub max_concurrent_sessions: usize,
+
+    /// 每个工作区同时运行的会话上限（0 表示不限制）
+    #[serde(default = "default_max_sessions_per_workspace")]
+    pub max_sessions_per_workspace: usize,
+
+    /// 文件浏览器除工作目录外还允许访问的目录
+    #[serde(default)]
+    pub extra_allowed_dirs: Vec<PathBuf>,
+
+    /// 回收站条目保留天数（0 表示不按时间清理）
+    #[serde(default = "default_trash_max_age_days")]
+    pub trash_max_age_days: u64,
+
+    /// 回收站总大小上限（字节，0 表示不限制），超出时先清理最早删除的条目
+    #[serde(default = "default_trash_max_bytes")]
+    pub trash_max_bytes: u64,
+
+    /// 文件搜索和列表额外排除的模式（gitignore 语法，`.gitignore` 等规则之外）
+    #[serde(default = "default_file_excludes")]
+    pub file_excludes: Vec<String>,
 }
 
 fn default_enable_logging() -> bool {
     true
 }
 
+fn default_max_concurrent_sessions() -> usize {
+    4
+}
+
+fn default_max_sessions_per_workspace() -> usize {
+    2
+}
+
+fn default_trash_max_age_days() -> u64 {
+    30
+}
+
+fn default_trash_max_bytes() -> u64 {
+    1024 * 1024 * 1024
+}
+
+fn default_file_excludes() -> Vec<String> {
+    vec!["node_modules".to_string(), "target".to_string()]
+}
+
 impl Default for Config {
     fn default() -> Self {
         #[cfg(windows)]
@@ -44,6 +92,13 @@ impl Default for Config {
             session_dir: None,
             git_bin_path: None,
             enable_logging: true,
+            record_sessions: false,
+            max_concurrent_sessions: default_max_concurrent_sessions(),
+            max_sessions_per_workspace: default_max_sessions_per_workspace(),
+            extra_allowed_dirs: Vec::new(),
+            trash_max_age_days: default_trash_max_age_days(),
+            trash_max_bytes: default_trash_max_bytes(),
+            file_excludes: default_file_excludes(),
         }
     }
 }
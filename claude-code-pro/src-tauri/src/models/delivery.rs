@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+/// 推送给前端的单个事件帧
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFrame {
+    /// 会话内递增序号，用于 fetch_event_payload
+    pub seq: u64,
+
+    /// 事件内容（超大时已截断，截断后仍过大时只含 `type` 和 `omitted_bytes`）
+    pub event: serde_json::Value,
+
+    /// 是否被截断
+    pub truncated: bool,
+}
+
+/// 一次推送的事件批次（`chat-events`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventBatch {
+    pub session_id: String,
+    pub frames: Vec<EventFrame>,
+}
+
+/// 事件推送统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryMetrics {
+    /// 已推送的事件数
+    pub frames_sent: u64,
+
+    /// 已推送的批次数
+    pub batches_sent: u64,
+
+    /// 推送失败或通道关闭而丢弃的事件数
+    pub frames_dropped: u64,
+
+    /// 排队时间超过阈值的事件数
+    pub frames_delayed: u64,
+
+    /// 被截断的事件数
+    pub frames_truncated: u64,
+
+    /// 通道已满导致读取端等待的次数
+    pub backpressure_waits: u64,
+
+    /// 最长排队时间（毫秒）
+    pub max_latency_ms: u64,
+
+    /// 最大批次大小
+    pub max_batch_size: usize,
+}
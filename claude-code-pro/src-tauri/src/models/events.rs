@@ -34,10 +34,37 @@ pub enum StreamEvent {
         message: serde_json::Value,
     },
 
+    /// 部分消息信封（--include-partial-messages），由后端转换为下面的增量事件
+    #[serde(rename = "stream_event")]
+    Partial {
+        event: serde_json::Value,
+        #[serde(flatten)]
+        extra: HashMap<String, serde_json::Value>,
+    },
+
     /// 文本内容
     #[serde(rename = "text_delta")]
     TextDelta { text: String },
 
+    /// 思考内容增量
+    #[serde(rename = "thinking_delta")]
+    ThinkingDelta { text: String },
+
+    /// 工具输入 JSON 增量
+    #[serde(rename = "tool_input_delta")]
+    ToolInputDelta {
+        tool_use_id: String,
+        tool_name: String,
+        partial_json: String,
+    },
+
+    /// 消息级增量（停止原因、用量）
+    #[serde(rename = "message_delta")]
+    MessageDelta {
+        stop_reason: Option<String>,
+        usage: Option<serde_json::Value>,
+    },
+
     /// 工具调用开始
     #[serde(rename = "tool_start")]
     ToolStart { tool_name: String, input: serde_json::Value },
@@ -68,17 +95,346 @@ pub enum StreamEvent {
     /// 会话结束
     #[serde(rename = "session_end")]
     SessionEnd,
+
+    /// 会话进入等待队列（已达到并发上限）
+    #[serde(rename = "queued")]
+    Queued { session_id: String, position: usize },
+
+    /// 会话离开等待队列，cancelled 为 true 表示在启动前被取消
+    #[serde(rename = "dequeued")]
+    Dequeued { session_id: String, cancelled: bool },
+
+    /// 未识别的事件类型（原样保留，避免新版 CLI 的事件被静默丢弃）
+    #[serde(rename = "unknown")]
+    Unknown { raw: serde_json::Value },
+
+    /// 无法解析的行
+    #[serde(rename = "parse_error")]
+    ParseError { line: String, error: String },
 }
 
+/// 已知的事件类型标签
+const KNOWN_TYPES: &[&str] = &[
+    "system",
+    "assistant",
+    "user",
+    "stream_event",
+    "text_delta",
+    "thinking_delta",
+    "tool_input_delta",
+    "message_delta",
+    "tool_start",
+    "tool_end",
+    "permission_request",
+    "result",
+    "error",
+    "session_end",
+    "queued",
+    "dequeued",
+    "unknown",
+    "parse_error",
+];
+
 impl StreamEvent {
     /// 解析 Claude CLI 的 stream-json 行
+    ///
+    /// 只有空行返回 `None`。未知类型返回 `Unknown`，
+    /// 非法 JSON 或已知类型但结构不符时返回 `ParseError`。
     pub fn parse_line(line: &str) -> Option<Self> {
         let line = line.trim();
         if line.is_empty() {
             return None;
         }
 
-        // 直接使用 serde 解析
-        serde_json::from_str(line).ok()
+        let value: serde_json::Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(e) => return Some(Self::parse_error(line, e)),
+        };
+
+        let is_known = value
+            .get("type")
+            .and_then(|t| t.as_str())
+            .is_some_and(|t| KNOWN_TYPES.contains(&t));
+
+        if !is_known {
+            return Some(if value.is_object() {
+                StreamEvent::Unknown { raw: value }
+            } else {
+                StreamEvent::ParseError {
+                    line: line.to_string(),
+                    error: "不是 JSON 对象".to_string(),
+                }
+            });
+        }
+
+        Some(serde_json::from_value(value).unwrap_or_else(|e| Self::parse_error(line, e)))
+    }
+
+    fn parse_error(line: &str, error: serde_json::Error) -> Self {
+        StreamEvent::ParseError {
+            line: line.to_string(),
+            error: error.to_string(),
+        }
+    }
+
+    /// 事件类型标签（用于日志和统计）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StreamEvent::System { .. } => "system",
+            StreamEvent::Assistant { .. } => "assistant",
+            StreamEvent::User { .. } => "user",
+            StreamEvent::Partial { .. } => "stream_event",
+            StreamEvent::TextDelta { .. } => "text_delta",
+            StreamEvent::ThinkingDelta { .. } => "thinking_delta",
+            StreamEvent::ToolInputDelta { .. } => "tool_input_delta",
+            StreamEvent::MessageDelta { .. } => "message_delta",
+            StreamEvent::ToolStart { .. } => "tool_start",
+            StreamEvent::ToolEnd { .. } => "tool_end",
+            StreamEvent::PermissionRequest { .. } => "permission_request",
+            StreamEvent::Result { .. } => "result",
+            StreamEvent::Error { .. } => "error",
+            StreamEvent::SessionEnd => "session_end",
+            StreamEvent::Queued { .. } => "queued",
+            StreamEvent::Dequeued { .. } => "dequeued",
+            StreamEvent::Unknown { .. } => "unknown",
+            StreamEvent::ParseError { .. } => "parse_error",
+        }
+    }
+}
+
+/// 单个会话的解析统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseStats {
+    /// 收到的事件总数
+    pub events: u64,
+    /// 未识别类型的事件数
+    pub unknown: u64,
+    /// 解析失败的行数
+    pub parse_errors: u64,
+    /// 各未识别类型出现的次数
+    pub unknown_types: HashMap<String, u64>,
+}
+
+impl ParseStats {
+    /// 统计一个事件
+    pub fn record(&mut self, event: &StreamEvent) {
+        self.events += 1;
+        match event {
+            StreamEvent::Unknown { raw } => {
+                self.unknown += 1;
+                let kind = raw.get("type").and_then(|t| t.as_str()).unwrap_or("<none>");
+                *self.unknown_types.entry(kind.to_string()).or_default() += 1;
+            }
+            StreamEvent::ParseError { .. } => self.parse_errors += 1,
+            _ => {}
+        }
+    }
+
+    /// 未能按已知类型处理的事件数
+    pub fn dropped(&self) -> u64 {
+        self.unknown + self.parse_errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(value: serde_json::Value) -> StreamEvent {
+        StreamEvent::parse_line(&value.to_string()).expect("non-empty line")
+    }
+
+    #[test]
+    fn system_init() {
+        let event = parse(json!({
+            "type": "system", "subtype": "init", "cwd": "/tmp/project",
+            "session_id": "s-1", "tools": ["Bash", "Read"], "mcp_servers": [],
+            "model": "claude-sonnet-4-5", "permissionMode": "default", "apiKeySource": "none"
+        }));
+        match event {
+            StreamEvent::System { subtype, extra } => {
+                assert_eq!(subtype.as_deref(), Some("init"));
+                assert_eq!(extra["session_id"], "s-1");
+                assert_eq!(extra["tools"][0], "Bash");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn system_compact_boundary() {
+        let event = parse(json!({
+            "type": "system", "subtype": "compact_boundary", "session_id": "s-1",
+            "compact_metadata": { "trigger": "auto", "pre_tokens": 150000 }
+        }));
+        assert!(matches!(event, StreamEvent::System { subtype: Some(ref s), .. } if s == "compact_boundary"));
+    }
+
+    #[test]
+    fn assistant_with_text_thinking_and_tool_use() {
+        let event = parse(json!({
+            "type": "assistant",
+            "message": {
+                "id": "msg_1", "type": "message", "role": "assistant", "model": "claude-sonnet-4-5",
+                "content": [
+                    { "type": "thinking", "thinking": "Let me check.", "signature": "sig" },
+                    { "type": "text", "text": "Running tests." },
+                    { "type": "tool_use", "id": "toolu_1", "name": "Bash", "input": { "command": "ls" } }
+                ],
+                "stop_reason": null
+            },
+            "parent_tool_use_id": null, "session_id": "s-1"
+        }));
+        match event {
+            StreamEvent::Assistant { message } => assert_eq!(message["content"].as_array().unwrap().len(), 3),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn user_tool_result() {
+        let event = parse(json!({
+            "type": "user",
+            "message": {
+                "role": "user",
+                "content": [{ "type": "tool_result", "tool_use_id": "toolu_1", "content": "ok", "is_error": false }]
+            },
+            "parent_tool_use_id": null, "session_id": "s-1",
+            "tool_use_result": { "stdout": "ok", "stderr": "", "interrupted": false }
+        }));
+        match event {
+            StreamEvent::User { message } => assert_eq!(message["content"][0]["tool_use_id"], "toolu_1"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn result_subtypes() {
+        for subtype in ["success", "error_max_turns", "error_during_execution"] {
+            let event = parse(json!({
+                "type": "result", "subtype": subtype, "is_error": subtype != "success",
+                "duration_ms": 10, "num_turns": 1, "session_id": "s-1", "total_cost_usd": 0.01
+            }));
+            match event {
+                StreamEvent::Result { subtype: s, extra } => {
+                    assert_eq!(s, subtype);
+                    assert_eq!(extra["num_turns"], 1);
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn app_level_events() {
+        assert!(matches!(parse(json!({ "type": "text_delta", "text": "hi" })), StreamEvent::TextDelta { .. }));
+        assert!(matches!(parse(json!({ "type": "thinking_delta", "text": "hm" })), StreamEvent::ThinkingDelta { .. }));
+        assert!(matches!(
+            parse(json!({ "type": "tool_input_delta", "tool_use_id": "toolu_1", "tool_name": "Bash", "partial_json": "{" })),
+            StreamEvent::ToolInputDelta { .. }
+        ));
+        assert!(matches!(
+            parse(json!({ "type": "message_delta", "stop_reason": "tool_use", "usage": null })),
+            StreamEvent::MessageDelta { .. }
+        ));
+        assert!(matches!(
+            parse(json!({ "type": "tool_start", "tool_name": "Bash", "input": {} })),
+            StreamEvent::ToolStart { .. }
+        ));
+        assert!(matches!(
+            parse(json!({ "type": "tool_end", "tool_name": "Bash", "output": null })),
+            StreamEvent::ToolEnd { .. }
+        ));
+        assert!(matches!(
+            parse(json!({
+                "type": "permission_request", "session_id": "s-1",
+                "denials": [{ "tool_name": "Bash", "reason": "denied", "tool_use_id": "toolu_1" }]
+            })),
+            StreamEvent::PermissionRequest { .. }
+        ));
+        assert!(matches!(parse(json!({ "type": "error", "error": "boom" })), StreamEvent::Error { .. }));
+        assert!(matches!(parse(json!({ "type": "session_end" })), StreamEvent::SessionEnd));
+        assert!(matches!(
+            parse(json!({ "type": "queued", "session_id": "s-1", "position": 2 })),
+            StreamEvent::Queued { position: 2, .. }
+        ));
+        assert!(matches!(
+            parse(json!({ "type": "dequeued", "session_id": "s-1", "cancelled": true })),
+            StreamEvent::Dequeued { cancelled: true, .. }
+        ));
+    }
+
+    #[test]
+    fn partial_message_envelope() {
+        let event = parse(json!({
+            "type": "stream_event",
+            "event": { "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": "He" } },
+            "session_id": "s-1", "parent_tool_use_id": null, "uuid": "u-1"
+        }));
+        match event {
+            StreamEvent::Partial { event, extra } => {
+                assert_eq!(event["delta"]["text"], "He");
+                assert_eq!(extra["session_id"], "s-1");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_type_is_preserved() {
+        let raw = json!({ "type": "future_event", "event": { "type": "message_start" }, "session_id": "s-1" });
+        match parse(raw.clone()) {
+            StreamEvent::Unknown { raw: kept } => assert_eq!(kept, raw),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let missing_type = json!({ "foo": 1 });
+        assert!(matches!(parse(missing_type), StreamEvent::Unknown { .. }));
+    }
+
+    #[test]
+    fn unknown_serializes_with_raw_payload() {
+        let event = parse(json!({ "type": "future_event", "x": 1 }));
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value, json!({ "type": "unknown", "raw": { "type": "future_event", "x": 1 } }));
+    }
+
+    #[test]
+    fn malformed_lines_become_parse_errors() {
+        for line in [r#"{"type":"assistant","message":"#, "not json", "[1, 2]", r#"{"type":"assistant"}"#] {
+            match StreamEvent::parse_line(line) {
+                Some(StreamEvent::ParseError { line: kept, error }) => {
+                    assert_eq!(kept, line);
+                    assert!(!error.is_empty());
+                }
+                other => panic!("{}: unexpected {:?}", line, other),
+            }
+        }
+    }
+
+    #[test]
+    fn blank_lines_are_skipped() {
+        assert!(StreamEvent::parse_line("").is_none());
+        assert!(StreamEvent::parse_line("  \t ").is_none());
+    }
+
+    #[test]
+    fn stats_count_drops() {
+        let mut stats = ParseStats::default();
+        for line in [
+            r#"{"type":"system","subtype":"init"}"#,
+            r#"{"type":"future_event","event":{}}"#,
+            r#"{"type":"future_event","event":{}}"#,
+            "garbage",
+        ] {
+            stats.record(&StreamEvent::parse_line(line).unwrap());
+        }
+        assert_eq!(stats.events, 4);
+        assert_eq!(stats.unknown, 2);
+        assert_eq!(stats.parse_errors, 1);
+        assert_eq!(stats.dropped(), 3);
+        assert_eq!(stats.unknown_types["future_event"], 2);
     }
 }
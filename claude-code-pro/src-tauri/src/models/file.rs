@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+/// 文件在磁盘上的版本，保存时用于检测外部修改
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileVersion {
+    /// 内容的 SHA-256（十六进制）
+    pub hash: String,
+
+    /// 修改时间（Unix 毫秒）
+    pub mtime: u64,
+}
+
+/// 换行符风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    /// 同时包含 LF 和 CRLF
+    Mixed,
+    /// 没有换行
+    None,
+}
+
+/// 读取范围
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ReadRange {
+    /// 字节范围（会对齐到字符边界，非末尾的分段在最后一个换行处截断）
+    Bytes { offset: u64, length: u64 },
+    /// 行范围（从 1 开始，含两端；`end` 为空时读到文件末尾或大小上限）
+    Lines { start: usize, end: Option<usize> },
+}
+
+/// 文件内容及其版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileContent {
+    /// 解码后的文本（二进制文件为空）
+    pub content: String,
+
+    /// 只读取了一部分（`partial`）或超过 10MB 的文件不计算 `hash`（为空），按这个版本保存会报告冲突
+    #[serde(flatten)]
+    pub version: FileVersion,
+
+    /// 检测到的编码（UTF-8、UTF-16LE、UTF-16BE、GBK、Shift_JIS、windows-1252）
+    pub encoding: String,
+
+    /// 是否带 BOM
+    pub bom: bool,
+
+    pub line_ending: LineEnding,
+
+    pub binary: bool,
+
+    /// 二进制文件的十六进制预览（最多 4KB）
+    pub hex_preview: Option<String>,
+
+    /// 文件大小（字节）
+    pub size: u64,
+
+    /// 返回内容在文件中的字节范围
+    pub offset: u64,
+    pub length: u64,
+
+    /// 按行读取时实际返回的行范围（从 1 开始，含两端）
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+
+    /// 总行数（按字节范围读取时未知）
+    pub total_lines: Option<usize>,
+
+    /// 只包含文件的一部分，不能直接保存
+    pub partial: bool,
+}
+
+/// 图片预览
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePreview {
+    /// MIME 类型（`image/png`、`image/jpeg`、`image/gif`、`image/webp`、`image/svg+xml`）
+    pub mime_type: String,
+
+    /// 原图尺寸（像素）
+    pub width: u32,
+    pub height: u32,
+
+    /// 缩略图的 data URL（格式不一定与原图相同）
+    pub thumbnail: String,
+
+    /// 缩略图尺寸（SVG 为建议的显示尺寸）
+    pub thumbnail_width: u32,
+    pub thumbnail_height: u32,
+
+    /// 文件大小（字节）
+    pub size: u64,
+}
+
+/// 文件系统变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FsChangeKind {
+    Created,
+    Modified,
+    Removed,
+    Renamed,
+}
+
+/// 单个文件系统变更
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsChange {
+    pub kind: FsChangeKind,
+    pub path: String,
+
+    /// 重命名前的路径
+    pub old_path: Option<String>,
+}
+
+/// 一次推送的变更批次（`fs-changed` 事件）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsChangeBatch {
+    /// 被监听的工作目录
+    pub root: String,
+    pub changes: Vec<FsChange>,
+}
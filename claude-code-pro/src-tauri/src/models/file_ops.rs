@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// 可撤销的文件操作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum FileOpKind {
+    /// 新建文件或目录（`path` 为本次新建的最上层路径）
+    Create {
+        path: String,
+        is_dir: bool,
+        /// 撤销后所在的回收站条目，重做时从这里恢复
+        trash_id: Option<String>,
+    },
+    /// 覆盖已有文件（前后内容保存在日志目录中）
+    Write { path: String },
+    /// 重命名或移动
+    Rename { from: String, to: String },
+    /// 删除（移入回收站）
+    Delete { path: String, trash_id: String },
+    /// 批量替换（每个文件的前后内容保存在日志目录中，撤销和重做整体执行）
+    Replace { paths: Vec<String> },
+}
+
+/// 操作日志中的一条记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOp {
+    pub id: String,
+    pub op: FileOpKind,
+    pub created_at: String,
+}
+
+/// 工作区的撤销 / 重做栈（末尾为最近的操作）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOpHistory {
+    pub undo: Vec<FileOp>,
+    pub redo: Vec<FileOp>,
+}
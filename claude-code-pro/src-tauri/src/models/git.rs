@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+
+/// 文件变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GitChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChange,
+    Untracked,
+    Conflicted,
+}
+
+/// 单个文件的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitFileStatus {
+    /// 相对仓库根目录的路径
+    pub path: String,
+
+    /// 重命名前的路径
+    pub old_path: Option<String>,
+
+    pub kind: GitChangeKind,
+}
+
+/// 工作区状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    /// 当前分支（分离 HEAD 时为空）
+    pub branch: Option<String>,
+
+    /// HEAD 提交 ID（空仓库时为空）
+    pub head: Option<String>,
+
+    /// 已暂存的变更
+    pub staged: Vec<GitFileStatus>,
+
+    /// 未暂存的变更
+    pub unstaged: Vec<GitFileStatus>,
+
+    /// 未跟踪的文件
+    pub untracked: Vec<GitFileStatus>,
+
+    /// 存在冲突的文件
+    pub conflicted: Vec<GitFileStatus>,
+}
+
+/// 差异行类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffLineKind {
+    Context,
+    Addition,
+    Deletion,
+}
+
+/// 差异行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+
+    /// 行内容（不含换行符）
+    pub content: String,
+
+    /// 旧文件行号（新增行为空）
+    pub old_lineno: Option<u32>,
+
+    /// 新文件行号（删除行为空）
+    pub new_lineno: Option<u32>,
+
+    /// 文件末尾没有换行符
+    pub no_newline: bool,
+}
+
+/// 差异块
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    /// `@@ -a,b +c,d @@` 头部
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+/// 单个文件的差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    pub path: String,
+    pub old_path: Option<String>,
+    pub kind: GitChangeKind,
+
+    /// 二进制文件不包含差异块
+    pub binary: bool,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// 分支信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchInfo {
+    pub name: String,
+
+    /// 是否为当前分支
+    pub is_head: bool,
+
+    /// 是否为远程分支
+    pub is_remote: bool,
+
+    /// 上游分支名称
+    pub upstream: Option<String>,
+
+    /// 分支指向的提交 ID
+    pub commit: Option<String>,
+}
+
+/// 提交信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitInfo {
+    pub id: String,
+    pub short_id: String,
+
+    /// 提交说明第一行
+    pub summary: String,
+    pub message: String,
+    pub author_name: String,
+    pub author_email: String,
+
+    /// 提交时间（RFC 3339）
+    pub time: String,
+    pub parents: Vec<String>,
+
+    /// 是否由 Claude 会话提交（作者或 Co-Authored-By 为 Claude）
+    pub agent: bool,
+}
+
+/// 分页的提交历史
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitPage {
+    pub commits: Vec<CommitInfo>,
+
+    /// 是否还有更早的提交
+    pub has_more: bool,
+}
+
+/// 储藏条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashInfo {
+    /// 储藏序号（0 为最新）
+    pub index: usize,
+    pub id: String,
+    pub message: String,
+}
+
+/// 会话专用的 Git 工作树
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionWorktree {
+    pub session_id: String,
+
+    /// 主仓库工作目录
+    pub repo_path: String,
+
+    /// 创建会话时的工作目录（会话调度按它计算工作区并发，旧记录为空）
+    #[serde(default)]
+    pub work_dir: String,
+
+    /// 工作树目录
+    pub path: String,
+
+    /// Git 中的工作树名称
+    pub name: String,
+
+    /// 工作树所在分支
+    pub branch: String,
+
+    /// 创建时的基准提交
+    pub base_commit: String,
+
+    pub created_at: String,
+
+    /// Claude 会话 ID（前端收到 system 事件后改用它引用会话）
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// 合并冲突的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+    pub path: String,
+
+    /// bothModified / bothAdded / deletedByUs / deletedByThem
+    pub kind: String,
+}
+
+/// 工作树合并结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeReport {
+    /// 是否已合并（存在冲突时为 false，主工作区不受影响）
+    pub merged: bool,
+
+    /// 主分支已包含全部修改
+    pub up_to_date: bool,
+
+    /// 是否为快进合并
+    pub fast_forward: bool,
+
+    /// 合并后的 HEAD 提交
+    pub commit: Option<String>,
+
+    /// 合并前在工作树中自动提交的未提交修改
+    pub auto_commit: Option<String>,
+
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// 逐行追溯结果中的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameLine {
+    /// 行号（从 1 开始）
+    pub line: usize,
+    pub content: String,
+
+    /// 最后修改该行的提交，未提交的行为空
+    pub commit: Option<String>,
+    pub short_id: Option<String>,
+    pub summary: Option<String>,
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    pub time: Option<String>,
+
+    /// 是否来自 Claude 会话的提交
+    pub agent: bool,
+
+    /// 工作区中尚未提交的行
+    pub uncommitted: bool,
+}
+
+/// 文件的逐行追溯（基于工作区版本）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileBlame {
+    pub path: String,
+    pub lines: Vec<BlameLine>,
+}
+
+/// 文件历史中的一次提交
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    pub commit: CommitInfo,
+
+    /// 该提交中的文件路径
+    pub path: String,
+
+    /// 在该提交中被重命名时的原路径
+    pub old_path: Option<String>,
+    pub kind: GitChangeKind,
+}
+
+/// 分页的文件历史
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryPage {
+    pub entries: Vec<FileHistoryEntry>,
+
+    /// 是否还有更早的提交
+    pub has_more: bool,
+}
@@ -1,2 +1,10 @@
 pub mod config;
 pub mod events;
+pub mod shell;
+pub mod delivery;
+pub mod scheduler;
+pub mod git;
+pub mod trash;
+pub mod file_ops;
+pub mod file;
+pub mod search;
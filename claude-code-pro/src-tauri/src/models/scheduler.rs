@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// 会话运行限制（0 表示不限制）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunLimits {
+    /// 全局同时运行上限
+    pub max_concurrent: usize,
+
+    /// 每个工作区同时运行上限
+    pub max_per_workspace: usize,
+}
+
+/// 运行中或排队中的会话
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunInfo {
+    /// 会话 ID
+    pub session_id: String,
+
+    /// 工作区路径（未设置工作目录时为空）
+    pub workspace: String,
+
+    /// 优先级，数值越大越先启动
+    pub priority: i32,
+
+    /// 进入调度的时间
+    pub submitted_at: String,
+}
+
+/// 调度器状态（返回给前端）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunQueueStatus {
+    pub limits: RunLimits,
+    pub running: Vec<RunInfo>,
+    /// 按启动顺序排列
+    pub queued: Vec<RunInfo>,
+}
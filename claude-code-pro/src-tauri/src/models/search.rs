@@ -0,0 +1,169 @@
+use crate::models::file::FileVersion;
+use crate::models::git::FileDiff;
+use serde::{Deserialize, Serialize};
+
+/// 全文搜索选项
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ContentSearchOptions {
+    /// 按正则表达式匹配（否则按字面量）
+    pub regex: bool,
+
+    /// 区分大小写
+    pub case_sensitive: bool,
+
+    /// 全词匹配
+    pub whole_word: bool,
+
+    /// 只搜索匹配这些 glob 的文件（gitignore 语法，如 `*.rs`、`src/**`）
+    pub include: Vec<String>,
+
+    /// 额外排除的 glob
+    pub exclude: Vec<String>,
+
+    /// 最多返回的匹配行数
+    pub max_results: Option<usize>,
+
+    /// 每个文件最多返回的匹配行数
+    pub max_results_per_file: Option<usize>,
+
+    /// 匹配行前后附带的上下文行数
+    pub context_lines: usize,
+}
+
+/// 一行中的匹配
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentMatch {
+    /// 完整路径
+    pub path: String,
+
+    /// 相对工作目录的路径
+    pub relative_path: String,
+
+    /// 行号（从 1 开始）
+    pub line: usize,
+
+    /// 第一个匹配的列号（从 1 开始，UTF-16 单位）
+    pub column: usize,
+
+    /// 行内容（不含换行符）
+    pub text: String,
+
+    /// `text` 中全部匹配的区间 `[start, end)`（UTF-16 偏移）
+    pub ranges: Vec<[usize; 2]>,
+
+    /// 前面的上下文行
+    pub before: Vec<String>,
+
+    /// 后面的上下文行
+    pub after: Vec<String>,
+}
+
+/// 搜索结束时的统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentSearchSummary {
+    /// 已搜索的文件数
+    pub files_searched: usize,
+
+    /// 有匹配的文件数
+    pub files_matched: usize,
+
+    /// 匹配行数
+    pub match_count: usize,
+
+    /// 跳过的二进制或过大的文件数
+    pub files_skipped: usize,
+
+    /// 是否因达到结果上限而提前结束
+    pub truncated: bool,
+
+    /// 是否被取消
+    pub cancelled: bool,
+
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+/// 推送给前端的搜索事件（`content-search`）
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ContentSearchEvent {
+    /// 一批新的匹配
+    Matches {
+        search_id: String,
+        matches: Vec<ContentMatch>,
+    },
+    /// 搜索结束（完成、达到上限、取消或出错）
+    Done {
+        search_id: String,
+        summary: ContentSearchSummary,
+        error: Option<String>,
+    },
+}
+
+/// 替换的目标文件
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceTarget {
+    pub path: String,
+
+    /// 只替换这些行（从 1 开始），为空时替换文件中的全部匹配
+    #[serde(default)]
+    pub lines: Option<Vec<usize>>,
+
+    /// 预览时的内容哈希（应用替换时必填，文件在预览后被修改则跳过）
+    #[serde(default)]
+    pub expected_hash: Option<String>,
+}
+
+/// 单个文件的替换预览
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplacePreview {
+    pub path: String,
+
+    /// 预览时的文件版本，应用时传回 `hash`
+    pub version: FileVersion,
+
+    /// 替换次数
+    pub replacements: usize,
+
+    pub diff: FileDiff,
+}
+
+/// 被跳过的文件
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceSkip {
+    pub path: String,
+    pub reason: String,
+}
+
+/// 替换预览结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplacePreviewResult {
+    pub files: Vec<ReplacePreview>,
+    pub skipped: Vec<ReplaceSkip>,
+}
+
+/// 已写入的文件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceApplied {
+    pub path: String,
+    pub replacements: usize,
+
+    /// 写入后的版本
+    pub version: FileVersion,
+}
+
+/// 应用替换的结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceResult {
+    pub applied: Vec<ReplaceApplied>,
+    pub skipped: Vec<ReplaceSkip>,
+}
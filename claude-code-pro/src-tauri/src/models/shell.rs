@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+/// Bash 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellJobStatus {
+    /// 前台命令执行中
+    Running,
+    /// 后台 shell 仍在运行
+    Background,
+    /// 正常结束
+    Completed,
+    /// 非零退出或工具报错
+    Failed,
+    /// 被 KillShell 终止
+    Killed,
+}
+
+impl ShellJobStatus {
+    /// 是否已结束
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Killed)
+    }
+}
+
+/// 单次 Bash 工具调用（含后台 shell）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellJob {
+    /// 对应的 tool_use ID
+    pub tool_use_id: String,
+
+    /// 所属会话
+    pub session_id: String,
+
+    /// 执行的命令
+    pub command: String,
+
+    /// 命令描述（Claude 提供）
+    pub description: Option<String>,
+
+    /// 执行目录
+    pub cwd: Option<String>,
+
+    /// 是否为后台 shell
+    pub background: bool,
+
+    /// 后台 shell ID（BashOutput / KillShell 使用）
+    pub shell_id: Option<String>,
+
+    /// 当前状态
+    pub status: ShellJobStatus,
+
+    /// 退出码
+    pub exit_code: Option<i32>,
+
+    /// 累计输出（只保留末尾的 1MB）
+    pub output: String,
+
+    /// 累计输出的总字节数（含已丢弃的开头部分）
+    pub output_bytes: u64,
+
+    /// 开始时间
+    pub started_at: String,
+
+    /// 最近更新时间
+    pub updated_at: String,
+}
+
+/// 推送给前端的 Bash 任务更新
+///
+/// 只携带本次新增的输出，完整输出通过 `list_shell_jobs` 获取。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellJobEvent {
+    pub session_id: String,
+
+    /// 任务状态（`output` 为空）
+    pub job: ShellJob,
+
+    /// 本次新增的输出
+    pub chunk: String,
+
+    /// `chunk` 在累计输出中的起始字节偏移
+    pub chunk_offset: u64,
+}
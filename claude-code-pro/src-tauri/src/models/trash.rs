@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// 回收站条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    pub id: String,
+
+    /// 文件或目录名
+    pub name: String,
+
+    /// 删除前的完整路径
+    pub original_path: String,
+
+    pub is_dir: bool,
+
+    /// 占用大小（字节，目录为全部内容之和）
+    pub size: u64,
+
+    /// 删除时间（RFC 3339）
+    pub deleted_at: String,
+}
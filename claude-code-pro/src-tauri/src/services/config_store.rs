@@ -213,6 +213,47 @@ impl ConfigStore {
         }
     }
 
+    /// 获取会话录制目录
+    pub fn recordings_dir(&self) -> Result<PathBuf> {
+        let dir = self.session_dir()?.join("recordings");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// 获取应用数据目录下的子目录
+    fn data_subdir(name: &str) -> Result<PathBuf> {
+        let dir = dirs::data_local_dir()
+            .ok_or_else(|| AppError::ConfigError("无法获取数据目录".to_string()))?
+            .join("claude-code-pro")
+            .join(name);
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// 获取应用回收站目录
+    pub fn trash_dir(&self) -> Result<PathBuf> {
+        Self::data_subdir("trash")
+    }
+
+    /// 获取文件操作日志目录（按工作区分子目录）
+    pub fn file_ops_dir(&self) -> Result<PathBuf> {
+        Self::data_subdir("file-ops")
+    }
+
+    /// 获取会话工作树目录
+    pub fn worktrees_dir(&self) -> Result<PathBuf> {
+        let dir = self.session_dir()?.join("worktrees");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// 获取运行中会话的锁文件目录
+    pub fn locks_dir(&self) -> Result<PathBuf> {
+        let dir = self.session_dir()?.join("locks");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
     /// 获取健康状态
     pub fn health_status(&self) -> HealthStatus {
         let claude_version = self.detect_claude();
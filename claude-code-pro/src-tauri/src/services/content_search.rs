@@ -0,0 +1,284 @@
+use crate::error::{AppError, Result};
+use crate::models::search::{ContentMatch, ContentSearchOptions, ContentSearchSummary};
+use crate::services::encoding::{decode, detect, is_binary};
+use crate::services::walker::WalkRules;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// 默认最多返回的匹配行数
+pub const DEFAULT_MAX_RESULTS: usize = 2000;
+/// 上下文行数上限
+pub const MAX_CONTEXT_LINES: usize = 10;
+/// 超过此大小的文件不搜索
+pub const MAX_SEARCH_FILE_BYTES: u64 = 10 * 1024 * 1024;
+/// 累积到这么多匹配或超过间隔时推送一批
+const BATCH_SIZE: usize = 100;
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 由查询和选项编译出的匹配器
+pub struct ContentMatcher {
+    regex: Regex,
+}
+
+impl ContentMatcher {
+    pub fn new(query: &str, options: &ContentSearchOptions) -> Result<Self> {
+        if query.is_empty() {
+            return Err(AppError::ParseError("搜索内容为空".to_string()));
+        }
+        let pattern = if options.regex { query.to_string() } else { regex::escape(query) };
+        let pattern = if options.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .build()
+            .map_err(|e| AppError::ParseError(format!("无效的正则表达式: {}", e)))?;
+        Ok(Self { regex })
+    }
+
+    /// 替换 `content` 中的匹配，返回新内容和替换次数
+    ///
+    /// `lines` 给出时只替换这些行（从 1 开始，与搜索结果的行号一致）；`expand` 为 true 时
+    /// 替换文本中的 `$1`、`${name}` 展开为捕获组。换行符保持不变。
+    pub fn replace(&self, content: &str, replacement: &str, expand: bool, lines: Option<&HashSet<usize>>) -> (String, usize) {
+        let mut output = String::with_capacity(content.len());
+        let mut count = 0;
+        for (index, line) in content.split_inclusive('\n').enumerate() {
+            if lines.is_some_and(|lines| !lines.contains(&(index + 1))) {
+                output.push_str(line);
+                continue;
+            }
+            let body = line.strip_suffix('\n').map_or(line, |l| l.strip_suffix('\r').unwrap_or(l));
+            let mut last = 0;
+            for caps in self.regex.captures_iter(body) {
+                let found = caps.get(0).expect("捕获组 0 总是存在");
+                if found.is_empty() {
+                    continue;
+                }
+                output.push_str(&body[last..found.start()]);
+                if expand {
+                    caps.expand(replacement, &mut output);
+                } else {
+                    output.push_str(replacement);
+                }
+                last = found.end();
+                count += 1;
+            }
+            output.push_str(&line[last..]);
+        }
+        (output, count)
+    }
+
+    /// 行内非空匹配的字节区间
+    fn find_in(&self, line: &str) -> Vec<(usize, usize)> {
+        self.regex.find_iter(line)
+            .filter(|m| !m.is_empty())
+            .map(|m| (m.start(), m.end()))
+            .collect()
+    }
+}
+
+/// 在 `root` 下搜索内容，每批匹配调用一次 `on_batch`
+///
+/// 遍历遵循 [`WalkRules`]（`excludes` 为配置中的排除规则，再加上选项中的 include/exclude），
+/// 文件按检测到的编码解码，跳过二进制文件和超过 [`MAX_SEARCH_FILE_BYTES`] 的文件。
+/// 每个文件开始前检查 `cancel`。
+pub fn search_workspace(
+    root: &Path,
+    matcher: &ContentMatcher,
+    options: &ContentSearchOptions,
+    excludes: &[String],
+    cancel: &CancellationToken,
+    mut on_batch: impl FnMut(Vec<ContentMatch>),
+) -> ContentSearchSummary {
+    let started = Instant::now();
+    let excludes: Vec<String> = excludes.iter().chain(&options.exclude).cloned().collect();
+    let rules = WalkRules::with_includes(root, &options.include, &excludes);
+    let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+    let per_file = options.max_results_per_file.unwrap_or(usize::MAX);
+    let context = options.context_lines.min(MAX_CONTEXT_LINES);
+
+    let mut summary = ContentSearchSummary::default();
+    let mut pending = Vec::new();
+    let mut last_flush = Instant::now();
+
+    for entry in rules.walk(root).build().flatten() {
+        if cancel.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if entry.metadata().map(|m| m.len() > MAX_SEARCH_FILE_BYTES).unwrap_or(true) {
+            summary.files_skipped += 1;
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(entry.path()) else {
+            continue;
+        };
+        let detected = detect(&bytes, true);
+        if !detected.is_utf16() && is_binary(&bytes) {
+            summary.files_skipped += 1;
+            continue;
+        }
+
+        summary.files_searched += 1;
+        let limit = per_file.min(max_results - summary.match_count);
+        let matches = search_file(root, entry.path(), &decode(&bytes, detected), matcher, context, limit);
+        if !matches.is_empty() {
+            summary.files_matched += 1;
+            summary.match_count += matches.len();
+            pending.extend(matches);
+        }
+
+        if pending.len() >= BATCH_SIZE || (!pending.is_empty() && last_flush.elapsed() >= BATCH_INTERVAL) {
+            on_batch(std::mem::take(&mut pending));
+            last_flush = Instant::now();
+        }
+        if summary.match_count >= max_results {
+            summary.truncated = true;
+            break;
+        }
+    }
+
+    if !pending.is_empty() {
+        on_batch(pending);
+    }
+    summary.elapsed_ms = started.elapsed().as_millis() as u64;
+    summary
+}
+
+/// 搜索单个文件，最多返回 `limit` 个匹配行
+fn search_file(root: &Path, path: &Path, content: &str, matcher: &ContentMatcher, context: usize, limit: usize) -> Vec<ContentMatch> {
+    if limit == 0 || !matcher.regex.is_match(content) {
+        return Vec::new();
+    }
+
+    let lines: Vec<&str> = content.split('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).collect();
+    let relative_path = path.strip_prefix(root)
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_else(|_| path.to_string_lossy().to_string());
+    let to_owned = |range: &[&str]| range.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+
+    let mut matches = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let found = matcher.find_in(line);
+        if found.is_empty() {
+            continue;
+        }
+        let ranges: Vec<[usize; 2]> = found.iter()
+            .map(|&(start, end)| [utf16_len(&line[..start]), utf16_len(&line[..end])])
+            .collect();
+        matches.push(ContentMatch {
+            path: path.to_string_lossy().to_string(),
+            relative_path: relative_path.clone(),
+            line: index + 1,
+            column: ranges[0][0] + 1,
+            text: line.to_string(),
+            ranges,
+            before: to_owned(&lines[index.saturating_sub(context)..index]),
+            after: to_owned(&lines[index + 1..(index + 1 + context).min(lines.len())]),
+        });
+        if matches.len() >= limit {
+            break;
+        }
+    }
+    matches
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::TempDir::with_prefix("content-search-").unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("dist")).unwrap();
+        fs::write(root.join(".gitignore"), "dist/\n").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {\r\n    let 名字 = Foo::new();\r\n    foo_bar(Foo);\r\n}\r\n").unwrap();
+        fs::write(root.join("src/lib.rs"), "pub struct Foo;\n").unwrap();
+        fs::write(root.join("dist/bundle.js"), "Foo\n").unwrap();
+        fs::write(root.join("logo.png"), b"\x89PNG\0\0Foo").unwrap();
+        dir
+    }
+
+    fn run(root: &Path, query: &str, options: &ContentSearchOptions) -> (Vec<ContentMatch>, ContentSearchSummary) {
+        let matcher = ContentMatcher::new(query, options).unwrap();
+        let mut all = Vec::new();
+        let summary = search_workspace(root, &matcher, options, &[], &CancellationToken::new(), |batch| all.extend(batch));
+        (all, summary)
+    }
+
+    #[test]
+    fn finds_matches_with_options() {
+        let dir = workspace();
+        let root = dir.path();
+
+        let options = ContentSearchOptions { whole_word: true, context_lines: 1, ..Default::default() };
+        let (matches, summary) = run(root, "foo", &options);
+        let lines: Vec<(String, usize, usize)> = matches.iter()
+            .map(|m| (m.relative_path.clone(), m.line, m.column))
+            .collect();
+        assert_eq!(lines, vec![
+            ("src/lib.rs".to_string(), 1, 12),
+            ("src/main.rs".to_string(), 2, 14),
+            ("src/main.rs".to_string(), 3, 13),
+        ]);
+        assert_eq!(matches[1].text, "    let 名字 = Foo::new();");
+        assert_eq!(matches[1].ranges, vec![[13, 16]]);
+        assert_eq!(matches[1].before, vec!["fn main() {"]);
+        assert_eq!(matches[1].after, vec!["    foo_bar(Foo);"]);
+        assert_eq!((summary.files_searched, summary.files_matched, summary.files_skipped), (3, 2, 1));
+
+        let options = ContentSearchOptions { regex: true, case_sensitive: true, ..Default::default() };
+        let (matches, _) = run(root, r"foo_\w+", &options);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].ranges, vec![[4, 11]]);
+
+        let options = ContentSearchOptions { include: vec!["*.rs".to_string()], exclude: vec!["lib.rs".to_string()],
+                                             max_results: Some(1), ..Default::default() };
+        let (matches, summary) = run(root, "foo", &options);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].relative_path, "src/main.rs");
+        assert!(summary.truncated);
+
+        assert!(ContentMatcher::new("(", &ContentSearchOptions { regex: true, ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn replaces_with_captures_and_line_filter() {
+        let options = ContentSearchOptions { regex: true, case_sensitive: true, ..Default::default() };
+        let matcher = ContentMatcher::new(r"get_(\w+)\(\)", &options).unwrap();
+        let content = "a.get_name();\r\nb.get_id() + get_id();\r\nget_x()";
+
+        let (replaced, count) = matcher.replace(content, "${1}()", true, None);
+        assert_eq!(replaced, "a.name();\r\nb.id() + id();\r\nx()");
+        assert_eq!(count, 4);
+
+        let (replaced, count) = matcher.replace(content, "$1", false, Some(&HashSet::from([2])));
+        assert_eq!(replaced, "a.get_name();\r\nb.$1 + $1;\r\nget_x()");
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn stops_when_cancelled() {
+        let dir = workspace();
+        let root = dir.path();
+        let matcher = ContentMatcher::new("foo", &ContentSearchOptions::default()).unwrap();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let summary = search_workspace(root, &matcher, &ContentSearchOptions::default(), &[], &cancel, |_| {
+            panic!("取消后不应推送结果");
+        });
+        assert!(summary.cancelled);
+        assert_eq!(summary.files_searched, 0);
+    }
+}
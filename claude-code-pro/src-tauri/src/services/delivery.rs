@@ -0,0 +1,397 @@
+use crate::models::delivery::{DeliveryMetrics, EventBatch, EventFrame};
+use crate::models::events::StreamEvent;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+use tokio::task::JoinHandle;
+
+/// 推送参数
+#[derive(Debug, Clone)]
+pub struct DeliveryConfig {
+    /// 每个会话的通道容量（事件数）
+    pub capacity: usize,
+    /// 批次间隔
+    pub frame_interval: Duration,
+    /// 单批次最大事件数
+    pub max_batch: usize,
+    /// 事件序列化后超过该大小时截断
+    pub max_event_bytes: usize,
+    /// 截断后每个字符串保留的最大字节数
+    pub max_string_bytes: usize,
+    /// 每个会话缓存的完整内容条数（不少于 `max_batch`，保证同一批次的内容都能取回）
+    pub payload_cache: usize,
+    /// 排队超过该时间计为延迟
+    pub delay_threshold: Duration,
+}
+
+impl Default for DeliveryConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            frame_interval: Duration::from_millis(16),
+            max_batch: 64,
+            max_event_bytes: 64 * 1024,
+            max_string_bytes: 8 * 1024,
+            payload_cache: 128,
+            delay_threshold: Duration::from_millis(250),
+        }
+    }
+}
+
+#[derive(Default)]
+struct DeliveryShared {
+    next_seq: u64,
+    metrics: DeliveryMetrics,
+    /// 被截断事件的完整 JSON（seq, json）
+    payloads: VecDeque<(u64, String)>,
+}
+
+/// 会话推送状态句柄
+///
+/// 保存序号、统计和截断事件的完整内容，同一会话的多轮对话共用。
+#[derive(Clone, Default)]
+pub struct DeliveryHandle {
+    inner: Arc<Mutex<DeliveryShared>>,
+}
+
+impl DeliveryHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 当前统计
+    pub fn metrics(&self) -> DeliveryMetrics {
+        self.with(|shared| shared.metrics.clone()).unwrap_or_default()
+    }
+
+    /// 获取被截断事件的完整 JSON
+    pub fn payload(&self, seq: u64) -> Option<String> {
+        self.with(|shared| {
+            shared.payloads
+                .iter()
+                .find(|(s, _)| *s == seq)
+                .map(|(_, json)| json.clone())
+        })
+        .flatten()
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut DeliveryShared) -> T) -> Option<T> {
+        self.inner.lock().ok().map(|mut shared| f(&mut shared))
+    }
+}
+
+struct Queued {
+    seq: u64,
+    event: StreamEvent,
+    enqueued: Instant,
+}
+
+/// 读取端使用的发送器
+///
+/// 通道满时异步等待（向 CLI 输出反向施压），并计入 backpressure_waits。
+#[derive(Clone)]
+pub struct EventSender {
+    tx: Sender<Queued>,
+    handle: DeliveryHandle,
+}
+
+impl EventSender {
+    /// 发送一个事件，通道已关闭时返回 false
+    pub async fn send(&self, event: StreamEvent) -> bool {
+        let Some(seq) = self.handle.with(|shared| {
+            shared.next_seq += 1;
+            shared.next_seq
+        }) else {
+            return false;
+        };
+        let queued = Queued { seq, event, enqueued: Instant::now() };
+
+        let result = match self.tx.try_send(queued) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(queued)) => {
+                self.handle.with(|shared| shared.metrics.backpressure_waits += 1);
+                self.tx.send(queued).await.map_err(|_| ())
+            }
+            Err(TrySendError::Closed(_)) => Err(()),
+        };
+
+        if result.is_err() {
+            self.handle.with(|shared| shared.metrics.frames_dropped += 1);
+            return false;
+        }
+        true
+    }
+}
+
+/// 启动会话的推送任务
+///
+/// `sink` 返回 false 表示推送失败，该批次计为丢弃。
+/// 发送器全部释放后，任务推送完剩余事件再退出。
+pub fn spawn_delivery<S>(
+    session_id: String,
+    handle: DeliveryHandle,
+    config: DeliveryConfig,
+    mut sink: S,
+) -> (EventSender, JoinHandle<()>)
+where
+    S: FnMut(EventBatch) -> bool + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::channel(config.capacity.max(1));
+    let sender = EventSender { tx, handle: handle.clone() };
+
+    let task = tokio::spawn(async move {
+        while let Some((queued, disconnected)) = next_batch(&mut rx, &config).await {
+            let batch = build_batch(&session_id, &handle, &config, queued);
+            let count = batch.frames.len() as u64;
+            let delivered = sink(batch);
+
+            handle.with(|shared| {
+                if delivered {
+                    shared.metrics.frames_sent += count;
+                    shared.metrics.batches_sent += 1;
+                } else {
+                    shared.metrics.frames_dropped += count;
+                }
+            });
+
+            if disconnected {
+                break;
+            }
+        }
+        eprintln!("[spawn_delivery] 会话 {} 推送结束", session_id);
+    });
+
+    (sender, task)
+}
+
+/// 等待下一批事件：先等第一个，之后在批次间隔内尽量多取
+async fn next_batch(rx: &mut Receiver<Queued>, config: &DeliveryConfig) -> Option<(Vec<Queued>, bool)> {
+    let first = rx.recv().await?;
+    let deadline = tokio::time::Instant::now() + config.frame_interval;
+    let mut batch = vec![first];
+
+    while batch.len() < config.max_batch {
+        match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Some(queued)) => batch.push(queued),
+            Ok(None) => return Some((batch, true)),
+            Err(_) => break,
+        }
+    }
+
+    Some((batch, false))
+}
+
+/// 序列化事件，必要时截断并缓存完整内容
+///
+/// 超过 `max_event_bytes` 的事件先截断其中的长字符串，仍然超出时只推送占位内容。
+///
+/// 序列化和截断在锁外完成，锁内只更新统计和缓存。
+fn build_batch(
+    session_id: &str,
+    handle: &DeliveryHandle,
+    config: &DeliveryConfig,
+    queued: Vec<Queued>,
+) -> EventBatch {
+    let now = Instant::now();
+    let batch_size = queued.len();
+    let mut frames = Vec::with_capacity(batch_size);
+    let mut latencies = Vec::with_capacity(batch_size);
+    let mut payloads = Vec::new();
+
+    for item in queued {
+        latencies.push(now.duration_since(item.enqueued));
+
+        let mut event = serde_json::to_value(&item.event).unwrap_or(Value::Null);
+        let size = json_len(&event);
+        let truncated = size > config.max_event_bytes;
+        if truncated {
+            payloads.push((item.seq, event.to_string()));
+            // 由大量短字符串组成的事件没有可截断的内容，或截断后仍超出上限
+            if !truncate_strings(&mut event, config.max_string_bytes) || json_len(&event) > config.max_event_bytes {
+                event = stub(&event, size);
+            }
+        }
+        frames.push(EventFrame { seq: item.seq, event, truncated });
+    }
+
+    handle.with(|shared| {
+        let metrics = &mut shared.metrics;
+        metrics.max_batch_size = metrics.max_batch_size.max(batch_size);
+        for latency in latencies {
+            metrics.max_latency_ms = metrics.max_latency_ms.max(latency.as_millis() as u64);
+            if latency > config.delay_threshold {
+                metrics.frames_delayed += 1;
+            }
+        }
+
+        metrics.frames_truncated += payloads.len() as u64;
+        shared.payloads.extend(payloads);
+        while shared.payloads.len() > config.payload_cache.max(config.max_batch) {
+            shared.payloads.pop_front();
+        }
+    });
+
+    EventBatch { session_id: session_id.to_string(), frames }
+}
+
+/// 序列化后的字节数（不分配字符串）
+fn json_len(value: &Value) -> usize {
+    struct Counter(usize);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+/// 截断后仍然过大的事件只保留类型，完整内容通过 fetch_event_payload 获取
+fn stub(event: &Value, bytes: usize) -> Value {
+    serde_json::json!({
+        "type": event.get("type").cloned().unwrap_or(Value::Null),
+        "omitted_bytes": bytes,
+    })
+}
+
+/// 截断过长的字符串，返回是否有截断
+fn truncate_strings(value: &mut Value, max_bytes: usize) -> bool {
+    match value {
+        Value::String(s) if s.len() > max_bytes => {
+            let mut end = max_bytes;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            let removed = s.len() - end;
+            s.truncate(end);
+            s.push_str(&format!("\n…[已截断 {} 字节]", removed));
+            true
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .fold(false, |acc, item| truncate_strings(item, max_bytes) | acc),
+        Value::Object(map) => map
+            .values_mut()
+            .fold(false, |acc, item| truncate_strings(item, max_bytes) | acc),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn text(s: &str) -> StreamEvent {
+        StreamEvent::TextDelta { text: s.to_string() }
+    }
+
+    #[tokio::test]
+    async fn batches_and_numbers_events() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let handle = DeliveryHandle::new();
+        let config = DeliveryConfig { frame_interval: Duration::from_secs(5), ..DeliveryConfig::default() };
+        let (sender, task) = spawn_delivery("s-1".to_string(), handle.clone(), config, move |batch| {
+            tx.send(batch).is_ok()
+        });
+
+        for s in ["a", "b", "c"] {
+            assert!(sender.send(text(s)).await);
+        }
+        drop(sender);
+        task.await.unwrap();
+
+        // 发送端释放后立即推送，不等待批次间隔
+        let batch = rx.recv().await.unwrap();
+        assert!(rx.recv().await.is_none());
+        let seqs: Vec<u64> = batch.frames.iter().map(|f| f.seq).collect();
+        assert_eq!(seqs, [1, 2, 3]);
+        assert_eq!(batch.frames[1].event, json!({ "type": "text_delta", "text": "b" }));
+
+        let metrics = handle.metrics();
+        assert_eq!((metrics.frames_sent, metrics.batches_sent, metrics.frames_dropped), (3, 1, 0));
+    }
+
+    #[tokio::test]
+    async fn oversize_events_are_truncated_and_cached() {
+        let handle = DeliveryHandle::new();
+        let config = DeliveryConfig { max_event_bytes: 100, max_string_bytes: 10, ..DeliveryConfig::default() };
+        let big = "日志".repeat(100);
+        let (sender, task) = spawn_delivery("s-1".to_string(), handle.clone(), config, |batch| {
+            let frame = &batch.frames[0];
+            assert!(frame.truncated);
+            let text = frame.event["text"].as_str().unwrap();
+            assert!(text.starts_with("日志日") && text.contains("已截断"));
+            true
+        });
+
+        sender.send(text(&big)).await;
+        drop(sender);
+        task.await.unwrap();
+
+        let full: Value = serde_json::from_str(&handle.payload(1).unwrap()).unwrap();
+        assert_eq!(full["text"], big.as_str());
+        assert!(handle.payload(2).is_none());
+        assert_eq!(handle.metrics().frames_truncated, 1);
+    }
+
+    #[tokio::test]
+    async fn oversize_events_of_short_strings_are_stubbed() {
+        let handle = DeliveryHandle::new();
+        let config = DeliveryConfig { max_event_bytes: 100, max_string_bytes: 10, ..DeliveryConfig::default() };
+        let items: Vec<Value> = (0..50).map(|i| json!({ "type": "text", "text": format!("line {}", i) })).collect();
+        let event = StreamEvent::User { message: json!({ "content": [{ "type": "tool_result", "content": items }] }) };
+        let size = serde_json::to_string(&event).unwrap().len();
+        let (sender, task) = spawn_delivery("s-1".to_string(), handle.clone(), config, move |batch| {
+            let frame = &batch.frames[0];
+            assert!(frame.truncated);
+            assert_eq!(frame.event, json!({ "type": "user", "omitted_bytes": size }));
+            true
+        });
+
+        sender.send(event.clone()).await;
+        drop(sender);
+        task.await.unwrap();
+
+        let full: Value = serde_json::from_str(&handle.payload(1).unwrap()).unwrap();
+        assert_eq!(full, serde_json::to_value(&event).unwrap());
+    }
+
+    #[tokio::test]
+    async fn full_channel_applies_backpressure() {
+        let handle = DeliveryHandle::new();
+        let config = DeliveryConfig { capacity: 1, ..DeliveryConfig::default() };
+        let (sender, task) = spawn_delivery("s-1".to_string(), handle.clone(), config, |_| true);
+
+        // 单线程运行时中推送任务尚未运行，第二个事件必须等待
+        for s in ["a", "b", "c", "d"] {
+            sender.send(text(s)).await;
+        }
+        drop(sender);
+        task.await.unwrap();
+
+        let metrics = handle.metrics();
+        assert_eq!(metrics.frames_sent, 4);
+        assert!(metrics.backpressure_waits >= 1);
+    }
+
+    #[tokio::test]
+    async fn failed_sink_counts_dropped() {
+        let handle = DeliveryHandle::new();
+        let (sender, task) = spawn_delivery("s-1".to_string(), handle.clone(), DeliveryConfig::default(), |_| false);
+        sender.send(text("a")).await;
+        drop(sender);
+        task.await.unwrap();
+        assert_eq!(handle.metrics().frames_dropped, 1);
+    }
+}
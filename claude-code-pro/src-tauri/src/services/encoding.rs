@@ -0,0 +1,207 @@
+use crate::models::file::LineEnding;
+use encoding_rs::{Encoding, GBK, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// 检测二进制文件时读取的字节数
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+/// 检测到的编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detected {
+    pub encoding: &'static Encoding,
+
+    /// BOM 的字节数（没有 BOM 时为 0）
+    pub bom_len: usize,
+}
+
+impl Detected {
+    pub fn is_utf16(&self) -> bool {
+        self.encoding == UTF_16LE || self.encoding == UTF_16BE
+    }
+}
+
+/// 前 8KB 含 NUL 字节即视为二进制（调用方需先排除 UTF-16）
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+/// 检测编码：BOM（UTF-8 / UTF-16）→ 合法 UTF-8 → GBK / Shift_JIS → Latin-1（windows-1252）
+///
+/// `sample` 为文件开头的内容，`complete` 为 false 时末尾可能截断在字符中间。
+pub fn detect(sample: &[u8], complete: bool) -> Detected {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(sample) {
+        return Detected { encoding, bom_len };
+    }
+    let plain = |encoding| Detected { encoding, bom_len: 0 };
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => return plain(UTF_8),
+        // 只是末尾被截断
+        Err(e) if !complete && e.error_len().is_none() => return plain(UTF_8),
+        Err(_) => {}
+    }
+
+    // 截断的样本只检测到最后一个换行为止，避免末尾的半个字符导致解码失败
+    let sample = match sample.iter().rposition(|b| *b == b'\n') {
+        Some(end) if !complete => &sample[..=end],
+        _ => sample,
+    };
+    let decodes = |encoding: &'static Encoding| encoding.decode_without_bom_handling_and_without_replacement(sample);
+    let gbk = decodes(GBK).map(|_| gbk_score(sample));
+    let sjis = decodes(SHIFT_JIS).map(|text| cjk_score(&text));
+
+    // 西欧文本也常能按 GBK / Shift_JIS 解码，要求得分与非 ASCII 字节数成比例
+    // （正常中日文每个字符占两个非 ASCII 字节，至少得 1 分）
+    let non_ascii = sample.iter().filter(|b| !b.is_ascii()).count() as i64;
+    let threshold = (non_ascii / 4).max(1);
+    match (gbk, sjis) {
+        (gbk, Some(sjis)) if sjis >= threshold && gbk.is_none_or(|gbk| sjis > gbk) => plain(SHIFT_JIS),
+        (Some(gbk), _) if gbk >= threshold => plain(GBK),
+        _ => plain(WINDOWS_1252),
+    }
+}
+
+/// GBK 字节序列像正常中文的程度：GB2312 的汉字区和符号区加分，扩展区（多为生僻字，常由西欧文本误解码而来）减分
+fn gbk_score(bytes: &[u8]) -> i64 {
+    let mut score = 0;
+    let mut iter = bytes.iter().copied();
+    while let Some(lead) = iter.next() {
+        if lead.is_ascii() {
+            continue;
+        }
+        if lead == 0x80 {
+            score -= 3;
+            continue;
+        }
+        let trail = iter.next().unwrap_or(0);
+        score += match (lead, trail) {
+            (0xB0..=0xF7 | 0xA1..=0xA9, 0xA1..=0xFE) => 1,
+            _ => -3,
+        };
+    }
+    score
+}
+
+/// 解码后文本像正常日文的程度：常用汉字、假名和全角标点加分，半角片假名、扩展区汉字和私用区减分
+fn cjk_score(text: &str) -> i64 {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x3040..=0x30FF => 2,
+            0x4E00..=0x9FFF | 0x3000..=0x303F | 0xFF01..=0xFF5E => 1,
+            0xFF61..=0xFF9F | 0x3400..=0x4DBF | 0xE000..=0xF8FF => -3,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// 按检测结果解码（跳过 BOM，无法解码的字节替换为 U+FFFD）
+pub fn decode<'a>(bytes: &'a [u8], detected: Detected) -> Cow<'a, str> {
+    let bytes = &bytes[detected.bom_len.min(bytes.len())..];
+    detected.encoding.decode_without_bom_handling(bytes).0
+}
+
+/// 按原编码编码文本（保留 BOM），含有原编码无法表示的字符时返回 None
+pub fn encode(text: &str, detected: Detected) -> Option<Vec<u8>> {
+    let bom: &[u8] = match (detected.bom_len > 0, detected.encoding) {
+        (false, _) => &[],
+        (true, e) if e == UTF_16LE => &[0xFF, 0xFE],
+        (true, e) if e == UTF_16BE => &[0xFE, 0xFF],
+        (true, _) => &[0xEF, 0xBB, 0xBF],
+    };
+    let mut bytes = bom.to_vec();
+    // encoding_rs 不支持编码为 UTF-16，手动转换
+    if detected.encoding == UTF_16LE {
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    } else if detected.encoding == UTF_16BE {
+        bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+    } else {
+        let (encoded, _, had_errors) = detected.encoding.encode(text);
+        if had_errors {
+            return None;
+        }
+        bytes.extend_from_slice(&encoded);
+    }
+    Some(bytes)
+}
+
+/// 换行符风格
+pub fn line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    match (crlf, lf) {
+        (0, 0) => LineEnding::None,
+        (_, 0) => LineEnding::Crlf,
+        (0, _) => LineEnding::Lf,
+        _ => LineEnding::Mixed,
+    }
+}
+
+/// `xxd` 风格的十六进制预览，`offset` 为第一个字节在文件中的位置
+pub fn hex_dump(bytes: &[u8], offset: u64) -> String {
+    let mut out = String::new();
+    for (index, chunk) in bytes.chunks(16).enumerate() {
+        let _ = write!(out, "{:08x}  ", offset + index as u64 * 16);
+        for column in 0..16 {
+            match chunk.get(column) {
+                Some(byte) => { let _ = write!(out, "{:02x} ", byte); }
+                None => out.push_str("   "),
+            }
+            if column == 7 {
+                out.push(' ');
+            }
+        }
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }));
+        out.push_str("|\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_common_encodings() {
+        let text = "中文注释：编码检测\n";
+        assert_eq!(detect(text.as_bytes(), true), Detected { encoding: UTF_8, bom_len: 0 });
+        assert_eq!(detect(&[b"\xEF\xBB\xBF", text.as_bytes()].concat(), true).bom_len, 3);
+
+        let gbk = GBK.encode(text).0;
+        assert_eq!(detect(&gbk, true).encoding, GBK);
+        let sjis = SHIFT_JIS.encode("日本語のコメント、テストです\n").0;
+        assert_eq!(detect(&sjis, true).encoding, SHIFT_JIS);
+        assert_eq!(detect(b"caf\xE9 cr\xE8me\n", true).encoding, WINDOWS_1252);
+        // 能按 GBK 解码的西欧文本
+        assert!(GBK.decode_without_bom_handling_and_without_replacement(b"Se\xF1or Garc\xEDa").is_some());
+        assert_eq!(detect(b"Se\xF1or Garc\xEDa", true).encoding, WINDOWS_1252);
+        assert_eq!(detect(b"Gr\xFC\xDFe aus M\xFCnchen", true).encoding, WINDOWS_1252);
+
+        // 截断在多字节字符中间的样本
+        let utf8 = "注释".repeat(100);
+        assert_eq!(detect(&utf8.as_bytes()[..100], false).encoding, UTF_8);
+        let text = "编码\n".repeat(50);
+        let gbk = GBK.encode(&text).0;
+        assert_eq!(detect(&gbk[..gbk.len() - 2], false).encoding, GBK);
+
+        let utf16 = encode("a\r\nb", Detected { encoding: UTF_16LE, bom_len: 2 }).unwrap();
+        assert_eq!(utf16, b"\xFF\xFEa\0\r\0\n\0b\0");
+        let detected = detect(&utf16, true);
+        assert!(detected.is_utf16());
+        assert_eq!(decode(&utf16, detected), "a\r\nb");
+        assert_eq!(line_ending(&decode(&utf16, detected)), LineEnding::Crlf);
+    }
+
+    #[test]
+    fn encodes_back_and_dumps_hex() {
+        let gbk = Detected { encoding: GBK, bom_len: 0 };
+        assert_eq!(encode("编码", gbk).unwrap(), GBK.encode("编码").0.to_vec());
+        assert!(encode("表情 😀", gbk).is_none());
+        assert_eq!(line_ending("a\nb\r\n"), LineEnding::Mixed);
+
+        let dump = hex_dump(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR!", 16);
+        assert_eq!(dump, "00000010  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|\n\
+                          00000020  21                                                |!|\n");
+    }
+}
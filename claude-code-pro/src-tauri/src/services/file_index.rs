@@ -0,0 +1,631 @@
+use crate::models::events::StreamEvent;
+use crate::models::file::{FsChange, FsChangeKind};
+use crate::services::walker::WalkRules;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 未被监听的目录，索引在此时间后重建
+pub const INDEX_TTL: Duration = Duration::from_secs(30);
+/// Agent 编辑的加分在此时间内线性衰减到 0
+pub const RECENT_WINDOW: Duration = Duration::from_secs(30 * 60);
+/// 最多记住的最近编辑数
+const MAX_RECENT: usize = 200;
+/// 除监听中的工作区外，最多缓存的索引数
+const MAX_CACHED_INDEXES: usize = 4;
+
+// 评分参数（参考 fzf）
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = 10;
+const BONUS_DELIMITER: i64 = 8;
+const BONUS_CAMEL: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+const BONUS_BASENAME: i64 = 24;
+const BONUS_RECENT: i64 = 48;
+
+/// 会修改文件的工具及其路径参数
+const EDIT_TOOLS: [(&str, &str); 4] = [
+    ("Edit", "file_path"),
+    ("MultiEdit", "file_path"),
+    ("Write", "file_path"),
+    ("NotebookEdit", "notebook_path"),
+];
+
+/// 模糊搜索结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexMatch {
+    /// 相对根目录的路径（`/` 分隔）
+    pub path: String,
+    pub score: i64,
+    /// 命中区间 `[start, end)`，UTF-16 偏移，可直接用于前端字符串截取
+    pub highlights: Vec<[usize; 2]>,
+}
+
+struct IndexEntry {
+    path: Box<str>,
+    lower: Box<[u8]>,
+    name_start: usize,
+}
+
+impl IndexEntry {
+    fn new(path: String) -> Self {
+        let lower = path.to_ascii_lowercase().into_bytes().into_boxed_slice();
+        let name_start = path.rfind('/').map(|i| i + 1).unwrap_or(0);
+        Self { path: path.into_boxed_str(), lower, name_start }
+    }
+}
+
+/// 按根目录缓存的文件索引
+///
+/// 超出容量时淘汰最早建立的索引，监听中的工作区索引不会被其他目录挤掉。
+/// 索引在锁外建立，期间收到的变更先暂存，放入索引时再补上。
+#[derive(Default)]
+pub struct IndexCache {
+    indexes: HashMap<PathBuf, FileIndex>,
+    building: HashMap<PathBuf, PendingBuild>,
+}
+
+/// 建立中的索引：进行中的构建数及期间收到的变更
+#[derive(Default)]
+struct PendingBuild {
+    builds: usize,
+    changes: Vec<FsChange>,
+}
+
+impl IndexCache {
+    /// 获取 `root` 仍然有效的索引
+    pub fn get_fresh(&self, root: &Path, excludes: &[String], watched: bool) -> Option<&FileIndex> {
+        self.indexes.get(root).filter(|index| index.is_fresh(root, excludes, watched))
+    }
+
+    /// 标记开始在锁外建立 `root` 的索引，之后须调用 [`IndexCache::insert`] 或 [`IndexCache::abort_build`]
+    pub fn begin_build(&mut self, root: &Path) {
+        self.building.entry(root.to_path_buf()).or_default().builds += 1;
+    }
+
+    /// 放弃建立中的索引
+    pub fn abort_build(&mut self, root: &Path) {
+        self.end_build(root);
+    }
+
+    /// 放入新建的索引并补上建立期间的变更，`watched_root` 为当前监听的工作区
+    pub fn insert(&mut self, mut index: FileIndex, watched_root: Option<&Path>) -> &FileIndex {
+        let root = index.root.clone();
+        if let Some(changes) = self.end_build(&root) {
+            // 变更按磁盘现状应用，重复应用遍历前已生效的变更不影响结果
+            index.apply(&changes);
+        }
+        self.indexes.insert(root.clone(), index);
+
+        let limit = MAX_CACHED_INDEXES + usize::from(watched_root.is_some_and(|w| self.indexes.contains_key(w)));
+        while self.indexes.len() > limit {
+            let oldest = self.indexes.values()
+                .filter(|index| index.root != root && Some(index.root.as_path()) != watched_root)
+                .min_by_key(|index| index.built_at)
+                .map(|index| index.root.clone());
+            match oldest {
+                Some(oldest) => self.indexes.remove(&oldest),
+                None => break,
+            };
+        }
+        &self.indexes[&root]
+    }
+
+    /// 将文件监听的变更应用到对应目录的索引
+    pub fn apply(&mut self, root: &Path, changes: &[FsChange]) {
+        if let Some(index) = self.indexes.get_mut(root) {
+            index.apply(changes);
+        }
+        if let Some(pending) = self.building.get_mut(root) {
+            pending.changes.extend_from_slice(changes);
+        }
+    }
+
+    /// 结束一次构建，返回需要补上的变更
+    fn end_build(&mut self, root: &Path) -> Option<Vec<FsChange>> {
+        let pending = self.building.get_mut(root)?;
+        pending.builds = pending.builds.saturating_sub(1);
+        if pending.builds == 0 {
+            self.building.remove(root).map(|pending| pending.changes)
+        } else {
+            Some(pending.changes.clone())
+        }
+    }
+}
+
+/// 工作区文件路径的内存索引
+///
+/// 只收录文件（遵循 [`WalkRules`]），通过 [`FileIndex::apply`] 跟随文件系统事件增量更新。
+pub struct FileIndex {
+    root: PathBuf,
+    excludes: Vec<String>,
+    rules: WalkRules,
+    entries: Vec<IndexEntry>,
+    known: HashSet<Box<str>>,
+    built_at: Instant,
+}
+
+impl FileIndex {
+    /// 遍历 `root` 建立索引
+    pub fn build(root: &Path, excludes: &[String]) -> Self {
+        let started = Instant::now();
+        let mut index = Self {
+            root: root.to_path_buf(),
+            excludes: excludes.to_vec(),
+            rules: WalkRules::new(root, excludes),
+            entries: Vec::new(),
+            known: HashSet::new(),
+            built_at: Instant::now(),
+        };
+        index.add_tree(root);
+        eprintln!("[FileIndex::build] {:?}: {} 个文件，耗时 {:?}", root, index.entries.len(), started.elapsed());
+        index
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 索引是否对应 `root` 和当前的排除规则；`watched` 为 false 时还要求未超过 [`INDEX_TTL`]
+    pub fn is_fresh(&self, root: &Path, excludes: &[String], watched: bool) -> bool {
+        self.root == root && self.excludes == excludes && (watched || self.built_at.elapsed() < INDEX_TTL)
+    }
+
+    /// 应用一批文件系统变更：先处理删除，再按磁盘现状补充新增
+    pub fn apply(&mut self, changes: &[FsChange]) {
+        let mut removed = HashSet::new();
+        let mut added = Vec::new();
+        for change in changes {
+            let path = PathBuf::from(&change.path);
+            match change.kind {
+                FsChangeKind::Created => added.push(path),
+                FsChangeKind::Removed => removed.extend(self.relative(&path)),
+                FsChangeKind::Renamed => {
+                    removed.extend(change.old_path.as_deref().and_then(|old| self.relative(Path::new(old))));
+                    added.push(path);
+                }
+                // 目录的修改事件（含事件丢失后的重新扫描）按整棵子树刷新
+                FsChangeKind::Modified if path.is_dir() => {
+                    removed.extend(self.relative(&path));
+                    added.push(path);
+                }
+                FsChangeKind::Modified => {}
+            }
+        }
+
+        if !removed.is_empty() {
+            self.remove_all(&removed);
+        }
+        for path in added {
+            self.add_tree(&path);
+        }
+    }
+
+    /// 模糊搜索，按得分从高到低返回最多 `limit` 个结果
+    ///
+    /// `recent` 为最近编辑的文件（绝对路径 -> 加分），空查询时只按最近编辑排序。
+    pub fn search(&self, query: &str, limit: usize, recent: &HashMap<PathBuf, i64>) -> Vec<IndexMatch> {
+        let query: Vec<u8> = query.bytes()
+            .filter(|b| !b.is_ascii_whitespace())
+            .map(|b| if b == b'\\' { b'/' } else { b.to_ascii_lowercase() })
+            .collect();
+        let recent: HashMap<String, i64> = recent.iter()
+            .filter_map(|(path, bonus)| Some((self.relative(path)?, *bonus)))
+            .collect();
+
+        let mut scored: Vec<(i64, &IndexEntry, Vec<usize>)> = if query.is_empty() {
+            self.entries.iter()
+                .filter_map(|entry| Some((*recent.get(&*entry.path)?, entry, Vec::new())))
+                .collect()
+        } else {
+            self.entries.iter()
+                .filter_map(|entry| {
+                    let (score, positions) = score_entry(entry, &query)?;
+                    let bonus = recent.get(&*entry.path).copied().unwrap_or(0);
+                    Some((score + bonus, entry, positions))
+                })
+                .collect()
+        };
+
+        let order = |a: &(i64, &IndexEntry, Vec<usize>), b: &(i64, &IndexEntry, Vec<usize>)| {
+            b.0.cmp(&a.0)
+                .then(a.1.path.len().cmp(&b.1.path.len()))
+                .then(a.1.path.cmp(&b.1.path))
+        };
+        if scored.len() > limit && limit > 0 {
+            scored.select_nth_unstable_by(limit - 1, order);
+        }
+        scored.truncate(limit);
+        scored.sort_unstable_by(order);
+
+        scored.into_iter()
+            .map(|(score, entry, positions)| IndexMatch {
+                path: entry.path.to_string(),
+                score,
+                highlights: highlight_ranges(&entry.path, &positions),
+            })
+            .collect()
+    }
+
+    /// `path` 相对根目录的 `/` 分隔路径（根目录本身为空字符串）
+    fn relative(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    fn insert(&mut self, path: &Path) {
+        let Some(relative) = self.relative(path).filter(|r| !r.is_empty()) else {
+            return;
+        };
+        if self.known.insert(relative.clone().into_boxed_str()) {
+            self.entries.push(IndexEntry::new(relative));
+        }
+    }
+
+    /// 加入 `path`（文件或整个目录）
+    fn add_tree(&mut self, path: &Path) {
+        let Ok(metadata) = path.symlink_metadata() else {
+            return;
+        };
+        if path != self.root && self.rules.is_excluded(path) {
+            return;
+        }
+        if metadata.is_file() {
+            self.insert(path);
+        } else if metadata.is_dir() {
+            let files: Vec<PathBuf> = self.rules.walk(path)
+                .build()
+                .flatten()
+                .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+                .map(|entry| entry.into_path())
+                .collect();
+            for file in files {
+                self.insert(&file);
+            }
+        }
+    }
+
+    /// 移除给定的相对路径及其下所有文件（空字符串表示全部）
+    fn remove_all(&mut self, removed: &HashSet<String>) {
+        let is_removed = |path: &str| {
+            removed.contains("")
+                || removed.contains(path)
+                || path.match_indices('/').any(|(i, _)| removed.contains(&path[..i]))
+        };
+        let known = &mut self.known;
+        self.entries.retain(|entry| {
+            let keep = !is_removed(&entry.path);
+            if !keep {
+                known.remove(&entry.path);
+            }
+            keep
+        });
+    }
+}
+
+/// 最近被 Agent 编辑过的文件
+#[derive(Default)]
+pub struct RecentEdits {
+    edits: HashMap<PathBuf, Instant>,
+}
+
+impl RecentEdits {
+    pub fn touch(&mut self, path: PathBuf) {
+        self.edits.insert(path, Instant::now());
+        if self.edits.len() > MAX_RECENT {
+            self.edits.retain(|_, at| at.elapsed() < RECENT_WINDOW);
+        }
+        if self.edits.len() > MAX_RECENT {
+            if let Some(oldest) = self.edits.iter().min_by_key(|(_, at)| **at).map(|(p, _)| p.clone()) {
+                self.edits.remove(&oldest);
+            }
+        }
+    }
+
+    /// 各文件的加分（越近越高，超过 [`RECENT_WINDOW`] 的不计）
+    pub fn bonuses(&self) -> HashMap<PathBuf, i64> {
+        self.edits.iter()
+            .filter_map(|(path, at)| {
+                let remaining = RECENT_WINDOW.checked_sub(at.elapsed())?;
+                let bonus = BONUS_RECENT * remaining.as_millis() as i64 / RECENT_WINDOW.as_millis() as i64;
+                Some((path.clone(), bonus.max(1)))
+            })
+            .collect()
+    }
+}
+
+/// 助手消息中编辑文件的工具调用所涉及的路径（相对路径按 `cwd` 解析）
+pub fn edited_paths(event: &StreamEvent, cwd: Option<&Path>) -> Vec<PathBuf> {
+    let StreamEvent::Assistant { message } = event else {
+        return Vec::new();
+    };
+    message.get("content")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("tool_use"))
+        .filter_map(|item| {
+            let name = item.get("name")?.as_str()?;
+            let (_, key) = EDIT_TOOLS.iter().find(|(tool, _)| *tool == name)?;
+            let path = PathBuf::from(item.get("input")?.get(*key)?.as_str()?);
+            Some(match cwd {
+                Some(cwd) if path.is_relative() => cwd.join(path),
+                _ => path,
+            })
+        })
+        .collect()
+}
+
+/// 计算一个条目的得分和命中位置（字节偏移），不匹配时返回 None
+///
+/// 查询不含 `/` 时优先在文件名内匹配并加分，否则在整个路径上匹配。
+fn score_entry(entry: &IndexEntry, query: &[u8]) -> Option<(i64, Vec<usize>)> {
+    if !is_subsequence(&entry.lower, query) {
+        return None;
+    }
+    let original = entry.path.as_bytes();
+    if !query.contains(&b'/') {
+        if let Some(positions) = align(&entry.lower[entry.name_start..], query) {
+            let positions: Vec<usize> = positions.into_iter().map(|p| p + entry.name_start).collect();
+            return Some((score_positions(original, &positions) + BONUS_BASENAME, positions));
+        }
+    }
+    let positions = align(&entry.lower, query)?;
+    Some((score_positions(original, &positions), positions))
+}
+
+fn is_subsequence(text: &[u8], query: &[u8]) -> bool {
+    let mut rest = text;
+    query.iter().all(|q| match rest.iter().position(|b| b == q) {
+        Some(i) => {
+            rest = &rest[i + 1..];
+            true
+        }
+        None => false,
+    })
+}
+
+/// fzf v1 风格的对齐：先向前找到最早的完整匹配，再从结尾向后收缩到最短窗口
+fn align(text: &[u8], query: &[u8]) -> Option<Vec<usize>> {
+    let mut end = 0;
+    let mut qi = 0;
+    for (i, b) in text.iter().enumerate() {
+        if *b == query[qi] {
+            qi += 1;
+            if qi == query.len() {
+                end = i;
+                break;
+            }
+        }
+    }
+    if qi < query.len() {
+        return None;
+    }
+
+    let mut positions = vec![0; query.len()];
+    let mut qi = query.len();
+    for i in (0..=end).rev() {
+        if text[i] == query[qi - 1] {
+            qi -= 1;
+            positions[qi] = i;
+            if qi == 0 {
+                break;
+            }
+        }
+    }
+    Some(positions)
+}
+
+/// 字符所在位置的边界加分（路径分隔符、`_-. ` 之后、驼峰和数字开头）
+fn boundary_bonus(text: &[u8], pos: usize) -> i64 {
+    let Some(&prev) = pos.checked_sub(1).map(|i| &text[i]) else {
+        return BONUS_BOUNDARY;
+    };
+    let current = text[pos];
+    match prev {
+        b'/' => BONUS_BOUNDARY,
+        b'_' | b'-' | b'.' | b' ' => BONUS_DELIMITER,
+        _ if prev.is_ascii_lowercase() && current.is_ascii_uppercase() => BONUS_CAMEL,
+        _ if !prev.is_ascii_digit() && current.is_ascii_digit() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+fn score_positions(text: &[u8], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    let mut run_bonus = 0;
+    for (i, &pos) in positions.iter().enumerate() {
+        let mut bonus = boundary_bonus(text, pos);
+        if i == 0 {
+            bonus *= BONUS_FIRST_CHAR_MULTIPLIER;
+            run_bonus = bonus;
+        } else if pos == positions[i - 1] + 1 {
+            // 连续匹配沿用这一段开头的边界加分
+            run_bonus = run_bonus.max(bonus).max(BONUS_CONSECUTIVE);
+            bonus = run_bonus;
+        } else {
+            let gap = (pos - positions[i - 1] - 1) as i64;
+            score += SCORE_GAP_START + SCORE_GAP_EXTENSION * (gap - 1);
+            run_bonus = bonus;
+        }
+        score += SCORE_MATCH + bonus;
+    }
+    score
+}
+
+/// 将字节位置合并为 UTF-16 偏移区间
+fn highlight_ranges(path: &str, positions: &[usize]) -> Vec<[usize; 2]> {
+    let mut ranges: Vec<[usize; 2]> = Vec::new();
+    let mut next = positions.iter().peekable();
+    let mut utf16 = 0;
+    for (byte, ch) in path.char_indices() {
+        let width = ch.len_utf16();
+        let mut hit = false;
+        while next.peek().is_some_and(|&&p| p < byte + ch.len_utf8()) {
+            next.next();
+            hit = true;
+        }
+        if hit {
+            match ranges.last_mut() {
+                Some(last) if last[1] == utf16 => last[1] += width,
+                _ => ranges.push([utf16, utf16 + width]),
+            }
+        }
+        utf16 += width;
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn index_of(paths: &[&str]) -> FileIndex {
+        let root = std::env::temp_dir().join(format!("file-index-{}", uuid::Uuid::new_v4()));
+        let mut index = FileIndex::build(&root, &[]);
+        for path in paths {
+            index.entries.push(IndexEntry::new(path.to_string()));
+        }
+        index
+    }
+
+    fn top(index: &FileIndex, query: &str, recent: &HashMap<PathBuf, i64>) -> Vec<String> {
+        index.search(query, 3, recent).into_iter().map(|m| m.path).collect()
+    }
+
+    #[test]
+    fn cache_keeps_watched_index() {
+        let watched = index_of(&["src/main.rs"]);
+        let watched_root = watched.root.clone();
+        let mut cache = IndexCache::default();
+        cache.insert(watched, Some(&watched_root));
+
+        let mut others = Vec::new();
+        for _ in 0..MAX_CACHED_INDEXES + 2 {
+            let index = index_of(&[]);
+            others.push(index.root.clone());
+            cache.insert(index, Some(&watched_root));
+        }
+
+        assert_eq!(cache.indexes.len(), MAX_CACHED_INDEXES + 1);
+        assert!(cache.get_fresh(&watched_root, &[], true).is_some());
+        assert!(cache.get_fresh(&others[0], &[], false).is_none());
+        assert!(cache.get_fresh(others.last().unwrap(), &[], false).is_some());
+        assert!(cache.get_fresh(&watched_root, &["dist".to_string()], true).is_none());
+    }
+
+    #[test]
+    fn cache_replays_changes_during_build() {
+        let tmp = tempfile::TempDir::with_prefix("file-index-build-").unwrap();
+        let root = tmp.path();
+        fs::write(root.join("old.rs"), "").unwrap();
+
+        let mut cache = IndexCache::default();
+        cache.begin_build(root);
+        let index = FileIndex::build(root, &[]);
+
+        // 遍历结束后、放入缓存前发生的变更
+        fs::write(root.join("new.rs"), "").unwrap();
+        cache.apply(root, &[FsChange {
+            kind: FsChangeKind::Created,
+            path: root.join("new.rs").to_string_lossy().to_string(),
+            old_path: None,
+        }]);
+
+        let index = cache.insert(index, Some(root));
+        assert_eq!(top(index, "new", &HashMap::new()), vec!["new.rs"]);
+        assert!(cache.building.is_empty());
+    }
+
+    #[test]
+    fn ranks_boundaries_and_basenames_first() {
+        let index = index_of(&[
+            "docs/domain/README.md",
+            "src/commands/main_window.rs",
+            "src/main.rs",
+            "src/maintenance/mod.rs",
+            "src/services/fileIndex.ts",
+            "src/fast/file_index_stub.txt",
+        ]);
+        let none = HashMap::new();
+
+        assert_eq!(top(&index, "main", &none)[0], "src/main.rs");
+        assert_eq!(top(&index, "fi", &none)[0], "src/services/fileIndex.ts");
+        assert_eq!(top(&index, "src/mod", &none), vec!["src/maintenance/mod.rs"]);
+        assert!(top(&index, "xyz", &none).is_empty());
+
+        let hit = &index.search("fidx", 1, &none)[0];
+        assert_eq!(hit.path, "src/services/fileIndex.ts");
+        assert_eq!(hit.highlights, vec![[13, 14], [17, 18], [19, 20], [21, 22]]);
+
+        // 最近编辑的文件排在前面
+        let recent = HashMap::from([(index.root.join("src/maintenance/mod.rs"), BONUS_RECENT)]);
+        assert_eq!(top(&index, "main", &recent)[0], "src/maintenance/mod.rs");
+        assert_eq!(top(&index, "", &recent), vec!["src/maintenance/mod.rs"]);
+    }
+
+    #[test]
+    fn follows_filesystem_changes() {
+        let tmp = tempfile::TempDir::with_prefix("file-index-").unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        fs::write(root.join("src/nested/a.rs"), "").unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "").unwrap();
+
+        let mut index = FileIndex::build(root, &["node_modules".to_string()]);
+        assert_eq!(index.len(), 2);
+
+        let change = |kind, path: &str, old: Option<&str>| FsChange {
+            kind,
+            path: root.join(path).to_string_lossy().to_string(),
+            old_path: old.map(|o| root.join(o).to_string_lossy().to_string()),
+        };
+        fs::rename(root.join("src/nested"), root.join("src/moved")).unwrap();
+        fs::write(root.join("src/new.rs"), "").unwrap();
+        fs::write(root.join("node_modules/pkg/other.js"), "").unwrap();
+        fs::remove_file(root.join("src/lib.rs")).unwrap();
+        index.apply(&[
+            change(FsChangeKind::Renamed, "src/moved", Some("src/nested")),
+            change(FsChangeKind::Created, "src/new.rs", None),
+            change(FsChangeKind::Created, "node_modules/pkg/other.js", None),
+            change(FsChangeKind::Removed, "src/lib.rs", None),
+        ]);
+
+        let mut paths: Vec<&str> = index.entries.iter().map(|e| &*e.path).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["src/moved/a.rs", "src/new.rs"]);
+        assert!(index.is_fresh(root, &["node_modules".to_string()], false));
+        assert!(!index.is_fresh(root, &[], true));
+    }
+
+    #[test]
+    fn extracts_agent_edits() {
+        let event: StreamEvent = serde_json::from_value(serde_json::json!({
+            "type": "assistant",
+            "message": {"content": [
+                {"type": "tool_use", "name": "Edit", "input": {"file_path": "/w/src/a.rs"}},
+                {"type": "tool_use", "name": "NotebookEdit", "input": {"notebook_path": "nb.ipynb"}},
+                {"type": "tool_use", "name": "Read", "input": {"file_path": "/w/src/b.rs"}},
+                {"type": "text", "text": "done"}
+            ]}
+        })).unwrap();
+        assert_eq!(edited_paths(&event, Some(Path::new("/w"))), vec![
+            PathBuf::from("/w/src/a.rs"),
+            PathBuf::from("/w/nb.ipynb"),
+        ]);
+    }
+}
@@ -0,0 +1,599 @@
+use crate::error::{AppError, Result};
+use crate::models::file::{FileContent, FileVersion, LineEnding, ReadRange};
+use crate::services::encoding::{decode, detect, encode, hex_dump, is_binary, line_ending, Detected};
+use encoding_rs::UTF_8;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// 完整读取的大小上限，更大的文件只能按范围读取
+pub const MAX_READ_BYTES: u64 = 10 * 1024 * 1024;
+/// 检测编码时读取的文件开头字节数
+const SAMPLE_BYTES: u64 = 64 * 1024;
+/// 二进制文件十六进制预览的字节数
+const HEX_PREVIEW_BYTES: u64 = 4096;
+/// 按字节范围读取的最小长度：跳过开头的续字节后仍至少包含一个完整字符，保证每段都有进展
+const MIN_RANGE_BYTES: u64 = 8;
+
+/// 带版本检查的写入结果
+pub struct WriteOutcome {
+    /// 写入前的内容（文件原本不存在时为空）
+    pub previous: Option<Vec<u8>>,
+
+    /// 实际写入的内容（已按原文件换行符转换）
+    pub written: Vec<u8>,
+
+    /// 写入后的版本
+    pub version: FileVersion,
+}
+
+/// 内容的 SHA-256（十六进制）
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// 根据已读取的内容和文件元数据计算版本
+pub fn file_version(path: &Path, bytes: &[u8]) -> Result<FileVersion> {
+    Ok(FileVersion { hash: content_hash(bytes), mtime: mtime_ms(path)? })
+}
+
+/// 文件修改时间（Unix 毫秒，无法获取时为 0）
+pub fn mtime_ms(path: &Path) -> Result<u64> {
+    Ok(fs::metadata(path)?
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0))
+}
+
+/// 读取文件：检测编码和二进制，`range` 为空时完整读取（不超过 [`MAX_READ_BYTES`]）
+pub fn read_document(path: &Path, range: Option<&ReadRange>) -> Result<FileContent> {
+    let size = fs::metadata(path)?.len();
+    let Some(range) = range else {
+        if size > MAX_READ_BYTES {
+            return Err(AppError::InvalidPath("文件过大，超过10MB限制，请按范围读取".to_string()));
+        }
+        let bytes = fs::read(path)?;
+        let version = file_version(path, &bytes)?;
+        return Ok(document_from_bytes(&bytes, version));
+    };
+
+    let mut file = fs::File::open(path)?;
+    let sample = read_at(&mut file, 0, SAMPLE_BYTES)?;
+    let detected = detect(&sample, size <= SAMPLE_BYTES);
+    let binary = !detected.is_utf16() && is_binary(&sample);
+    let version = FileVersion { hash: String::new(), mtime: mtime_ms(path)? };
+    let mut document = empty_document(detected, binary, size, version);
+
+    if binary {
+        let offset = match range {
+            ReadRange::Bytes { offset, .. } => (*offset).min(size),
+            ReadRange::Lines { .. } => 0,
+        };
+        let bytes = read_at(&mut file, offset, HEX_PREVIEW_BYTES)?;
+        document.hex_preview = Some(hex_dump(&bytes, offset));
+        document.offset = offset;
+        document.length = bytes.len() as u64;
+    } else {
+        // 分段内容不含 BOM
+        let plain = Detected { bom_len: 0, ..detected };
+        match range {
+            ReadRange::Bytes { offset, length } => {
+                let (start, bytes) = read_byte_range(&mut file, size, detected, *offset, (*length).min(MAX_READ_BYTES))?;
+                document.content = decode(&bytes, plain).into_owned();
+                document.offset = start;
+                document.length = bytes.len() as u64;
+            }
+            ReadRange::Lines { start, end } => {
+                let lines = read_line_range(&mut file, size, detected, (*start).max(1), *end)?;
+                document.content = decode(&lines.bytes, plain).into_owned();
+                document.offset = lines.offset;
+                document.length = lines.bytes.len() as u64;
+                document.start_line = lines.first;
+                document.end_line = lines.last;
+                document.total_lines = Some(lines.total);
+            }
+        }
+        document.line_ending = line_ending(&document.content);
+    }
+
+    let whole = document.offset <= detected.bom_len as u64 && document.offset + document.length >= size;
+    document.partial = !whole;
+    if whole && document.total_lines.is_none() && !binary {
+        document.total_lines = Some(document.content.lines().count());
+    }
+    // 只有完整读取才带上 hash，分段内容按这个版本保存会截断文件，写入时会报告冲突
+    if whole && size <= MAX_READ_BYTES {
+        document.version = file_version(path, &fs::read(path)?)?;
+    }
+    Ok(document)
+}
+
+/// 将完整的文件内容解码为 [`FileContent`]
+pub fn document_from_bytes(bytes: &[u8], version: FileVersion) -> FileContent {
+    let detected = detect(bytes, true);
+    let binary = !detected.is_utf16() && is_binary(bytes);
+    let mut document = empty_document(detected, binary, bytes.len() as u64, version);
+    document.length = bytes.len() as u64;
+    document.partial = false;
+    if binary {
+        document.hex_preview = Some(hex_dump(&bytes[..bytes.len().min(HEX_PREVIEW_BYTES as usize)], 0));
+    } else {
+        document.content = decode(bytes, detected).into_owned();
+        document.line_ending = line_ending(&document.content);
+        document.total_lines = Some(document.content.lines().count());
+    }
+    document
+}
+
+fn empty_document(detected: Detected, binary: bool, size: u64, version: FileVersion) -> FileContent {
+    FileContent {
+        content: String::new(),
+        version,
+        encoding: detected.encoding.name().to_string(),
+        bom: detected.bom_len > 0,
+        line_ending: LineEnding::None,
+        binary,
+        hex_preview: None,
+        size,
+        offset: 0,
+        length: 0,
+        start_line: None,
+        end_line: None,
+        total_lines: None,
+        partial: true,
+    }
+}
+
+fn read_at(file: &mut fs::File, offset: u64, length: u64) -> Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.take(length).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// 读取字节范围并对齐到字符边界（UTF-8 跳过开头的续字节，UTF-16 按两字节对齐）；
+/// 未到文件末尾时在最后一个换行处截断，使下一段从新的一行开始，没有换行时去掉末尾不完整的字符
+fn read_byte_range(file: &mut fs::File, size: u64, detected: Detected, offset: u64, length: u64) -> Result<(u64, Vec<u8>)> {
+    let bom = detected.bom_len as u64;
+    let mut start = offset.max(bom).min(size);
+    if detected.is_utf16() {
+        start = (start + (start - bom) % 2).min(size);
+    }
+    let mut bytes = read_at(file, start, length.max(MIN_RANGE_BYTES))?;
+
+    if detected.encoding == UTF_8 && start > bom {
+        let skip = bytes.iter().take(3).take_while(|b| **b & 0xC0 == 0x80).count();
+        bytes.drain(..skip);
+        start += skip as u64;
+    }
+
+    if start + (bytes.len() as u64) < size {
+        if detected.is_utf16() {
+            bytes.truncate(bytes.len() & !1);
+            // 不拆开代理对
+            if let [.., a, b] = bytes[..] {
+                let unit = if detected.encoding == encoding_rs::UTF_16LE { u16::from_le_bytes([a, b]) } else { u16::from_be_bytes([a, b]) };
+                if (0xD800..0xDC00).contains(&unit) {
+                    bytes.truncate(bytes.len() - 2);
+                }
+            }
+        } else if let Some(end) = bytes.iter().rposition(|b| *b == b'\n') {
+            bytes.truncate(end + 1);
+        } else if detected.encoding == UTF_8 {
+            if let Err(e) = std::str::from_utf8(&bytes) {
+                if e.error_len().is_none() {
+                    bytes.truncate(e.valid_up_to());
+                }
+            }
+        } else if !detected.encoding.is_single_byte() {
+            // GBK、Shift_JIS 等的后续字节可能落在 ASCII 范围，只能通过解码判断末尾字符是否完整
+            let cut = (0..bytes.len().min(4)).find(|cut| {
+                detected.encoding
+                    .decode_without_bom_handling_and_without_replacement(&bytes[..bytes.len() - cut])
+                    .is_some()
+            });
+            if let Some(cut) = cut {
+                bytes.truncate(bytes.len() - cut);
+            }
+        }
+    }
+    Ok((start, bytes))
+}
+
+/// 按行读取的结果
+struct LineRange {
+    offset: u64,
+    bytes: Vec<u8>,
+    first: Option<usize>,
+    last: Option<usize>,
+    total: usize,
+}
+
+/// 读取 `start..=end` 行（内容不超过 [`MAX_READ_BYTES`]），同时统计总行数
+fn read_line_range(file: &mut fs::File, size: u64, detected: Detected, start: usize, end: Option<usize>) -> Result<LineRange> {
+    let mut range = LineRange { offset: 0, bytes: Vec::new(), first: None, last: None, total: 0 };
+    let wanted = |number: usize| number >= start && end.is_none_or(|end| number <= end);
+
+    if detected.is_utf16() {
+        // UTF-16 的换行不是单字节，整体解码后再切分
+        if size > MAX_READ_BYTES {
+            return Err(AppError::InvalidPath("UTF-16 文件过大，请按字节范围读取".to_string()));
+        }
+        let bytes = read_at(file, 0, size)?;
+        let text = decode(&bytes, detected);
+        let mut offset = detected.bom_len as u64;
+        let mut selected = String::new();
+        for (index, line) in text.split_inclusive('\n').enumerate() {
+            let number = index + 1;
+            if wanted(number) {
+                range.first.get_or_insert(number);
+                range.offset = if range.last.is_none() { offset } else { range.offset };
+                range.last = Some(number);
+                selected.push_str(line);
+            }
+            offset += line.encode_utf16().count() as u64 * 2;
+            range.total = number;
+        }
+        range.bytes = encode(&selected, Detected { bom_len: 0, ..detected }).unwrap_or_default();
+        return Ok(range);
+    }
+
+    file.seek(SeekFrom::Start(detected.bom_len as u64))?;
+    let mut reader = BufReader::new(file);
+    let mut offset = detected.bom_len as u64;
+    let mut line = Vec::new();
+    let mut full = false;
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+        range.total += 1;
+        if !full && wanted(range.total) {
+            if (range.bytes.len() + read) as u64 > MAX_READ_BYTES {
+                full = true;
+            } else {
+                if range.first.is_none() {
+                    range.first = Some(range.total);
+                    range.offset = offset;
+                }
+                range.last = Some(range.total);
+                range.bytes.extend_from_slice(&line);
+            }
+        }
+        offset += read as u64;
+    }
+    Ok(range)
+}
+
+/// 读取文件内容及版本，文件不存在时返回 None
+pub fn read_with_version(path: &Path) -> Result<Option<(Vec<u8>, FileVersion)>> {
+    match fs::read(path) {
+        Ok(bytes) => {
+            let version = file_version(path, &bytes)?;
+            Ok(Some((bytes, version)))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 检查磁盘版本后原子写入
+///
+/// 给出 `expected_hash` 或 `expected_mtime` 时，磁盘上的文件必须仍是该版本，否则返回
+/// `FileConflict` 并携带磁盘上的当前内容；都为空时直接覆盖。原文件使用 CRLF 时，
+/// 新内容中的 LF 会转换为 CRLF；原文件不是 UTF-8 时按原编码（含 BOM）写入。
+///
+/// `path` 须已经过沙箱解析（符号链接已展开），解析之后才换成符号链接的路径拒绝写入。
+pub fn write_checked(
+    path: &Path,
+    content: &str,
+    expected_hash: Option<&str>,
+    expected_mtime: Option<u64>,
+) -> Result<WriteOutcome> {
+    if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+        return Err(AppError::InvalidPath(format!("文件已被替换为符号链接: {}", path.display())));
+    }
+    let current = read_with_version(path)?;
+
+    let expected = expected_hash.is_some() || expected_mtime.is_some();
+    let matches = match &current {
+        Some((_, version)) => {
+            expected_hash.is_none_or(|hash| hash == version.hash)
+                && expected_mtime.is_none_or(|mtime| mtime == version.mtime)
+        }
+        None => !expected,
+    };
+    if !matches {
+        eprintln!("[write_checked] 文件已在磁盘上被修改: {:?}", path);
+        let current = current.map(|(bytes, version)| Box::new(document_from_bytes(&bytes, version)));
+        return Err(AppError::FileConflict { path: path.to_string_lossy().to_string(), current });
+    }
+
+    let previous = current.map(|(bytes, _)| bytes);
+    let written = match &previous {
+        Some(previous) => encode_like(previous, &match_line_endings(previous, content)),
+        None => content.as_bytes().to_vec(),
+    };
+    atomic_write(path, &written)?;
+
+    let version = file_version(path, &written)?;
+    Ok(WriteOutcome { previous, written, version })
+}
+
+/// 原文件使用 CRLF 而新内容只有 LF 时，转换为 CRLF
+pub fn match_line_endings<'a>(previous: &[u8], content: &'a str) -> Cow<'a, str> {
+    let crlf = decode(previous, detect(previous, true)).contains("\r\n");
+    if crlf && content.contains('\n') && !content.contains("\r\n") {
+        Cow::Owned(content.replace('\n', "\r\n"))
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
+/// 按原文件的编码（含 BOM）编码新内容；原文件是二进制，或新内容含原编码无法表示的字符时使用 UTF-8
+pub fn encode_like(previous: &[u8], content: &str) -> Vec<u8> {
+    let detected = detect(previous, true);
+    let plain_utf8 = detected.encoding == UTF_8 && detected.bom_len == 0;
+    if plain_utf8 || (!detected.is_utf16() && is_binary(previous)) {
+        return content.as_bytes().to_vec();
+    }
+    encode(content, detected).unwrap_or_else(|| {
+        eprintln!("[encode_like] 内容无法用 {} 表示，改为 UTF-8 保存", detected.encoding.name());
+        content.as_bytes().to_vec()
+    })
+}
+
+/// 原子写入：先写入同目录下的临时文件并同步到磁盘，再重命名覆盖目标
+///
+/// 目标为符号链接时写入链接指向的文件，链接本身保持不变。
+/// 目标已存在时保留其权限和属主；无法保留属主时改为原地写入。
+pub fn atomic_write(path: &Path, bytes: &[u8]) -> Result<()> {
+    let target = resolve_link(path)?;
+    let path = target.as_path();
+    let parent = path.parent()
+        .ok_or_else(|| AppError::InvalidPath(format!("无效路径: {}", path.display())))?;
+    let name = path.file_name()
+        .ok_or_else(|| AppError::InvalidPath(format!("无效路径: {}", path.display())))?
+        .to_string_lossy();
+    let uuid = uuid::Uuid::new_v4().simple().to_string();
+    let temp = parent.join(format!(".{}.{}.tmp", name, &uuid[..8]));
+
+    let result = (|| -> std::io::Result<bool> {
+        let mut file = fs::OpenOptions::new().write(true).create_new(true).open(&temp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+            if !keep_owner(&temp, &metadata) {
+                return Ok(false);
+            }
+        }
+        fs::rename(&temp, path)?;
+        Ok(true)
+    })();
+
+    match result {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            eprintln!("[atomic_write] 无法保留 {:?} 的属主，改为原地写入", path);
+            let _ = fs::remove_file(&temp);
+            let mut file = fs::OpenOptions::new().write(true).truncate(true).open(path)?;
+            file.write_all(bytes)?;
+            file.sync_all()?;
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("[atomic_write] 写入 {:?} 失败: {}", path, e);
+            let _ = fs::remove_file(&temp);
+            Err(e.into())
+        }
+    }
+}
+
+/// 符号链接解析为最终指向的路径，其他路径原样返回
+///
+/// 悬空链接的目标未经沙箱检查，直接报错而不是按链接内容创建文件。
+fn resolve_link(path: &Path) -> Result<std::path::PathBuf> {
+    let is_link = fs::symlink_metadata(path)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false);
+    if !is_link {
+        return Ok(path.to_path_buf());
+    }
+
+    fs::canonicalize(path)
+        .map_err(|_| AppError::InvalidPath(format!("符号链接指向的文件不存在: {}", path.display())))
+}
+
+/// 临时文件改为原文件的属主，成功（或无需修改）返回 true
+#[cfg(unix)]
+fn keep_owner(temp: &Path, original: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let Ok(current) = fs::metadata(temp) else {
+        return false;
+    };
+    if current.uid() == original.uid() && current.gid() == original.gid() {
+        return true;
+    }
+    std::os::unix::fs::chown(temp, Some(original.uid()), Some(original.gid())).is_ok()
+}
+
+#[cfg(not(unix))]
+fn keep_owner(_temp: &Path, _original: &fs::Metadata) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 临时目录中的文件路径（目录随返回的 [`TempDir`] 一起删除）
+    fn temp_file(name: &str) -> (TempDir, std::path::PathBuf) {
+        let dir = TempDir::with_prefix("file-io-").unwrap();
+        let path = dir.path().join(name);
+        (dir, path)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_through_symlinks() {
+        let (_dir, target) = temp_file("real.txt");
+        fs::write(&target, "one").unwrap();
+        let link = target.with_file_name("link.txt");
+        std::os::unix::fs::symlink("real.txt", &link).unwrap();
+
+        atomic_write(&link, b"two").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "two");
+
+        let dangling = target.with_file_name("dangling.txt");
+        std::os::unix::fs::symlink("created.txt", &dangling).unwrap();
+        assert!(atomic_write(&dangling, b"three").is_err());
+        assert!(!target.with_file_name("created.txt").exists());
+
+        // 带版本检查的写入只接受已解析的路径
+        assert!(write_checked(&link, "four", None, None).is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "two");
+    }
+
+    #[test]
+    fn detects_external_changes() {
+        let (_dir, path) = temp_file("a.txt");
+        fs::write(&path, "one").unwrap();
+        let (_, version) = read_with_version(&path).unwrap().unwrap();
+
+        let outcome = write_checked(&path, "two", Some(&version.hash), None).unwrap();
+        assert_eq!(outcome.previous.as_deref(), Some(&b"one"[..]));
+        assert_eq!(outcome.version.hash, content_hash(b"two"));
+
+        // 使用旧版本保存：冲突并返回磁盘上的内容
+        match write_checked(&path, "three", Some(&version.hash), None) {
+            Err(AppError::FileConflict { current: Some(current), .. }) => {
+                assert_eq!(current.content, "two");
+                assert_eq!(current.version, outcome.version);
+            }
+            other => panic!("expected conflict, got {:?}", other.map(|o| o.version)),
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two");
+
+        // 期望文件存在但已被删除
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            write_checked(&path, "x", None, Some(outcome.version.mtime)),
+            Err(AppError::FileConflict { current: None, .. })
+        ));
+        assert!(write_checked(&path, "x", None, None).unwrap().previous.is_none());
+    }
+
+    #[test]
+    fn preserves_line_endings_and_permissions() {
+        let (_dir, path) = temp_file("crlf.txt");
+        fs::write(&path, "a\r\nb\r\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+        }
+
+        write_checked(&path, "a\nb\nc\n", None, None).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\r\nb\r\nc\r\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o750);
+        }
+
+        // 不留下临时文件
+        let entries = fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(entries, 1);
+        assert_eq!(match_line_endings(b"a\nb\n", "x\ny"), "x\ny");
+    }
+
+    #[test]
+    fn keeps_original_encoding_when_saving() {
+        let (_dir, path) = temp_file("gbk.txt");
+        fs::write(&path, encoding_rs::GBK.encode("旧内容\r\n").0).unwrap();
+        let document = read_document(&path, None).unwrap();
+        assert_eq!((document.content.as_str(), document.encoding.as_str()), ("旧内容\r\n", "GBK"));
+        assert_eq!(document.line_ending, LineEnding::Crlf);
+
+        write_checked(&path, "新内容\n", Some(&document.version.hash), None).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), encoding_rs::GBK.encode("新内容\r\n").0.to_vec());
+
+        let (_dir, path) = temp_file("utf16.txt");
+        fs::write(&path, b"\xFF\xFEa\0").unwrap();
+        write_checked(&path, "b", None, None).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"\xFF\xFEb\0");
+    }
+
+    #[test]
+    fn reads_ranges_and_binaries() {
+        let (_dir, path) = temp_file("lines.txt");
+        let text: String = (1..=100).map(|i| format!("第{}行\n", i)).collect();
+        fs::write(&path, encoding_rs::GBK.encode(&text).0).unwrap();
+
+        let lines = read_document(&path, Some(&ReadRange::Lines { start: 10, end: Some(12) })).unwrap();
+        assert_eq!(lines.content, "第10行\n第11行\n第12行\n");
+        assert_eq!((lines.start_line, lines.end_line, lines.total_lines), (Some(10), Some(12), Some(100)));
+        assert!(lines.partial);
+        assert!(lines.version.hash.is_empty());
+        assert!(matches!(
+            write_checked(&path, &lines.content, Some(&lines.version.hash), None),
+            Err(AppError::FileConflict { .. })
+        ));
+
+        // 字节范围从返回的偏移继续读取，拼起来与原文一致
+        let mut offset = 0;
+        let mut joined = String::new();
+        while offset < lines.size {
+            let chunk = read_document(&path, Some(&ReadRange::Bytes { offset, length: 50 })).unwrap();
+            assert!(chunk.length > 0);
+            joined.push_str(&chunk.content);
+            offset = chunk.offset + chunk.length;
+        }
+        assert_eq!(joined, text);
+
+        // 没有换行时按字符边界分段，长度小于一个字符也能继续
+        for (encoding, sample, length) in [
+            (encoding_rs::GBK, "中文内容abc", 3),
+            (encoding_rs::SHIFT_JIS, "日本語のテキストを表示します", 5),
+            (UTF_8, "中文テキストabc", 1),
+        ] {
+            let (_dir, path) = temp_file("no-newline.txt");
+            let text = sample.repeat(20);
+            let bytes = encoding.encode(&text).0;
+            fs::write(&path, &bytes).unwrap();
+
+            let mut offset = 0;
+            let mut joined = String::new();
+            while offset < bytes.len() as u64 {
+                let chunk = read_document(&path, Some(&ReadRange::Bytes { offset, length })).unwrap();
+                assert_eq!(chunk.encoding, encoding.name());
+                assert!(chunk.length > 0, "{}", encoding.name());
+                joined.push_str(&chunk.content);
+                offset = chunk.offset + chunk.length;
+            }
+            assert_eq!(joined, text, "{}", encoding.name());
+        }
+
+        let whole = read_document(&path, Some(&ReadRange::Bytes { offset: 0, length: 1 << 20 })).unwrap();
+        assert!(!whole.partial);
+        assert_eq!(whole.total_lines, Some(100));
+        assert_eq!(whole.version.hash, content_hash(&fs::read(&path).unwrap()));
+
+        let (_dir, path) = temp_file("image.png");
+        fs::write(&path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        let binary = read_document(&path, None).unwrap();
+        assert!(binary.binary && binary.content.is_empty());
+        assert!(binary.hex_preview.unwrap().starts_with("00000000  89 50 4e 47"));
+    }
+}
@@ -0,0 +1,490 @@
+use crate::error::{AppError, Result};
+use crate::models::file_ops::{FileOp, FileOpHistory, FileOpKind};
+use crate::services::file_io::atomic_write;
+use crate::services::trash::Trash;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 每个工作区最多保留的可撤销操作数
+const MAX_OPS: usize = 100;
+
+/// 覆盖写入时保存前后内容的大小上限，超过时不记录该操作
+pub const MAX_CONTENT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// 每个工作区保存的内容快照总大小上限，超过时丢弃最早的操作
+const MAX_BLOB_BYTES: u64 = 256 * 1024 * 1024;
+
+/// 文件操作日志
+///
+/// 每个工作区在 `root/<key>/` 下保存 `journal.json`，覆盖写入的前后内容保存在
+/// `blobs/<op_id>.before` / `.after`（批量替换为 `<op_id>.<序号>.before` / `.after`）。撤销和重做前都会检查磁盘状态是否与记录一致，
+/// 不一致时拒绝执行，避免覆盖之后的修改；执行失败的记录会被移除，不会挡住更早的操作。
+pub struct FileOpJournal {
+    dir: PathBuf,
+    history: FileOpHistory,
+    max_blob_bytes: u64,
+}
+
+impl FileOpJournal {
+    /// 打开工作区对应的日志（不存在时为空）
+    pub fn open(root: &Path, workspace: &Path) -> Self {
+        let dir = root.join(workspace_key(workspace));
+        let history = fs::read_to_string(dir.join("journal.json"))
+            .ok()
+            .and_then(|content| match serde_json::from_str(&content) {
+                Ok(history) => Some(history),
+                Err(e) => {
+                    eprintln!("[FileOpJournal::open] 日志损坏，已忽略: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Self { dir, history, max_blob_bytes: MAX_BLOB_BYTES }
+    }
+
+    pub fn history(&self) -> &FileOpHistory {
+        &self.history
+    }
+
+    /// 记录新操作，`contents` 为覆盖写入前后的内容；新操作会清空重做栈
+    pub fn record(&mut self, op: FileOpKind, contents: Option<(&[u8], &[u8])>) -> Result<FileOp> {
+        let entry = new_entry(op);
+        if let Some((before, after)) = contents {
+            self.write_blobs(&entry.id, before, after)?;
+        }
+        self.push(entry)
+    }
+
+    /// 记录批量替换，`files` 为每个文件的路径和替换前后的内容
+    pub fn record_replace(&mut self, files: &[(String, Vec<u8>, Vec<u8>)]) -> Result<FileOp> {
+        let entry = new_entry(FileOpKind::Replace {
+            paths: files.iter().map(|(path, _, _)| path.clone()).collect(),
+        });
+        for (index, (_, before, after)) in files.iter().enumerate() {
+            self.write_blobs(&format!("{}.{}", entry.id, index), before, after)?;
+        }
+        self.push(entry)
+    }
+
+    fn push(&mut self, entry: FileOp) -> Result<FileOp> {
+        for dropped in std::mem::take(&mut self.history.redo) {
+            self.remove_blobs(&dropped);
+        }
+        self.history.undo.push(entry.clone());
+        if self.history.undo.len() > MAX_OPS {
+            let excess = self.history.undo.len() - MAX_OPS;
+            for dropped in self.history.undo.drain(..excess).collect::<Vec<_>>() {
+                self.remove_blobs(&dropped);
+            }
+        }
+
+        // 快照总大小超限时从最早的操作开始丢弃（保留刚记录的操作）
+        let mut total: u64 = self.history.undo.iter().map(|op| self.blob_bytes(op)).sum();
+        while total > self.max_blob_bytes && self.history.undo.len() > 1 {
+            let dropped = self.history.undo.remove(0);
+            total -= self.blob_bytes(&dropped);
+            self.remove_blobs(&dropped);
+        }
+
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// 撤销最近的操作，没有可撤销的操作时返回 None
+    pub fn undo(&mut self, trash: &Trash) -> Result<Option<FileOp>> {
+        let Some(mut entry) = self.history.undo.last().cloned() else {
+            return Ok(None);
+        };
+        if let Err(e) = self.revert(&mut entry, trash) {
+            self.history.undo.pop();
+            return Err(self.drop_failed(&entry, e, "撤销"));
+        }
+
+        self.history.undo.pop();
+        self.history.redo.push(entry.clone());
+        self.save()?;
+        eprintln!("[FileOpJournal::undo] {:?}", entry.op);
+        Ok(Some(entry))
+    }
+
+    /// 重做最近撤销的操作，没有可重做的操作时返回 None
+    pub fn redo(&mut self, trash: &Trash) -> Result<Option<FileOp>> {
+        let Some(mut entry) = self.history.redo.last().cloned() else {
+            return Ok(None);
+        };
+        if let Err(e) = self.reapply(&mut entry, trash) {
+            self.history.redo.pop();
+            return Err(self.drop_failed(&entry, e, "重做"));
+        }
+
+        self.history.redo.pop();
+        self.history.undo.push(entry.clone());
+        self.save()?;
+        eprintln!("[FileOpJournal::redo] {:?}", entry.op);
+        Ok(Some(entry))
+    }
+
+    /// 移除已出栈的失败记录，返回说明该记录已移除的错误
+    fn drop_failed(&self, entry: &FileOp, error: AppError, action: &str) -> AppError {
+        eprintln!("[FileOpJournal] {}失败，移除记录 {:?}: {}", action, entry.op, error);
+        self.remove_blobs(entry);
+        if let Err(e) = self.save() {
+            eprintln!("[FileOpJournal] 保存日志失败: {}", e);
+        }
+        let reason = match error {
+            AppError::InvalidPath(message) => message,
+            other => other.to_message(),
+        };
+        AppError::InvalidPath(format!("{}，该操作已无法{}并已移除", reason, action))
+    }
+
+    fn revert(&self, entry: &mut FileOp, trash: &Trash) -> Result<()> {
+        match &mut entry.op {
+            FileOpKind::Create { path, trash_id, .. } => {
+                ensure_exists(path)?;
+                *trash_id = Some(trash.move_to_trash(Path::new(path))?.id);
+            }
+            FileOpKind::Write { .. } | FileOpKind::Replace { .. } => {
+                self.replace_contents(entry, "after", "before")?;
+            }
+            FileOpKind::Rename { from, to } => move_back(to, from)?,
+            FileOpKind::Delete { trash_id, .. } => {
+                trash.restore(trash_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn reapply(&self, entry: &mut FileOp, trash: &Trash) -> Result<()> {
+        match &mut entry.op {
+            FileOpKind::Create { trash_id, .. } => {
+                let id = trash_id.as_deref()
+                    .ok_or_else(|| AppError::InvalidPath("缺少回收站条目，无法重做".to_string()))?;
+                trash.restore(id)?;
+                *trash_id = None;
+            }
+            FileOpKind::Write { .. } | FileOpKind::Replace { .. } => {
+                self.replace_contents(entry, "before", "after")?;
+            }
+            FileOpKind::Rename { from, to } => move_back(from, to)?,
+            FileOpKind::Delete { path, trash_id } => {
+                ensure_exists(path)?;
+                *trash_id = trash.move_to_trash(Path::new(path))?.id;
+            }
+        }
+        Ok(())
+    }
+
+    /// 所有文件的内容都与 `expected` 版本一致时，全部替换为 `target` 版本
+    ///
+    /// 记录的路径都已经过沙箱解析，之后被替换成符号链接的文件不跟随写入。
+    fn replace_contents(&self, entry: &FileOp, expected: &str, target: &str) -> Result<()> {
+        let mut targets = Vec::new();
+        for (path, key) in blob_keys(entry) {
+            if fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+                return Err(AppError::InvalidPath(format!("文件已被替换为符号链接: {}", path)));
+            }
+            let current = fs::read(&path)
+                .map_err(|_| AppError::InvalidPath(format!("文件不存在: {}", path)))?;
+            if current != fs::read(self.blob_path(&key, expected))? {
+                return Err(AppError::InvalidPath(format!("文件已被修改，无法撤销或重做: {}", path)));
+            }
+            targets.push((path, fs::read(self.blob_path(&key, target))?));
+        }
+        for (path, content) in targets {
+            atomic_write(Path::new(&path), &content)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let content = serde_json::to_string_pretty(&self.history)?;
+        fs::write(self.dir.join("journal.json"), content)?;
+        Ok(())
+    }
+
+    fn blobs_dir(&self) -> PathBuf {
+        self.dir.join("blobs")
+    }
+
+    fn blob_path(&self, key: &str, suffix: &str) -> PathBuf {
+        self.blobs_dir().join(format!("{}.{}", key, suffix))
+    }
+
+    fn write_blobs(&self, key: &str, before: &[u8], after: &[u8]) -> Result<()> {
+        fs::create_dir_all(self.blobs_dir())?;
+        fs::write(self.blob_path(key, "before"), before)?;
+        fs::write(self.blob_path(key, "after"), after)?;
+        Ok(())
+    }
+
+    fn blob_bytes(&self, entry: &FileOp) -> u64 {
+        blob_keys(entry)
+            .iter()
+            .flat_map(|(_, key)| [self.blob_path(key, "before"), self.blob_path(key, "after")])
+            .filter_map(|path| fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
+    fn remove_blobs(&self, entry: &FileOp) {
+        for (_, key) in blob_keys(entry) {
+            let _ = fs::remove_file(self.blob_path(&key, "before"));
+            let _ = fs::remove_file(self.blob_path(&key, "after"));
+        }
+    }
+}
+
+fn new_entry(op: FileOpKind) -> FileOp {
+    FileOp {
+        id: uuid::Uuid::new_v4().to_string(),
+        op,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+/// 操作涉及的文件及其内容快照的文件名前缀
+fn blob_keys(entry: &FileOp) -> Vec<(String, String)> {
+    match &entry.op {
+        FileOpKind::Write { path } => vec![(path.clone(), entry.id.clone())],
+        FileOpKind::Replace { paths } => paths.iter()
+            .enumerate()
+            .map(|(index, path)| (path.clone(), format!("{}.{}", entry.id, index)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 创建 `path` 时会新建的最上层路径，已存在时为 None
+pub fn created_root(path: &Path) -> Option<PathBuf> {
+    if path.symlink_metadata().is_ok() {
+        return None;
+    }
+    let mut root = path.to_path_buf();
+    while let Some(parent) = root.parent() {
+        if parent.as_os_str().is_empty() || parent.symlink_metadata().is_ok() {
+            break;
+        }
+        root = parent.to_path_buf();
+    }
+    Some(root)
+}
+
+fn ensure_exists(path: &str) -> Result<()> {
+    if Path::new(path).symlink_metadata().is_err() {
+        return Err(AppError::InvalidPath(format!("路径已不存在: {}", path)));
+    }
+    Ok(())
+}
+
+/// 将 `from` 移回 `to`，目标已存在时拒绝
+fn move_back(from: &str, to: &str) -> Result<()> {
+    ensure_exists(from)?;
+    if Path::new(to).symlink_metadata().is_ok() {
+        return Err(AppError::InvalidPath(format!("目标位置已存在: {}", to)));
+    }
+    fs::rename(from, to)?;
+    Ok(())
+}
+
+/// 工作区在日志目录下的子目录名（目录名 + 路径的 FNV-1a 哈希，跨版本稳定）
+fn workspace_key(workspace: &Path) -> String {
+    let canonical = workspace.canonicalize().unwrap_or_else(|_| workspace.to_path_buf());
+    let hash = canonical
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    let name: String = canonical
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(32)
+        .collect();
+    format!("{}-{:016x}", name, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixture {
+        work: PathBuf,
+        root: PathBuf,
+        trash: Trash,
+        _base: tempfile::TempDir,
+    }
+
+    fn fixture() -> Fixture {
+        let base = tempfile::TempDir::with_prefix("file-ops-").unwrap();
+        let work = base.path().join("work");
+        fs::create_dir_all(&work).unwrap();
+        Fixture {
+            root: base.path().join("journal"),
+            trash: Trash::new(base.path().join("trash")),
+            work,
+            _base: base,
+        }
+    }
+
+    fn s(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn undoes_and_redoes_create_rename_delete() {
+        let f = fixture();
+        let mut journal = FileOpJournal::open(&f.root, &f.work);
+
+        let new_dir = f.work.join("a/b");
+        let created = created_root(&new_dir.join("c.txt")).unwrap();
+        assert_eq!(created, f.work.join("a"));
+        fs::create_dir_all(&new_dir).unwrap();
+        fs::write(new_dir.join("c.txt"), "c").unwrap();
+        journal.record(FileOpKind::Create { path: s(&created), is_dir: true, trash_id: None }, None).unwrap();
+
+        fs::rename(f.work.join("a"), f.work.join("z")).unwrap();
+        journal.record(FileOpKind::Rename { from: s(&f.work.join("a")), to: s(&f.work.join("z")) }, None).unwrap();
+
+        let item = f.trash.move_to_trash(&f.work.join("z")).unwrap();
+        journal.record(FileOpKind::Delete { path: s(&f.work.join("z")), trash_id: item.id }, None).unwrap();
+
+        journal.undo(&f.trash).unwrap();
+        assert!(f.work.join("z/b/c.txt").exists());
+        journal.undo(&f.trash).unwrap();
+        assert!(f.work.join("a/b/c.txt").exists());
+        journal.undo(&f.trash).unwrap();
+        assert!(!f.work.join("a").exists());
+        assert!(journal.undo(&f.trash).unwrap().is_none());
+
+        // 重启后仍可重做
+        let mut journal = FileOpJournal::open(&f.root, &f.work);
+        assert_eq!(journal.history().redo.len(), 3);
+        journal.redo(&f.trash).unwrap();
+        journal.redo(&f.trash).unwrap();
+        assert_eq!(fs::read_to_string(f.work.join("z/b/c.txt")).unwrap(), "c");
+        journal.redo(&f.trash).unwrap();
+        assert!(!f.work.join("z").exists());
+        assert_eq!(journal.history().undo.len(), 3);
+    }
+
+    #[test]
+    fn write_undo_refuses_to_clobber_later_changes() {
+        let f = fixture();
+        let mut journal = FileOpJournal::open(&f.root, &f.work);
+        let file = f.work.join("notes.md");
+        fs::write(&file, "old").unwrap();
+
+        fs::write(&file, "new").unwrap();
+        journal.record(FileOpKind::Write { path: s(&file) }, Some((b"old", b"new"))).unwrap();
+
+        journal.undo(&f.trash).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "old");
+        journal.redo(&f.trash).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+
+        fs::write(&file, "edited elsewhere").unwrap();
+        assert!(journal.undo(&f.trash).is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "edited elsewhere");
+        assert!(journal.history().undo.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_undo_does_not_follow_symlinks() {
+        let f = fixture();
+        let mut journal = FileOpJournal::open(&f.root, &f.work);
+        let (file, outside) = (f.work.join("notes.md"), f.work.join("../outside.md"));
+        fs::write(&file, "new").unwrap();
+        journal.record(FileOpKind::Write { path: s(&file) }, Some((b"old", b"new"))).unwrap();
+
+        // 记录后文件被换成指向工作区外的链接，内容仍与记录一致
+        fs::write(&outside, "new").unwrap();
+        fs::remove_file(&file).unwrap();
+        std::os::unix::fs::symlink(&outside, &file).unwrap();
+        assert!(journal.undo(&f.trash).is_err());
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "new");
+    }
+
+    #[test]
+    fn failed_undo_does_not_block_older_ops() {
+        let f = fixture();
+        let mut journal = FileOpJournal::open(&f.root, &f.work);
+        let (a, b) = (f.work.join("a.txt"), f.work.join("b.txt"));
+        fs::write(&a, "a").unwrap();
+        journal.record(FileOpKind::Create { path: s(&a), is_dir: false, trash_id: None }, None).unwrap();
+        fs::write(&b, "b").unwrap();
+        journal.record(FileOpKind::Create { path: s(&b), is_dir: false, trash_id: None }, None).unwrap();
+
+        // 在应用外被删除的文件无法撤销，记录被移除
+        fs::remove_file(&b).unwrap();
+        let error = journal.undo(&f.trash).unwrap_err().to_message();
+        assert!(error.contains("已移除"), "{}", error);
+        assert_eq!(journal.history().undo.len(), 1);
+
+        journal.undo(&f.trash).unwrap();
+        assert!(!a.exists());
+        assert_eq!(FileOpJournal::open(&f.root, &f.work).history().redo.len(), 1);
+    }
+
+    #[test]
+    fn replace_is_undone_as_a_whole() {
+        let f = fixture();
+        let mut journal = FileOpJournal::open(&f.root, &f.work);
+        let (a, b) = (f.work.join("a.rs"), f.work.join("b.rs"));
+        fs::write(&a, "new_a").unwrap();
+        fs::write(&b, "new_b").unwrap();
+        let entry = journal.record_replace(&[
+            (s(&a), b"old_a".to_vec(), b"new_a".to_vec()),
+            (s(&b), b"old_b".to_vec(), b"new_b".to_vec()),
+        ]).unwrap();
+
+        journal.undo(&f.trash).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old_a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "old_b");
+
+        journal.redo(&f.trash).unwrap();
+        assert_eq!(fs::read_to_string(&b).unwrap(), "new_b");
+        journal.undo(&f.trash).unwrap();
+
+        // 任一文件被修改时整体拒绝，已检查过的文件也不会被写入
+        fs::write(&b, "edited").unwrap();
+        assert!(journal.redo(&f.trash).is_err());
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old_a");
+        assert!(journal.history().redo.is_empty());
+        assert!(!journal.blob_path(&format!("{}.1", entry.id), "before").exists());
+    }
+
+    #[test]
+    fn new_ops_clear_redo_and_history_is_bounded() {
+        let f = fixture();
+        let mut journal = FileOpJournal::open(&f.root, &f.work);
+        let file = f.work.join("x.txt");
+        fs::write(&file, "2").unwrap();
+        journal.record(FileOpKind::Write { path: s(&file) }, Some((b"1", b"2"))).unwrap();
+        journal.undo(&f.trash).unwrap();
+        let undone = journal.history().redo[0].id.clone();
+
+        journal.record(FileOpKind::Rename { from: "a".into(), to: "b".into() }, None).unwrap();
+        assert!(journal.history().redo.is_empty());
+        assert!(!journal.blob_path(&undone, "before").exists());
+
+        for _ in 0..MAX_OPS + 5 {
+            journal.record(FileOpKind::Rename { from: "a".into(), to: "b".into() }, None).unwrap();
+        }
+        assert_eq!(journal.history().undo.len(), MAX_OPS);
+
+        // 快照总大小同样受限
+        journal.max_blob_bytes = 10;
+        let first = journal.record(FileOpKind::Write { path: s(&file) }, Some((b"1234", b"5678"))).unwrap();
+        journal.record(FileOpKind::Write { path: s(&file) }, Some((b"abcd", b"efgh"))).unwrap();
+        assert!(!journal.blob_path(&first.id, "before").exists());
+        assert!(journal.history().undo.iter().all(|op| op.id != first.id));
+
+        // 不同工作区互不影响
+        let other = FileOpJournal::open(&f.root, &f.root);
+        assert!(other.history().undo.is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::error::{AppError, Result};
+use crate::models::file::{FsChange, FsChangeBatch, FsChangeKind};
+use crate::services::walker::{IgnoreMatcher, WalkRules};
+use notify_debouncer_full::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 防抖时间：同一文件的连续变更在此时间内合并为一次
+pub const FS_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 工作目录的递归监听
+///
+/// 变更经过防抖后按批次回调，被 [`WalkRules`] 忽略的路径（`.git` 内部、ignore 文件
+/// 和 `file_excludes`）会被过滤，与文件树和索引保持一致。Drop 时停止监听。
+pub struct FsWatcher {
+    root: PathBuf,
+    excludes: Vec<String>,
+    debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+}
+
+impl FsWatcher {
+    /// 开始监听 `root`，每批变更调用一次 `on_batch`（空批次不回调）
+    pub fn start(root: PathBuf, excludes: &[String], on_batch: impl Fn(FsChangeBatch) + Send + 'static) -> Result<Self> {
+        let rules = WalkRules::new(&root, excludes);
+        let batch_root = root.to_string_lossy().to_string();
+
+        let mut debouncer = new_debouncer(FS_DEBOUNCE, None, move |result: DebounceEventResult| {
+            match result {
+                Ok(events) => {
+                    // 每批重新读取 ignore 文件，规则变化在下一批生效
+                    let changes = to_changes(&events, &mut rules.matcher());
+                    if !changes.is_empty() {
+                        on_batch(FsChangeBatch { root: batch_root.clone(), changes });
+                    }
+                }
+                Err(errors) => {
+                    for error in errors {
+                        eprintln!("[FsWatcher] 监听错误: {}", error);
+                    }
+                }
+            }
+        })
+        .map_err(|e| AppError::Unknown(format!("启动文件监听失败: {}", e)))?;
+
+        debouncer.watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| AppError::Unknown(format!("监听目录失败 {:?}: {}", root, e)))?;
+
+        eprintln!("[FsWatcher::start] 开始监听: {:?}", root);
+        Ok(Self { root, excludes: excludes.to_vec(), debouncer })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn excludes(&self) -> &[String] {
+        &self.excludes
+    }
+
+    /// 停止监听并等待事件线程结束
+    pub fn stop(self) {
+        eprintln!("[FsWatcher::stop] 停止监听: {:?}", self.root);
+        self.debouncer.stop();
+    }
+}
+
+/// 将防抖后的事件转换为前端使用的变更列表（过滤忽略路径并去重）
+fn to_changes(events: &[DebouncedEvent], filter: &mut IgnoreMatcher) -> Vec<FsChange> {
+    let mut changes = Vec::new();
+    let mut seen = HashSet::new();
+    let mut push = |kind: FsChangeKind, path: &Path, old_path: Option<&Path>| {
+        let change = FsChange {
+            kind,
+            path: path.to_string_lossy().to_string(),
+            old_path: old_path.map(|p| p.to_string_lossy().to_string()),
+        };
+        if seen.insert((change.kind, change.path.clone(), change.old_path.clone())) {
+            changes.push(change);
+        }
+    };
+
+    for event in events {
+        if event.need_rescan() {
+            // 事件丢失时让前端刷新整个目录
+            if let Some(path) = event.paths.first() {
+                push(FsChangeKind::Modified, path, None);
+            }
+            continue;
+        }
+
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                let (from, to) = (&event.paths[0], &event.paths[1]);
+                match (filter.is_ignored(from), filter.is_ignored(to)) {
+                    (false, false) => push(FsChangeKind::Renamed, to, Some(from)),
+                    (true, false) => push(FsChangeKind::Created, to, None),
+                    (false, true) => push(FsChangeKind::Removed, from, None),
+                    (true, true) => {}
+                }
+            }
+            EventKind::Access(_) => {}
+            kind => {
+                for path in event.paths.iter().filter(|path| !filter.is_ignored(path)) {
+                    let kind = match kind {
+                        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => FsChangeKind::Created,
+                        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => FsChangeKind::Removed,
+                        EventKind::Modify(ModifyKind::Name(_)) if path.symlink_metadata().is_err() => FsChangeKind::Removed,
+                        EventKind::Modify(ModifyKind::Name(_)) => FsChangeKind::Created,
+                        _ => FsChangeKind::Modified,
+                    };
+                    push(kind, path, None);
+                }
+            }
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::git::tests::temp_repo;
+    use notify_debouncer_full::notify::event::{CreateKind, DataChange, RemoveKind};
+    use notify_debouncer_full::notify::Event;
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    fn event(kind: EventKind, paths: &[&Path]) -> DebouncedEvent {
+        let event = paths.iter().fold(Event::new(kind), |event, path| event.add_path(path.to_path_buf()));
+        DebouncedEvent::new(event, Instant::now())
+    }
+
+    #[test]
+    fn converts_and_filters_events() {
+        let (tmp, _repo) = temp_repo();
+        let dir = tmp.path();
+        std::fs::write(dir.join(".gitignore"), "dist/\n*.log\n").unwrap();
+        let rules = WalkRules::new(dir, &[]);
+
+        let events = vec![
+            event(EventKind::Create(CreateKind::File), &[&dir.join("a.txt")]),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &[&dir.join("a.txt")]),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &[&dir.join("a.txt")]),
+            event(EventKind::Create(CreateKind::File), &[&dir.join("dist/bundle.js")]),
+            event(EventKind::Create(CreateKind::File), &[&dir.join("debug.log")]),
+            event(EventKind::Modify(ModifyKind::Any), &[&dir.join(".git/index")]),
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &[&dir.join("a.txt"), &dir.join("b.txt")]),
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &[&dir.join("c.txt"), &dir.join("c.log")]),
+            event(EventKind::Remove(RemoveKind::Folder), &[&dir.join("old")]),
+        ];
+
+        let kinds: Vec<(FsChangeKind, String)> = to_changes(&events, &mut rules.matcher())
+            .into_iter()
+            .map(|c| (c.kind, Path::new(&c.path).file_name().unwrap().to_string_lossy().to_string()))
+            .collect();
+        assert_eq!(kinds, vec![
+            (FsChangeKind::Created, "a.txt".to_string()),
+            (FsChangeKind::Modified, "a.txt".to_string()),
+            (FsChangeKind::Renamed, "b.txt".to_string()),
+            (FsChangeKind::Removed, "c.txt".to_string()),
+            (FsChangeKind::Removed, "old".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn filters_ignored_paths_without_git() {
+        let tmp = tempfile::TempDir::with_prefix("fs-watcher-").unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join(".gitignore"), "dist/\n").unwrap();
+        std::fs::write(dir.join("src/.gitignore"), "*.tmp\n").unwrap();
+        let rules = WalkRules::new(dir, &["node_modules".to_string()]);
+
+        let events: Vec<DebouncedEvent> = ["dist/bundle.js", "node_modules/pkg/index.js", "src/a.tmp", "src/main.rs", "a.tmp"]
+            .iter()
+            .map(|path| event(EventKind::Create(CreateKind::File), &[&dir.join(path)]))
+            .collect();
+        let paths: Vec<String> = to_changes(&events, &mut rules.matcher())
+            .into_iter()
+            .map(|c| Path::new(&c.path).strip_prefix(dir).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        assert_eq!(paths, vec!["src/main.rs", "a.tmp"]);
+    }
+
+    #[test]
+    fn pushes_batches_for_real_changes() {
+        let (tmp, _repo) = temp_repo();
+        let dir = tmp.path();
+        let (tx, rx) = mpsc::channel();
+        let watcher = FsWatcher::start(dir.to_path_buf(), &[], move |batch| {
+            let _ = tx.send(batch);
+        })
+        .unwrap();
+
+        std::fs::write(dir.join("new.txt"), "hello").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut found = false;
+        while !found && Instant::now() < deadline {
+            if let Ok(batch) = rx.recv_timeout(Duration::from_millis(200)) {
+                found = batch.changes.iter().any(|c| c.path.ends_with("new.txt"));
+            }
+        }
+        assert!(found, "未收到 new.txt 的变更");
+        watcher.stop();
+    }
+}
@@ -0,0 +1,1087 @@
+use crate::error::{AppError, Result};
+use crate::models::git::{
+    BlameLine, BranchInfo, CommitInfo, CommitPage, DiffHunk, DiffLine, DiffLineKind, FileBlame,
+    FileDiff, FileHistoryEntry, FileHistoryPage, GitChangeKind, GitFileStatus, GitStatus, StashInfo,
+};
+use git2::build::CheckoutBuilder;
+use git2::{
+    ApplyLocation, ApplyOptions, BlameOptions, Branch, BranchType, Delta, Diff, DiffDelta, DiffFindOptions,
+    DiffFormat, DiffOptions, ErrorCode, IndexAddOption, Patch, Repository, Signature, Sort, StashFlags,
+    Status, StatusOptions, Tree,
+};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::{Component, Path};
+
+/// 代理（Claude 会话）提交使用的作者名
+pub const AGENT_NAME: &str = "Claude";
+
+/// 代理提交使用的作者邮箱
+pub const AGENT_EMAIL: &str = "noreply@anthropic.com";
+
+/// 工作区 Git 仓库
+pub struct GitRepo {
+    repo: Repository,
+}
+
+impl GitRepo {
+    /// 打开包含 `work_dir` 的仓库
+    pub fn open(work_dir: &Path) -> Result<Self> {
+        let repo = Repository::discover(work_dir).map_err(|e| match e.code() {
+            ErrorCode::NotFound => AppError::GitError(format!("不是 Git 仓库: {}", work_dir.display())),
+            _ => e.into(),
+        })?;
+        if repo.is_bare() {
+            return Err(AppError::GitError("不支持裸仓库".to_string()));
+        }
+        Ok(Self { repo })
+    }
+
+    pub fn repository(&self) -> &Repository {
+        &self.repo
+    }
+
+    /// 当前分支名，分离 HEAD 或空仓库时为 None
+    pub fn current_branch(&self) -> Result<Option<String>> {
+        match self.repo.head() {
+            Ok(head) if head.is_branch() => Ok(head.shorthand().map(String::from)),
+            Ok(_) => Ok(None),
+            // 空仓库：HEAD 指向尚未创建的分支
+            Err(e) if e.code() == ErrorCode::UnbornBranch => {
+                let head = self.repo.find_reference("HEAD")?;
+                Ok(head
+                    .symbolic_target()
+                    .map(|target| target.trim_start_matches("refs/heads/").to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn head_tree(&self) -> Result<Option<Tree<'_>>> {
+        match self.repo.head() {
+            Ok(head) => Ok(Some(head.peel_to_tree()?)),
+            Err(e) if e.code() == ErrorCode::UnbornBranch => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn signature(&self) -> Result<Signature<'static>> {
+        self.repo.signature()
+            .map_err(|_| AppError::GitError("未配置 user.name 和 user.email".to_string()))
+    }
+
+    fn head_id(&self) -> Option<String> {
+        self.repo.head().ok()?.target().map(|id| id.to_string())
+    }
+
+    /// 工作区状态（含重命名检测）
+    pub fn status(&self) -> Result<GitStatus> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_head_to_index(true)
+            .renames_index_to_workdir(true);
+
+        let mut status = GitStatus {
+            branch: self.current_branch()?,
+            head: self.head_id(),
+            ..GitStatus::default()
+        };
+
+        for entry in self.repo.statuses(Some(&mut opts))?.iter() {
+            let flags = entry.status();
+
+            if flags.contains(Status::CONFLICTED) {
+                if let Some(path) = entry.path() {
+                    status.conflicted.push(GitFileStatus {
+                        path: path.to_string(),
+                        old_path: None,
+                        kind: GitChangeKind::Conflicted,
+                    });
+                }
+                continue;
+            }
+
+            if let Some(kind) = index_kind(flags) {
+                status.staged.push(file_status(entry.head_to_index(), kind, entry.path()));
+            }
+
+            if flags.contains(Status::WT_NEW) {
+                status.untracked.push(file_status(entry.index_to_workdir(), GitChangeKind::Untracked, entry.path()));
+            } else if let Some(kind) = workdir_kind(flags) {
+                status.unstaged.push(file_status(entry.index_to_workdir(), kind, entry.path()));
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// 差异
+    ///
+    /// `staged` 为 true 时比较 HEAD 与暂存区，否则比较暂存区与工作区（含未跟踪文件）。
+    /// `path` 限定单个文件或目录。
+    pub fn diff(&self, staged: bool, path: Option<&str>) -> Result<Vec<FileDiff>> {
+        let mut opts = DiffOptions::new();
+        // 路径按字面匹配，文件名中的 `*`、`[` 等不作为通配符
+        if let Some(path) = path {
+            opts.pathspec(path).disable_pathspec_match(true);
+        }
+
+        let mut diff = if staged {
+            self.repo.diff_tree_to_index(self.head_tree()?.as_ref(), None, Some(&mut opts))?
+        } else {
+            opts.include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .show_untracked_content(true);
+            self.repo.diff_index_to_workdir(None, Some(&mut opts))?
+        };
+
+        let mut find = DiffFindOptions::new();
+        find.renames(true);
+        diff.find_similar(Some(&mut find))?;
+
+        file_diffs(&diff)
+    }
+
+    /// 已暂存变更的补丁文本，超过 `max_bytes` 时截断
+    pub fn staged_patch(&self, max_bytes: usize) -> Result<String> {
+        let diff = self.repo.diff_tree_to_index(self.head_tree()?.as_ref(), None, None)?;
+        let mut patch = String::new();
+        diff.print(DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin());
+            }
+            patch.push_str(&String::from_utf8_lossy(line.content()));
+            patch.len() < max_bytes
+        })
+        .or_else(|e| if e.code() == ErrorCode::User { Ok(()) } else { Err(e) })?;
+
+        if patch.len() > max_bytes {
+            let mut end = max_bytes;
+            while !patch.is_char_boundary(end) {
+                end -= 1;
+            }
+            patch.truncate(end);
+            patch.push_str("\n…[差异过长，已截断]\n");
+        }
+        Ok(patch)
+    }
+
+    /// 暂存文件或目录（含删除）
+    pub fn stage(&self, paths: &[String]) -> Result<()> {
+        let mut index = self.repo.index()?;
+        index.add_all(paths, IndexAddOption::DEFAULT, None)?;
+        index.update_all(paths, None)?;
+        index.write()?;
+        Ok(())
+    }
+
+    /// 取消暂存，恢复为 HEAD 中的状态
+    pub fn unstage(&self, paths: &[String]) -> Result<()> {
+        match self.repo.head() {
+            Ok(head) => {
+                let commit = head.peel_to_commit()?;
+                self.repo.reset_default(Some(commit.as_object()), paths)?;
+            }
+            // 空仓库：直接从暂存区移除
+            Err(e) if e.code() == ErrorCode::UnbornBranch => {
+                let mut index = self.repo.index()?;
+                index.remove_all(paths, None)?;
+                index.write()?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+        Ok(())
+    }
+
+    /// 暂存文件中的单个差异块
+    ///
+    /// `hunk_index` 为该文件未暂存差异（`diff(false, Some(path))`）中的序号。
+    pub fn stage_hunk(&self, path: &str, hunk_index: usize) -> Result<()> {
+        let mut opts = Self::file_diff_options(path);
+        opts.include_untracked(true).show_untracked_content(true);
+        let diff = self.repo.diff_index_to_workdir(None, Some(&mut opts))?;
+        self.apply_hunk(&diff, hunk_index)
+    }
+
+    /// 取消暂存单个差异块
+    ///
+    /// `hunk_index` 为该文件已暂存差异（`diff(true, Some(path))`）中的序号。
+    pub fn unstage_hunk(&self, path: &str, hunk_index: usize) -> Result<()> {
+        let mut opts = Self::file_diff_options(path);
+        // 反向差异：暂存区 -> HEAD，应用后该块恢复为 HEAD 中的内容
+        opts.reverse(true);
+        let diff = self.repo.diff_tree_to_index(self.head_tree()?.as_ref(), None, Some(&mut opts))?;
+        self.apply_hunk(&diff, hunk_index)
+    }
+
+    fn file_diff_options(path: &str) -> DiffOptions {
+        let mut opts = DiffOptions::new();
+        opts.pathspec(path).disable_pathspec_match(true);
+        opts
+    }
+
+    /// 只把指定序号的差异块应用到暂存区
+    fn apply_hunk(&self, diff: &Diff, hunk_index: usize) -> Result<()> {
+        let hunks: usize = file_diffs(diff)?.iter().map(|f| f.hunks.len()).sum();
+        if hunk_index >= hunks {
+            return Err(AppError::GitError(format!("差异块不存在: {}（共 {} 个）", hunk_index, hunks)));
+        }
+
+        let seen = Cell::new(0usize);
+        let mut opts = ApplyOptions::new();
+        opts.hunk_callback(|_| {
+            let current = seen.get();
+            seen.set(current + 1);
+            current == hunk_index
+        });
+        self.repo.apply(diff, ApplyLocation::Index, Some(&mut opts))?;
+        Ok(())
+    }
+
+    /// 提交已暂存的变更
+    pub fn commit(&self, message: &str) -> Result<CommitInfo> {
+        let message = message.trim();
+        if message.is_empty() {
+            return Err(AppError::GitError("提交说明不能为空".to_string()));
+        }
+
+        let mut index = self.repo.index()?;
+        if index.has_conflicts() {
+            return Err(AppError::GitError("存在未解决的冲突".to_string()));
+        }
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+
+        let parent = match self.repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+            Err(e) => return Err(e.into()),
+        };
+        let unchanged = match &parent {
+            Some(parent) => parent.tree_id() == tree.id(),
+            None => tree.is_empty(),
+        };
+        if unchanged {
+            return Err(AppError::GitError("没有已暂存的变更".to_string()));
+        }
+
+        let signature = self.signature()?;
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let id = self.repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?;
+        eprintln!("[GitRepo::commit] 已提交: {}", id);
+        Ok(commit_info(&self.repo.find_commit(id)?))
+    }
+
+    /// 创建分支，`start_point` 为空时从 HEAD 创建
+    pub fn create_branch(&self, name: &str, start_point: Option<&str>, checkout: bool) -> Result<()> {
+        if !Branch::name_is_valid(name)? {
+            return Err(AppError::GitError(format!("无效的分支名: {}", name)));
+        }
+        let target = match start_point {
+            Some(rev) => self.repo.revparse_single(rev)?.peel_to_commit()?,
+            None => self.repo.head()?.peel_to_commit()?,
+        };
+        self.repo.branch(name, &target, false)?;
+
+        if checkout {
+            self.checkout_branch(name)?;
+        }
+        Ok(())
+    }
+
+    /// 切换分支
+    ///
+    /// 只有远程分支时创建同名的本地跟踪分支。本地修改与目标分支冲突时失败，不会覆盖修改。
+    pub fn checkout_branch(&self, name: &str) -> Result<()> {
+        let branch = match self.repo.find_branch(name, BranchType::Local) {
+            Ok(branch) => branch,
+            Err(e) if e.code() == ErrorCode::NotFound => {
+                let remote = self.repo.find_branch(name, BranchType::Remote)
+                    .map_err(|_| AppError::GitError(format!("分支不存在: {}", name)))?;
+                let local_name = name.split_once('/').map(|(_, n)| n).unwrap_or(name);
+                let commit = remote.get().peel_to_commit()?;
+                let mut local = self.repo.branch(local_name, &commit, false)?;
+                local.set_upstream(Some(name))?;
+                local
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let reference = branch.into_reference();
+        let refname = reference.name()
+            .ok_or_else(|| AppError::GitError("分支名不是有效的 UTF-8".to_string()))?
+            .to_string();
+        let target = reference.peel_to_commit()?;
+
+        let mut checkout = CheckoutBuilder::new();
+        checkout.safe();
+        self.repo.checkout_tree(target.as_object(), Some(&mut checkout))?;
+        self.repo.set_head(&refname)?;
+        eprintln!("[GitRepo::checkout_branch] 已切换到 {}", refname);
+        Ok(())
+    }
+
+    /// 储藏本地修改，返回储藏的提交 ID
+    pub fn stash_save(&mut self, message: Option<&str>, include_untracked: bool) -> Result<String> {
+        let signature = self.signature()?;
+        let flags = if include_untracked { StashFlags::INCLUDE_UNTRACKED } else { StashFlags::DEFAULT };
+        let id = self.repo.stash_save(&signature, message.unwrap_or_default(), Some(flags))
+            .map_err(|e| match e.code() {
+                ErrorCode::NotFound => AppError::GitError("没有可储藏的修改".to_string()),
+                _ => e.into(),
+            })?;
+        Ok(id.to_string())
+    }
+
+    /// 储藏列表（最新的在前）
+    pub fn stash_list(&mut self) -> Result<Vec<StashInfo>> {
+        let mut list = Vec::new();
+        self.repo.stash_foreach(|index, message, id| {
+            list.push(StashInfo { index, id: id.to_string(), message: message.to_string() });
+            true
+        })?;
+        Ok(list)
+    }
+
+    /// 应用储藏，`pop` 为 true 时应用成功后删除
+    pub fn stash_apply(&mut self, index: usize, pop: bool) -> Result<()> {
+        if pop {
+            self.repo.stash_pop(index, None)?;
+        } else {
+            self.repo.stash_apply(index, None)?;
+        }
+        Ok(())
+    }
+
+    /// 删除储藏
+    pub fn stash_drop(&mut self, index: usize) -> Result<()> {
+        self.repo.stash_drop(index)?;
+        Ok(())
+    }
+
+    /// 本地和远程分支
+    pub fn branches(&self) -> Result<Vec<BranchInfo>> {
+        let mut list = Vec::new();
+        for item in self.repo.branches(None)? {
+            let (branch, branch_type) = item?;
+            let Some(name) = branch.name()?.map(String::from) else {
+                continue;
+            };
+            // 跳过 origin/HEAD 这类符号引用
+            if branch.get().symbolic_target().is_some() {
+                continue;
+            }
+
+            let upstream = branch
+                .upstream()
+                .ok()
+                .and_then(|u| u.name().ok().flatten().map(String::from));
+
+            list.push(BranchInfo {
+                name,
+                is_head: branch.is_head(),
+                is_remote: branch_type == BranchType::Remote,
+                upstream,
+                commit: branch.get().target().map(|id| id.to_string()),
+            });
+        }
+
+        list.sort_by(|a, b| a.is_remote.cmp(&b.is_remote).then_with(|| a.name.cmp(&b.name)));
+        Ok(list)
+    }
+
+    /// 分页的提交历史（从 HEAD 开始，按时间倒序）
+    ///
+    /// `path` 不为空时只返回修改过该路径的提交。
+    pub fn log(&self, skip: usize, limit: usize, path: Option<&str>) -> Result<CommitPage> {
+        // 空仓库没有提交
+        if self.repo.head().is_err_and(|e| e.code() == ErrorCode::UnbornBranch) {
+            return Ok(CommitPage { commits: Vec::new(), has_more: false });
+        }
+
+        let mut walk = self.repo.revwalk()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+        walk.push_head()?;
+
+        let mut matched = 0;
+        let mut commits = Vec::new();
+        for id in walk {
+            let commit = self.repo.find_commit(id?)?;
+            if let Some(path) = path {
+                if !self.touches(&commit, path)? {
+                    continue;
+                }
+            }
+
+            matched += 1;
+            if matched <= skip {
+                continue;
+            }
+            // 已取满一页且仍有匹配的提交
+            if commits.len() == limit {
+                return Ok(CommitPage { commits, has_more: true });
+            }
+            commits.push(commit_info(&commit));
+        }
+
+        Ok(CommitPage { commits, has_more: false })
+    }
+
+    /// 转换为相对仓库根目录的路径（接受绝对路径）
+    ///
+    /// 结果只能包含普通组件，`..`、根目录等会离开仓库的写法一律拒绝。
+    pub fn relative_path(&self, path: &str) -> Result<String> {
+        let workdir = self.repo.workdir()
+            .ok_or_else(|| AppError::GitError("不支持裸仓库".to_string()))?;
+        let original = Path::new(path);
+        let relative = if original.is_absolute() {
+            // 工作目录可能经过符号链接，比较前统一规范化
+            let workdir = workdir.canonicalize().unwrap_or_else(|_| workdir.to_path_buf());
+            let full = original.canonicalize().unwrap_or_else(|_| original.to_path_buf());
+            full.strip_prefix(&workdir)
+                .map_err(|_| AppError::InvalidPath(format!("不在仓库中: {}", original.display())))?
+                .to_path_buf()
+        } else {
+            original.to_path_buf()
+        };
+        let inside = relative.components().next().is_some()
+            && relative.components().all(|component| matches!(component, Component::Normal(_)));
+        if !inside {
+            return Err(AppError::InvalidPath(format!("不在仓库中: {}", original.display())));
+        }
+        Ok(relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    /// 逐行追溯工作区中的文件
+    ///
+    /// 未提交的修改（包括未跟踪的文件）标记为 `uncommitted`。
+    pub fn blame(&self, path: &str) -> Result<FileBlame> {
+        let relative = self.relative_path(path)?;
+        let workdir = self.repo.workdir()
+            .ok_or_else(|| AppError::GitError("不支持裸仓库".to_string()))?;
+        // 仓库内指向外部的符号链接同样拒绝
+        let full = workdir.join(&relative).canonicalize()?;
+        if !full.starts_with(workdir.canonicalize()?) {
+            return Err(AppError::InvalidPath(format!("不在仓库中: {}", path)));
+        }
+        let content = std::fs::read(&full)?;
+        if content.contains(&0) {
+            return Err(AppError::GitError("二进制文件不支持逐行追溯".to_string()));
+        }
+
+        // 只有 HEAD 中存在的文件才能追溯，新文件全部视为未提交
+        let tracked = self.head_tree()?
+            .map(|tree| tree.get_path(Path::new(&relative)).is_ok())
+            .unwrap_or(false);
+        let committed = if tracked {
+            let mut opts = BlameOptions::new();
+            Some(self.repo.blame_file(Path::new(&relative), Some(&mut opts))?)
+        } else {
+            None
+        };
+        let blame = match &committed {
+            Some(committed) => Some(committed.blame_buffer(&content)?),
+            None => None,
+        };
+
+        let text = String::from_utf8_lossy(&content);
+        let mut commits: HashMap<git2::Oid, CommitInfo> = HashMap::new();
+        let mut lines = Vec::new();
+
+        for (index, content) in text.lines().enumerate() {
+            let line = index + 1;
+            let id = blame.as_ref()
+                .and_then(|b| b.get_line(line))
+                .map(|hunk| hunk.final_commit_id())
+                .filter(|id| !id.is_zero());
+
+            let info = match id {
+                Some(id) => {
+                    if let std::collections::hash_map::Entry::Vacant(entry) = commits.entry(id) {
+                        entry.insert(commit_info(&self.repo.find_commit(id)?));
+                    }
+                    commits.get(&id)
+                }
+                None => None,
+            };
+
+            lines.push(BlameLine {
+                line,
+                content: content.to_string(),
+                commit: info.map(|c| c.id.clone()),
+                short_id: info.map(|c| c.short_id.clone()),
+                summary: info.map(|c| c.summary.clone()),
+                author_name: info.map(|c| c.author_name.clone()),
+                author_email: info.map(|c| c.author_email.clone()),
+                time: info.map(|c| c.time.clone()),
+                agent: info.is_some_and(|c| c.agent),
+                uncommitted: info.is_none(),
+            });
+        }
+
+        Ok(FileBlame { path: relative, lines })
+    }
+
+    /// 修改过文件的提交（跟随重命名），按时间倒序分页
+    pub fn file_history(&self, path: &str, skip: usize, limit: usize) -> Result<FileHistoryPage> {
+        let mut current = self.relative_path(path)?;
+        if self.repo.head().is_err_and(|e| e.code() == ErrorCode::UnbornBranch) {
+            return Ok(FileHistoryPage { entries: Vec::new(), has_more: false });
+        }
+
+        let mut walk = self.repo.revwalk()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+        walk.push_head()?;
+        walk.simplify_first_parent()?;
+
+        let mut matched = 0;
+        let mut entries = Vec::new();
+        for id in walk {
+            let commit = self.repo.find_commit(id?)?;
+            let Some((kind, old_path)) = self.file_change(&commit, &current)? else {
+                continue;
+            };
+
+            matched += 1;
+            if matched > skip {
+                if entries.len() == limit {
+                    return Ok(FileHistoryPage { entries, has_more: true });
+                }
+                entries.push(FileHistoryEntry {
+                    commit: commit_info(&commit),
+                    path: current.clone(),
+                    old_path: old_path.clone(),
+                    kind,
+                });
+            }
+
+            match (kind, old_path) {
+                // 更早的提交中文件使用原路径
+                (GitChangeKind::Renamed, Some(old_path)) => current = old_path,
+                // 文件在此提交中创建，历史到此为止
+                (GitChangeKind::Added, _) => break,
+                _ => {}
+            }
+        }
+
+        Ok(FileHistoryPage { entries, has_more: false })
+    }
+
+    /// 提交对文件的修改（与第一个父提交比较），未修改时返回 None
+    fn file_change(&self, commit: &git2::Commit, path: &str) -> Result<Option<(GitChangeKind, Option<String>)>> {
+        let tree = commit.tree()?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+
+        let entry_id = |tree: Option<&Tree>| {
+            tree.and_then(|t| t.get_path(Path::new(path)).ok()).map(|e| e.id())
+        };
+        let (old, new) = (entry_id(parent_tree.as_ref()), entry_id(Some(&tree)));
+        match (old, new) {
+            (_, None) => Ok(None),
+            (Some(old), Some(new)) if old == new => Ok(None),
+            (Some(_), Some(_)) => Ok(Some((GitChangeKind::Modified, None))),
+            (None, Some(_)) => {
+                // 父提交中不存在：检查是否由其他文件重命名而来
+                let mut diff = self.repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+                let mut find = DiffFindOptions::new();
+                find.renames(true);
+                diff.find_similar(Some(&mut find))?;
+
+                let renamed_from = diff.deltas()
+                    .filter(|d| d.status() == Delta::Renamed)
+                    .find(|d| delta_paths(d).1.as_deref() == Some(path))
+                    .and_then(|d| delta_paths(&d).0);
+                Ok(Some(match renamed_from {
+                    Some(old_path) => (GitChangeKind::Renamed, Some(old_path)),
+                    None => (GitChangeKind::Added, None),
+                }))
+            }
+        }
+    }
+
+    /// 提交是否修改了指定路径（与第一个父提交比较）
+    fn touches(&self, commit: &git2::Commit, path: &str) -> Result<bool> {
+        let tree = commit.tree()?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let mut opts = DiffOptions::new();
+        opts.pathspec(path).disable_pathspec_match(true);
+        let diff = self.repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))?;
+        Ok(diff.deltas().len() > 0)
+    }
+}
+
+fn index_kind(flags: Status) -> Option<GitChangeKind> {
+    if flags.contains(Status::INDEX_NEW) {
+        Some(GitChangeKind::Added)
+    } else if flags.contains(Status::INDEX_RENAMED) {
+        Some(GitChangeKind::Renamed)
+    } else if flags.contains(Status::INDEX_DELETED) {
+        Some(GitChangeKind::Deleted)
+    } else if flags.contains(Status::INDEX_TYPECHANGE) {
+        Some(GitChangeKind::TypeChange)
+    } else if flags.contains(Status::INDEX_MODIFIED) {
+        Some(GitChangeKind::Modified)
+    } else {
+        None
+    }
+}
+
+fn workdir_kind(flags: Status) -> Option<GitChangeKind> {
+    if flags.contains(Status::WT_RENAMED) {
+        Some(GitChangeKind::Renamed)
+    } else if flags.contains(Status::WT_DELETED) {
+        Some(GitChangeKind::Deleted)
+    } else if flags.contains(Status::WT_TYPECHANGE) {
+        Some(GitChangeKind::TypeChange)
+    } else if flags.contains(Status::WT_MODIFIED) {
+        Some(GitChangeKind::Modified)
+    } else {
+        None
+    }
+}
+
+fn delta_paths(delta: &DiffDelta) -> (Option<String>, Option<String>) {
+    let old = delta.old_file().path().map(|p| p.to_string_lossy().replace('\\', "/"));
+    let new = delta.new_file().path().map(|p| p.to_string_lossy().replace('\\', "/"));
+    (old, new)
+}
+
+fn file_status(delta: Option<DiffDelta>, kind: GitChangeKind, fallback: Option<&str>) -> GitFileStatus {
+    let (old, new) = delta.as_ref().map(delta_paths).unwrap_or((None, None));
+    let path = new.clone().or(old.clone()).or(fallback.map(String::from)).unwrap_or_default();
+    let old_path = match kind {
+        GitChangeKind::Renamed | GitChangeKind::Copied => old.filter(|o| *o != path),
+        _ => None,
+    };
+    GitFileStatus { path, old_path, kind }
+}
+
+fn delta_kind(status: Delta) -> GitChangeKind {
+    match status {
+        Delta::Added => GitChangeKind::Added,
+        Delta::Deleted => GitChangeKind::Deleted,
+        Delta::Renamed => GitChangeKind::Renamed,
+        Delta::Copied => GitChangeKind::Copied,
+        Delta::Typechange => GitChangeKind::TypeChange,
+        Delta::Untracked => GitChangeKind::Untracked,
+        Delta::Conflicted => GitChangeKind::Conflicted,
+        _ => GitChangeKind::Modified,
+    }
+}
+
+/// 将 git2 差异转换为结构化的文件差异
+pub fn file_diffs(diff: &Diff) -> Result<Vec<FileDiff>> {
+    let mut files = Vec::new();
+
+    for index in 0..diff.deltas().len() {
+        let Some(delta) = diff.get_delta(index) else {
+            continue;
+        };
+        let kind = delta_kind(delta.status());
+        let status = file_status(Some(delta), kind, None);
+        let mut file = FileDiff {
+            path: status.path,
+            old_path: status.old_path,
+            kind,
+            binary: false,
+            additions: 0,
+            deletions: 0,
+            hunks: Vec::new(),
+        };
+
+        let patch = Patch::from_diff(diff, index)?;
+        match patch {
+            Some(patch) if !patch.delta().flags().is_binary() => push_hunks(&patch, &mut file)?,
+            _ => file.binary = true,
+        }
+
+        files.push(file);
+    }
+
+    Ok(files)
+}
+
+/// 两段内容之间的差异（不需要仓库，用于替换预览等）
+pub fn text_diff(path: &str, old: &[u8], new: &[u8]) -> Result<FileDiff> {
+    let mut file = FileDiff {
+        path: path.to_string(),
+        old_path: None,
+        kind: GitChangeKind::Modified,
+        binary: false,
+        additions: 0,
+        deletions: 0,
+        hunks: Vec::new(),
+    };
+    let patch = Patch::from_buffers(old, Some(Path::new(path)), new, Some(Path::new(path)), None)?;
+    push_hunks(&patch, &mut file)?;
+    Ok(file)
+}
+
+fn push_hunks(patch: &Patch, file: &mut FileDiff) -> Result<()> {
+    for hunk_index in 0..patch.num_hunks() {
+        let hunk = convert_hunk(patch, hunk_index)?;
+        for line in &hunk.lines {
+            match line.kind {
+                DiffLineKind::Addition => file.additions += 1,
+                DiffLineKind::Deletion => file.deletions += 1,
+                DiffLineKind::Context => {}
+            }
+        }
+        file.hunks.push(hunk);
+    }
+    Ok(())
+}
+
+fn convert_hunk(patch: &Patch, hunk_index: usize) -> Result<DiffHunk> {
+    let (hunk, line_count) = patch.hunk(hunk_index)?;
+    let mut lines: Vec<DiffLine> = Vec::with_capacity(line_count);
+
+    for line_index in 0..line_count {
+        let line = patch.line_in_hunk(hunk_index, line_index)?;
+        let kind = match line.origin() {
+            ' ' => DiffLineKind::Context,
+            '+' => DiffLineKind::Addition,
+            '-' => DiffLineKind::Deletion,
+            // '=' '>' '<'：上一行没有结尾换行符
+            _ => {
+                if let Some(last) = lines.last_mut() {
+                    last.no_newline = true;
+                }
+                continue;
+            }
+        };
+
+        let content = String::from_utf8_lossy(line.content());
+        lines.push(DiffLine {
+            kind,
+            content: content.trim_end_matches(['\n', '\r']).to_string(),
+            old_lineno: line.old_lineno(),
+            new_lineno: line.new_lineno(),
+            no_newline: false,
+        });
+    }
+
+    Ok(DiffHunk {
+        header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+        old_start: hunk.old_start(),
+        old_lines: hunk.old_lines(),
+        new_start: hunk.new_start(),
+        new_lines: hunk.new_lines(),
+        lines,
+    })
+}
+
+/// 提交信息
+pub fn commit_info(commit: &git2::Commit) -> CommitInfo {
+    let author = commit.author();
+    let time = commit.time();
+    let offset = chrono::FixedOffset::east_opt(time.offset_minutes() * 60)
+        .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).unwrap());
+    let time = chrono::DateTime::from_timestamp(time.seconds(), 0)
+        .map(|t| t.with_timezone(&offset).to_rfc3339())
+        .unwrap_or_default();
+    let id = commit.id().to_string();
+
+    CommitInfo {
+        short_id: id.chars().take(7).collect(),
+        id,
+        summary: commit.summary().unwrap_or_default().to_string(),
+        message: commit.message().unwrap_or_default().to_string(),
+        author_name: author.name().unwrap_or_default().to_string(),
+        author_email: author.email().unwrap_or_default().to_string(),
+        time,
+        parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        agent: is_agent_commit(commit),
+    }
+}
+
+/// 是否为 Claude 会话的提交：作者为代理身份，或说明中带有 Claude 的 Co-Authored-By
+pub fn is_agent_commit(commit: &git2::Commit) -> bool {
+    if commit.author().email() == Some(AGENT_EMAIL) {
+        return true;
+    }
+    commit.message().unwrap_or_default().lines().any(|line| {
+        let line = line.trim().to_ascii_lowercase();
+        line.starts_with("co-authored-by:") && line.contains("claude")
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use git2::Signature;
+    use tempfile::TempDir;
+
+    /// 在临时目录中创建仓库
+    ///
+    /// 返回的 [`TempDir`] 在 drop 时删除整个目录，测试期间需要保持持有。
+    pub(crate) fn temp_repo() -> (TempDir, Repository) {
+        let dir = TempDir::with_prefix("git-test-").unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Tester").unwrap();
+        config.set_str("user.email", "tester@example.com").unwrap();
+        (dir, repo)
+    }
+
+    /// 暂存全部文件并提交
+    pub(crate) fn commit_all(repo: &Repository, message: &str) -> git2::Oid {
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Tester", "tester@example.com").unwrap();
+        let parents: Vec<git2::Commit> = repo.head().ok()
+            .and_then(|h| h.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap()
+    }
+
+    #[test]
+    fn status_reports_staged_unstaged_untracked_and_renames() {
+        let (tmp, repo) = temp_repo();
+        let dir = tmp.path();
+        std::fs::write(dir.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "a fairly long line so rename detection has content\n").unwrap();
+        commit_all(&repo, "init");
+
+        // 暂存的重命名
+        std::fs::rename(dir.join("b.txt"), dir.join("c.txt")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("b.txt")).unwrap();
+        index.add_path(Path::new("c.txt")).unwrap();
+        index.write().unwrap();
+        // 未暂存的修改和未跟踪的文件
+        std::fs::write(dir.join("a.txt"), "one\n2\nthree\n").unwrap();
+        std::fs::write(dir.join("new.txt"), "new\n").unwrap();
+
+        let git = GitRepo::open(dir).unwrap();
+        let status = git.status().unwrap();
+        assert!(status.head.is_some());
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.staged[0].kind, GitChangeKind::Renamed);
+        assert_eq!(status.staged[0].path, "c.txt");
+        assert_eq!(status.staged[0].old_path.as_deref(), Some("b.txt"));
+        assert_eq!(status.unstaged.len(), 1);
+        assert_eq!(status.unstaged[0].kind, GitChangeKind::Modified);
+        assert_eq!(status.untracked[0].path, "new.txt");
+    }
+
+    #[test]
+    fn diff_has_hunks_with_line_numbers() {
+        let (tmp, repo) = temp_repo();
+        let dir = tmp.path();
+        std::fs::write(dir.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        commit_all(&repo, "init");
+        std::fs::write(dir.join("a.txt"), "one\n2\nthree").unwrap();
+
+        let git = GitRepo::open(dir).unwrap();
+        let diffs = git.diff(false, Some("a.txt")).unwrap();
+        assert_eq!(diffs.len(), 1);
+        let file = &diffs[0];
+        assert_eq!((file.additions, file.deletions), (2, 2));
+        let hunk = &file.hunks[0];
+        assert_eq!((hunk.old_start, hunk.new_start), (1, 1));
+
+        let added: Vec<_> = hunk.lines.iter().filter(|l| l.kind == DiffLineKind::Addition).collect();
+        assert_eq!(added[0].content, "2");
+        assert_eq!(added[0].new_lineno, Some(2));
+        assert_eq!(added[0].old_lineno, None);
+        assert!(added[1].no_newline);
+
+        assert!(git.diff(true, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn log_pages_and_filters_by_path() {
+        let (tmp, repo) = temp_repo();
+        let dir = tmp.path();
+        let git = GitRepo::open(dir).unwrap();
+        assert!(git.log(0, 10, None).unwrap().commits.is_empty());
+        assert!(git.current_branch().unwrap().is_some());
+
+        for i in 0..5 {
+            std::fs::write(dir.join("a.txt"), format!("{}\n", i)).unwrap();
+            if i % 2 == 0 {
+                std::fs::write(dir.join("even.txt"), format!("{}\n", i)).unwrap();
+            }
+            commit_all(&repo, &format!("commit {}", i));
+        }
+
+        let page = git.log(0, 2, None).unwrap();
+        assert_eq!(page.commits.len(), 2);
+        assert!(page.has_more);
+        assert_eq!(page.commits[0].summary, "commit 4");
+
+        let last = git.log(4, 2, None).unwrap();
+        assert_eq!(last.commits.len(), 1);
+        assert!(!last.has_more);
+        assert!(last.commits[0].parents.is_empty());
+
+        let even = git.log(0, 10, Some("even.txt")).unwrap();
+        let summaries: Vec<_> = even.commits.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, ["commit 4", "commit 2", "commit 0"]);
+
+        let branches = git.branches().unwrap();
+        assert_eq!(branches.len(), 1);
+        assert!(branches[0].is_head);
+    }
+
+    #[test]
+    fn stage_and_unstage_single_hunks() {
+        let (tmp, repo) = temp_repo();
+        let dir = tmp.path();
+        let lines: Vec<String> = (1..=20).map(|i| format!("line {}", i)).collect();
+        std::fs::write(dir.join("a.txt"), lines.join("\n") + "\n").unwrap();
+        commit_all(&repo, "init");
+
+        // 相距较远的两处修改形成两个差异块
+        let mut changed = lines.clone();
+        changed[1] = "first change".to_string();
+        changed[17] = "second change".to_string();
+        std::fs::write(dir.join("a.txt"), changed.join("\n") + "\n").unwrap();
+
+        let git = GitRepo::open(dir).unwrap();
+        assert_eq!(git.diff(false, Some("a.txt")).unwrap()[0].hunks.len(), 2);
+        assert!(git.stage_hunk("a.txt", 2).is_err());
+
+        git.stage_hunk("a.txt", 1).unwrap();
+        let staged = git.diff(true, Some("a.txt")).unwrap();
+        assert_eq!(staged[0].hunks.len(), 1);
+        assert!(staged[0].hunks[0].lines.iter().any(|l| l.content == "second change"));
+        assert_eq!(git.diff(false, Some("a.txt")).unwrap()[0].hunks.len(), 1);
+
+        git.unstage_hunk("a.txt", 0).unwrap();
+        assert!(git.diff(true, None).unwrap().is_empty());
+        // 工作区内容不受影响
+        assert_eq!(git.diff(false, Some("a.txt")).unwrap()[0].hunks.len(), 2);
+    }
+
+    #[test]
+    fn commit_branch_and_stash() {
+        let (tmp, _repo) = temp_repo();
+        let dir = tmp.path();
+        let mut git = GitRepo::open(dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "one\n").unwrap();
+
+        assert!(git.commit("empty").is_err());
+        git.stage(&["a.txt".to_string()]).unwrap();
+        assert!(git.staged_patch(1024).unwrap().contains("+one"));
+        let first = git.commit("  add a\n").unwrap();
+        assert_eq!(first.summary, "add a");
+        assert!(git.commit("again").is_err());
+
+        git.create_branch("feature", None, true).unwrap();
+        assert_eq!(git.current_branch().unwrap().as_deref(), Some("feature"));
+        assert!(git.create_branch("bad..name", None, false).is_err());
+
+        std::fs::write(dir.join("a.txt"), "two\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "new\n").unwrap();
+        git.stage(&["a.txt".to_string(), "b.txt".to_string()]).unwrap();
+        git.unstage(&["b.txt".to_string()]).unwrap();
+        let status = git.status().unwrap();
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.untracked[0].path, "b.txt");
+
+        git.stash_save(Some("wip"), true).unwrap();
+        assert!(git.status().unwrap().untracked.is_empty());
+        let stashes = git.stash_list().unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert!(stashes[0].message.contains("wip"));
+
+        let default_branch = git.branches().unwrap().into_iter().find(|b| !b.is_head).unwrap().name;
+        git.checkout_branch(&default_branch).unwrap();
+        git.stash_apply(0, true).unwrap();
+        assert!(git.stash_list().unwrap().is_empty());
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "two\n");
+    }
+
+    #[test]
+    fn blame_marks_agent_and_uncommitted_lines() {
+        let (tmp, repo) = temp_repo();
+        let dir = tmp.path();
+        std::fs::write(dir.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        commit_all(&repo, "init");
+
+        // 代理身份的提交修改第二行
+        std::fs::write(dir.join("a.txt"), "one\nTWO\nthree\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let agent = Signature::now(AGENT_NAME, AGENT_EMAIL).unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &agent, &agent, "agent edit", &tree, &[&parent]).unwrap();
+
+        // 未提交的第三行和新增的第四行
+        std::fs::write(dir.join("a.txt"), "one\nTWO\n3\nfour\n").unwrap();
+
+        let git = GitRepo::open(dir).unwrap();
+        let absolute = dir.join("a.txt").to_string_lossy().to_string();
+        let blame = git.blame(&absolute).unwrap();
+        assert_eq!(blame.path, "a.txt");
+        assert_eq!(blame.lines.len(), 4);
+        assert_eq!(blame.lines[0].summary.as_deref(), Some("init"));
+        assert!(!blame.lines[0].agent);
+        assert!(blame.lines[1].agent);
+        assert_eq!(blame.lines[1].summary.as_deref(), Some("agent edit"));
+        assert!(blame.lines[2].uncommitted && blame.lines[3].uncommitted);
+        assert_eq!(blame.lines[3].content, "four");
+
+        // 未跟踪的文件全部是未提交的行
+        std::fs::write(dir.join("new.txt"), "x\n").unwrap();
+        assert!(git.blame("new.txt").unwrap().lines[0].uncommitted);
+
+        // 仓库之外的路径
+        let outside = tempfile::TempDir::with_prefix("git-outside-").unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "secret\n").unwrap();
+        let escape = dir.join("..").join(outside.path().file_name().unwrap()).join("secret.txt");
+        let escape = escape.to_string_lossy().to_string();
+        for path in ["../secret.txt", "a/../../secret.txt", "", escape.as_str()] {
+            assert!(matches!(git.blame(path), Err(AppError::InvalidPath(_))), "{}", path);
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outside.path().join("secret.txt"), dir.join("link.txt")).unwrap();
+            assert!(matches!(git.blame("link.txt"), Err(AppError::InvalidPath(_))));
+        }
+    }
+
+    #[test]
+    fn file_history_follows_renames() {
+        let (tmp, repo) = temp_repo();
+        let dir = tmp.path();
+        let body = "a fairly long line so rename detection has content\n".repeat(5);
+        std::fs::write(dir.join("old.txt"), &body).unwrap();
+        std::fs::write(dir.join("other.txt"), "x\n").unwrap();
+        commit_all(&repo, "add old");
+        std::fs::write(dir.join("other.txt"), "y\n").unwrap();
+        commit_all(&repo, "unrelated");
+        std::fs::rename(dir.join("old.txt"), dir.join("new.txt")).unwrap();
+        commit_all(&repo, "rename");
+        std::fs::write(dir.join("new.txt"), format!("{}more\n", body)).unwrap();
+        commit_all(&repo, "edit new");
+
+        let git = GitRepo::open(dir).unwrap();
+        let history = git.file_history("new.txt", 0, 10).unwrap();
+        let summaries: Vec<_> = history.entries.iter().map(|e| e.commit.summary.as_str()).collect();
+        assert_eq!(summaries, ["edit new", "rename", "add old"]);
+        assert_eq!(history.entries[1].kind, GitChangeKind::Renamed);
+        assert_eq!(history.entries[1].old_path.as_deref(), Some("old.txt"));
+        assert_eq!(history.entries[2].path, "old.txt");
+        assert_eq!(history.entries[2].kind, GitChangeKind::Added);
+
+        let page = git.file_history("new.txt", 1, 1).unwrap();
+        assert_eq!(page.entries[0].commit.summary, "rename");
+        assert!(page.has_more);
+    }
+}
@@ -1,2 +1,21 @@
 pub mod config_store;
 pub mod logger;
+pub mod shell_tracker;
+pub mod pty;
+pub mod recorder;
+pub mod partial;
+pub mod delivery;
+pub mod scheduler;
+pub mod session_lock;
+pub mod git;
+pub mod worktree;
+pub mod sandbox;
+pub mod trash;
+pub mod file_ops;
+pub mod file_io;
+pub mod fs_watcher;
+pub mod walker;
+pub mod file_index;
+pub mod content_search;
+pub mod encoding;
+pub mod preview;
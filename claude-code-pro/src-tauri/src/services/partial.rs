@@ -0,0 +1,295 @@
+use crate::models::events::StreamEvent;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// 合并增量的最长等待时间
+const COALESCE_MAX_DELAY: Duration = Duration::from_millis(50);
+
+/// 单次合并的最大字节数
+const COALESCE_MAX_BYTES: usize = 4096;
+
+/// 内容块类型
+enum BlockKind {
+    Text,
+    Thinking,
+    ToolUse { id: String, name: String },
+    Other,
+}
+
+/// 部分消息组装器
+///
+/// 把 `--include-partial-messages` 产生的 `stream_event` 信封
+/// （`content_block_start` / `content_block_delta` / `message_delta` 等）
+/// 转换为 `TextDelta`、`ThinkingDelta`、`ToolInputDelta` 和 `MessageDelta`。
+#[derive(Default)]
+pub struct PartialAssembler {
+    blocks: HashMap<u64, BlockKind>,
+}
+
+impl PartialAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 转换一个 `stream_event` 的内层事件
+    ///
+    /// `envelope` 为完整的原始信封，无法识别时作为 `Unknown` 原样返回。
+    pub fn convert(&mut self, event: &Value, envelope: &Value) -> Vec<StreamEvent> {
+        let index = event.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+
+        match event.get("type").and_then(|t| t.as_str()) {
+            Some("message_start") => {
+                self.blocks.clear();
+                Vec::new()
+            }
+            Some("content_block_start") => {
+                let block = event.get("content_block").unwrap_or(&Value::Null);
+                let str_field = |key: &str| block.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+
+                let (kind, initial) = match block.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => (BlockKind::Text, delta_event(&BlockKind::Text, &str_field("text"))),
+                    Some("thinking") => (BlockKind::Thinking, delta_event(&BlockKind::Thinking, &str_field("thinking"))),
+                    Some("tool_use") => (BlockKind::ToolUse { id: str_field("id"), name: str_field("name") }, None),
+                    _ => (BlockKind::Other, None),
+                };
+                self.blocks.insert(index, kind);
+                initial.into_iter().collect()
+            }
+            Some("content_block_delta") => {
+                let delta = event.get("delta").unwrap_or(&Value::Null);
+                let text_field = |key: &str| delta.get(key).and_then(|v| v.as_str()).unwrap_or("");
+
+                let converted = match (delta.get("type").and_then(|t| t.as_str()), self.blocks.get(&index)) {
+                    (Some("text_delta"), _) => delta_event(&BlockKind::Text, text_field("text")),
+                    (Some("thinking_delta"), _) => delta_event(&BlockKind::Thinking, text_field("thinking")),
+                    (Some("input_json_delta"), Some(kind @ BlockKind::ToolUse { .. })) => {
+                        delta_event(kind, text_field("partial_json"))
+                    }
+                    // 签名只用于校验思考内容，前端不需要
+                    (Some("signature_delta"), _) => None,
+                    _ => Some(StreamEvent::Unknown { raw: envelope.clone() }),
+                };
+                converted.into_iter().collect()
+            }
+            Some("content_block_stop") => {
+                self.blocks.remove(&index);
+                Vec::new()
+            }
+            Some("message_delta") => vec![StreamEvent::MessageDelta {
+                stop_reason: event
+                    .pointer("/delta/stop_reason")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+                usage: event.get("usage").cloned(),
+            }],
+            Some("message_stop") | Some("ping") => Vec::new(),
+            _ => vec![StreamEvent::Unknown { raw: envelope.clone() }],
+        }
+    }
+}
+
+/// 按内容块类型生成增量事件，空增量返回 None
+fn delta_event(kind: &BlockKind, text: &str) -> Option<StreamEvent> {
+    if text.is_empty() {
+        return None;
+    }
+
+    match kind {
+        BlockKind::Text => Some(StreamEvent::TextDelta { text: text.to_string() }),
+        BlockKind::Thinking => Some(StreamEvent::ThinkingDelta { text: text.to_string() }),
+        BlockKind::ToolUse { id, name } => Some(StreamEvent::ToolInputDelta {
+            tool_use_id: id.clone(),
+            tool_name: name.clone(),
+            partial_json: text.to_string(),
+        }),
+        BlockKind::Other => None,
+    }
+}
+
+/// 增量合并器
+///
+/// 相邻的同类增量合并为一个事件再发送，避免逐 token 调用 IPC。
+/// 遇到其他事件、超过等待时间或字节上限时立即输出。
+/// 等待时间到期由调用方按 `deadline` 定时调用 `flush`。
+pub struct DeltaCoalescer {
+    pending: Option<StreamEvent>,
+    pending_since: Instant,
+    max_delay: Duration,
+    max_bytes: usize,
+}
+
+impl Default for DeltaCoalescer {
+    fn default() -> Self {
+        Self::new(COALESCE_MAX_DELAY, COALESCE_MAX_BYTES)
+    }
+}
+
+impl DeltaCoalescer {
+    pub fn new(max_delay: Duration, max_bytes: usize) -> Self {
+        Self {
+            pending: None,
+            pending_since: Instant::now(),
+            max_delay,
+            max_bytes,
+        }
+    }
+
+    /// 加入一个事件，可能触发输出
+    pub fn push<F>(&mut self, event: StreamEvent, out: &mut F)
+    where
+        F: FnMut(StreamEvent),
+    {
+        if !is_delta(&event) {
+            self.flush(out);
+            out(event);
+            return;
+        }
+
+        let merged = match self.pending.as_mut() {
+            Some(pending) => try_merge(pending, &event),
+            None => false,
+        };
+        if !merged {
+            self.flush(out);
+            self.pending = Some(event);
+            self.pending_since = Instant::now();
+        }
+
+        let size = self.pending.as_ref().map_or(0, delta_len);
+        if size >= self.max_bytes || self.pending_since.elapsed() >= self.max_delay {
+            self.flush(out);
+        }
+    }
+
+    /// 缓冲增量的最晚输出时间，无缓冲时为 None
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|_| self.pending_since + self.max_delay)
+    }
+
+    /// 输出缓冲中的增量
+    pub fn flush<F>(&mut self, out: &mut F)
+    where
+        F: FnMut(StreamEvent),
+    {
+        if let Some(event) = self.pending.take() {
+            out(event);
+        }
+    }
+}
+
+fn is_delta(event: &StreamEvent) -> bool {
+    matches!(
+        event,
+        StreamEvent::TextDelta { .. } | StreamEvent::ThinkingDelta { .. } | StreamEvent::ToolInputDelta { .. }
+    )
+}
+
+fn delta_len(event: &StreamEvent) -> usize {
+    match event {
+        StreamEvent::TextDelta { text } | StreamEvent::ThinkingDelta { text } => text.len(),
+        StreamEvent::ToolInputDelta { partial_json, .. } => partial_json.len(),
+        _ => 0,
+    }
+}
+
+/// 同类增量合并到 pending 中，成功返回 true
+fn try_merge(pending: &mut StreamEvent, next: &StreamEvent) -> bool {
+    match (pending, next) {
+        (StreamEvent::TextDelta { text }, StreamEvent::TextDelta { text: more })
+        | (StreamEvent::ThinkingDelta { text }, StreamEvent::ThinkingDelta { text: more }) => {
+            text.push_str(more);
+            true
+        }
+        (
+            StreamEvent::ToolInputDelta { tool_use_id, partial_json, .. },
+            StreamEvent::ToolInputDelta { tool_use_id: next_id, partial_json: more, .. },
+        ) if tool_use_id == next_id => {
+            partial_json.push_str(more);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn text(s: &str) -> StreamEvent {
+        StreamEvent::TextDelta { text: s.to_string() }
+    }
+
+    #[test]
+    fn coalesces_adjacent_deltas() {
+        let mut coalescer = DeltaCoalescer::new(Duration::from_secs(60), 4096);
+        let mut out = Vec::new();
+        let mut emit = |e| out.push(e);
+
+        coalescer.push(text("a"), &mut emit);
+        coalescer.push(text("b"), &mut emit);
+        coalescer.push(StreamEvent::ThinkingDelta { text: "t".to_string() }, &mut emit);
+        coalescer.push(StreamEvent::Error { error: "x".to_string() }, &mut emit);
+        coalescer.push(text("c"), &mut emit);
+        coalescer.flush(&mut emit);
+
+        let kinds: Vec<&str> = out.iter().map(StreamEvent::kind).collect();
+        assert_eq!(kinds, ["text_delta", "thinking_delta", "error", "text_delta"]);
+        assert!(matches!(&out[0], StreamEvent::TextDelta { text } if text == "ab"));
+    }
+
+    #[test]
+    fn flushes_at_byte_limit() {
+        let mut coalescer = DeltaCoalescer::new(Duration::from_secs(60), 4);
+        let mut out = Vec::new();
+        coalescer.push(text("ab"), &mut |e| out.push(e));
+        assert!(out.is_empty());
+        coalescer.push(text("cd"), &mut |e| out.push(e));
+        assert_eq!(out.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flushes_after_max_delay_without_next_push() {
+        let mut coalescer = DeltaCoalescer::new(Duration::from_millis(50), 4096);
+        let mut out = Vec::new();
+        assert!(coalescer.deadline().is_none());
+
+        coalescer.push(text("a"), &mut |e| out.push(e));
+        let deadline = coalescer.deadline().expect("pending delta");
+        assert_eq!(deadline, Instant::now() + Duration::from_millis(50));
+
+        // 模拟 read_events：输出管道没有新行，只有定时器会触发
+        let (_tx, mut rx) = tokio::sync::mpsc::channel::<String>(1);
+        tokio::select! {
+            _ = rx.recv() => panic!("no line expected"),
+            _ = tokio::time::sleep_until(deadline) => coalescer.flush(&mut |e| out.push(e)),
+        }
+
+        assert_eq!(Instant::now(), deadline);
+        assert!(matches!(out.as_slice(), [StreamEvent::TextDelta { text }] if text == "a"));
+        assert!(coalescer.deadline().is_none());
+    }
+
+    #[test]
+    fn tool_input_uses_block_identity() {
+        let mut assembler = PartialAssembler::new();
+        let start = json!({ "type": "content_block_start", "index": 1,
+            "content_block": { "type": "tool_use", "id": "toolu_1", "name": "Read", "input": {} } });
+        assert!(assembler.convert(&start, &start).is_empty());
+
+        let delta = json!({ "type": "content_block_delta", "index": 1,
+            "delta": { "type": "input_json_delta", "partial_json": "{\"file_path\"" } });
+        match assembler.convert(&delta, &delta).as_slice() {
+            [StreamEvent::ToolInputDelta { tool_use_id, tool_name, partial_json }] => {
+                assert_eq!((tool_use_id.as_str(), tool_name.as_str()), ("toolu_1", "Read"));
+                assert_eq!(partial_json, "{\"file_path\"");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let unknown = json!({ "type": "future_block_event" });
+        assert!(matches!(assembler.convert(&unknown, &unknown).as_slice(), [StreamEvent::Unknown { .. }]));
+    }
+}
@@ -0,0 +1,292 @@
+use crate::error::{AppError, Result};
+use crate::models::file::FilePreview;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use regex::Regex;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 默认缩略图最长边
+pub const DEFAULT_PREVIEW_DIM: u32 = 256;
+/// 缩略图最长边的取值范围
+pub const MIN_PREVIEW_DIM: u32 = 16;
+pub const MAX_PREVIEW_DIM: u32 = 2048;
+/// 超过此大小的图片不解码
+const MAX_IMAGE_BYTES: u64 = 50 * 1024 * 1024;
+/// SVG 原样返回，限制大小
+const MAX_SVG_BYTES: u64 = 2 * 1024 * 1024;
+/// 原图不超过缩略图尺寸且小于此大小时直接返回原文件（保留 GIF 动画）
+const PASSTHROUGH_BYTES: u64 = 512 * 1024;
+/// 缩略图 JPEG 质量
+const JPEG_QUALITY: u8 = 85;
+/// 缓存的预览数
+const CACHE_ENTRIES: usize = 64;
+
+const SVG_MIME: &str = "image/svg+xml";
+
+/// 根据文件头识别可预览的图片类型，SVG 按扩展名或内容识别
+pub fn preview_mime(path: &Path, head: &[u8]) -> Option<&'static str> {
+    match head {
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => return Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => return Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => return Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => return Some("image/webp"),
+        _ => {}
+    }
+    let svg_extension = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    let text = String::from_utf8_lossy(head);
+    let text = text.trim_start_matches('\u{FEFF}').trim_start();
+    let svg_content = (text.starts_with("<?xml") || text.starts_with("<svg")) && text.contains("<svg");
+    (svg_extension || svg_content).then_some(SVG_MIME)
+}
+
+/// 生成图片预览，缩略图最长边不超过 `max_dim`
+///
+/// 位图解码后缩小，有透明通道时编码为 PNG，否则为 JPEG；本身足够小的图片直接返回原文件。
+/// SVG 是矢量图，原样返回（在 `<img>` 中显示时脚本不会执行）并附上按 `max_dim` 缩放的显示尺寸。
+pub fn render_preview(path: &Path, max_dim: u32) -> Result<FilePreview> {
+    let size = fs::metadata(path)?.len();
+    let mut head = Vec::with_capacity(512);
+    fs::File::open(path)?.take(512).read_to_end(&mut head)?;
+    let mime = preview_mime(path, &head)
+        .ok_or_else(|| AppError::InvalidPath("不支持预览的文件类型".to_string()))?;
+
+    if mime == SVG_MIME {
+        return svg_preview(path, size, max_dim);
+    }
+    if size > MAX_IMAGE_BYTES {
+        return Err(AppError::InvalidPath("图片过大，超过50MB限制".to_string()));
+    }
+
+    let (width, height) = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_dimensions()
+        .map_err(image_error)?;
+    if width <= max_dim && height <= max_dim && size <= PASSTHROUGH_BYTES {
+        return Ok(FilePreview {
+            mime_type: mime.to_string(),
+            width,
+            height,
+            thumbnail: data_url(mime, &fs::read(path)?),
+            thumbnail_width: width,
+            thumbnail_height: height,
+            size,
+        });
+    }
+
+    let image = ImageReader::open(path)?
+        .with_guessed_format()?
+        .decode()
+        .map_err(image_error)?;
+    let thumbnail = if width > max_dim || height > max_dim { image.thumbnail(max_dim, max_dim) } else { image };
+    let (thumbnail_mime, bytes) = encode_thumbnail(&thumbnail)?;
+    Ok(FilePreview {
+        mime_type: mime.to_string(),
+        width,
+        height,
+        thumbnail: data_url(thumbnail_mime, &bytes),
+        thumbnail_width: thumbnail.width(),
+        thumbnail_height: thumbnail.height(),
+        size,
+    })
+}
+
+fn svg_preview(path: &Path, size: u64, max_dim: u32) -> Result<FilePreview> {
+    if size > MAX_SVG_BYTES {
+        return Err(AppError::InvalidPath("SVG 过大，超过2MB限制".to_string()));
+    }
+    let bytes = fs::read(path)?;
+    // 没有声明尺寸时按浏览器的默认大小
+    let (width, height) = svg_dimensions(&String::from_utf8_lossy(&bytes)).unwrap_or((300, 150));
+    let (thumbnail_width, thumbnail_height) = fit(width, height, max_dim);
+    Ok(FilePreview {
+        mime_type: SVG_MIME.to_string(),
+        width,
+        height,
+        thumbnail: data_url(SVG_MIME, &bytes),
+        thumbnail_width,
+        thumbnail_height,
+        size,
+    })
+}
+
+/// 从根元素的 `width`/`height`（只认像素）和 `viewBox` 推算 SVG 尺寸
+fn svg_dimensions(svg: &str) -> Option<(u32, u32)> {
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    let attr = ATTR.get_or_init(|| {
+        Regex::new(r#"(?:^|\s)(width|height|viewBox)\s*=\s*["']([^"']*)["']"#).expect("正则表达式有效")
+    });
+    let start = svg.find("<svg")?;
+    let tag = &svg[start..start + svg[start..].find('>')?];
+
+    let (mut width, mut height, mut view_box) = (None, None, None);
+    for caps in attr.captures_iter(tag) {
+        let value = caps[2].trim();
+        match &caps[1] {
+            "width" => width = value.strip_suffix("px").unwrap_or(value).trim().parse::<f64>().ok(),
+            "height" => height = value.strip_suffix("px").unwrap_or(value).trim().parse::<f64>().ok(),
+            _ => {
+                let numbers: Vec<f64> = value.split([' ', ',']).filter_map(|n| n.parse().ok()).collect();
+                if let [_, _, w, h] = numbers[..] {
+                    view_box = Some((w, h)).filter(|(w, h)| *w > 0.0 && *h > 0.0);
+                }
+            }
+        }
+    }
+
+    let (width, height) = match (width, height, view_box) {
+        (Some(w), Some(h), _) => (w, h),
+        (Some(w), None, Some((vw, vh))) => (w, w * vh / vw),
+        (None, Some(h), Some((vw, vh))) => (h * vw / vh, h),
+        (None, None, Some(size)) => size,
+        _ => return None,
+    };
+    (width > 0.0 && height > 0.0).then(|| (width.round().max(1.0) as u32, height.round().max(1.0) as u32))
+}
+
+/// 等比缩放到最长边不超过 `max_dim`（不放大）
+fn fit(width: u32, height: u32, max_dim: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= max_dim {
+        return (width, height);
+    }
+    let scale = max_dim as f64 / longest as f64;
+    let scaled = |n: u32| ((n as f64 * scale).round() as u32).max(1);
+    (scaled(width), scaled(height))
+}
+
+fn encode_thumbnail(image: &DynamicImage) -> Result<(&'static str, Vec<u8>)> {
+    let mut bytes = Vec::new();
+    if image.color().has_alpha() {
+        image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png).map_err(image_error)?;
+        Ok(("image/png", bytes))
+    } else {
+        let rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+        JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY).encode_image(&rgb).map_err(image_error)?;
+        Ok(("image/jpeg", bytes))
+    }
+}
+
+fn data_url(mime: &str, bytes: &[u8]) -> String {
+    format!("data:{};base64,{}", mime, STANDARD.encode(bytes))
+}
+
+fn image_error(error: image::ImageError) -> AppError {
+    AppError::ParseError(format!("无法解码图片: {}", error))
+}
+
+/// 预览缓存：按路径和缩略图尺寸存放，修改时间不一致即失效，超出容量时淘汰最久未用的
+#[derive(Default)]
+pub struct PreviewCache {
+    entries: HashMap<(PathBuf, u32), (u64, FilePreview)>,
+    order: VecDeque<(PathBuf, u32)>,
+}
+
+impl PreviewCache {
+    pub fn get(&mut self, path: &Path, max_dim: u32, mtime: u64) -> Option<FilePreview> {
+        let key = (path.to_path_buf(), max_dim);
+        let preview = match self.entries.get(&key) {
+            Some((cached, preview)) if *cached == mtime => preview.clone(),
+            _ => return None,
+        };
+        self.order.retain(|k| k != &key);
+        self.order.push_back(key);
+        Some(preview)
+    }
+
+    pub fn insert(&mut self, path: PathBuf, max_dim: u32, mtime: u64, preview: FilePreview) {
+        let key = (path, max_dim);
+        self.order.retain(|k| k != &key);
+        self.order.push_back(key.clone());
+        self.entries.insert(key, (mtime, preview));
+        while self.order.len() > CACHE_ENTRIES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+    use tempfile::TempDir;
+
+    #[test]
+    fn downscales_large_images() {
+        let tmp = TempDir::with_prefix("preview-").unwrap();
+        let dir = tmp.path();
+
+        let png = dir.join("wide.png");
+        RgbaImage::from_pixel(1000, 500, Rgba([255, 0, 0, 128])).save(&png).unwrap();
+        let preview = render_preview(&png, 100).unwrap();
+        assert_eq!((preview.mime_type.as_str(), preview.width, preview.height), ("image/png", 1000, 500));
+        assert_eq!((preview.thumbnail_width, preview.thumbnail_height), (100, 50));
+        assert!(preview.thumbnail.starts_with("data:image/png;base64,"));
+
+        // 扩展名不对也按内容识别；没有透明通道的缩略图编码为 JPEG
+        let photo = dir.join("photo.bin");
+        RgbImage::from_pixel(300, 600, Rgb([10, 20, 30])).save_with_format(&photo, ImageFormat::Png).unwrap();
+        let preview = render_preview(&photo, 120).unwrap();
+        assert_eq!(preview.mime_type, "image/png");
+        assert_eq!((preview.thumbnail_width, preview.thumbnail_height), (60, 120));
+        assert!(preview.thumbnail.starts_with("data:image/jpeg;base64,"));
+
+        // 足够小的图片原样返回
+        let icon = dir.join("icon.jpg");
+        RgbImage::from_pixel(32, 32, Rgb([0, 0, 0])).save(&icon).unwrap();
+        let preview = render_preview(&icon, 64).unwrap();
+        assert_eq!((preview.width, preview.thumbnail_width), (32, 32));
+        assert_eq!(preview.thumbnail, data_url("image/jpeg", &fs::read(&icon).unwrap()));
+
+        let text = dir.join("notes.txt");
+        fs::write(&text, "not an image").unwrap();
+        assert!(render_preview(&text, 64).is_err());
+    }
+
+    #[test]
+    fn reads_svg_dimensions() {
+        assert_eq!(svg_dimensions(r#"<svg width="120px" height="80" stroke-width="2">"#), Some((120, 80)));
+        assert_eq!(svg_dimensions(r#"<?xml version="1.0"?><svg viewBox="0 0 24 12">"#), Some((24, 12)));
+        assert_eq!(svg_dimensions(r#"<svg width="48" viewBox="0,0,24,12">"#), Some((48, 24)));
+        assert_eq!(svg_dimensions(r#"<svg width="100%">"#), None);
+
+        let tmp = TempDir::with_prefix("preview-").unwrap();
+        let path = tmp.path().join("logo.svg");
+        fs::write(&path, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1024 512"></svg>"#).unwrap();
+        let preview = render_preview(&path, 256).unwrap();
+        assert_eq!((preview.mime_type.as_str(), preview.width, preview.height), (SVG_MIME, 1024, 512));
+        assert_eq!((preview.thumbnail_width, preview.thumbnail_height), (256, 128));
+        assert!(preview.thumbnail.starts_with("data:image/svg+xml;base64,"));
+    }
+
+    #[test]
+    fn cache_is_keyed_by_mtime_and_bounded() {
+        let preview = FilePreview {
+            mime_type: "image/png".to_string(),
+            width: 1,
+            height: 1,
+            thumbnail: String::new(),
+            thumbnail_width: 1,
+            thumbnail_height: 1,
+            size: 1,
+        };
+        let mut cache = PreviewCache::default();
+        cache.insert(PathBuf::from("/a.png"), 256, 10, preview.clone());
+        assert_eq!(cache.get(Path::new("/a.png"), 256, 10), Some(preview.clone()));
+        assert_eq!(cache.get(Path::new("/a.png"), 256, 11), None);
+        assert_eq!(cache.get(Path::new("/a.png"), 128, 10), None);
+
+        for i in 0..CACHE_ENTRIES {
+            cache.insert(PathBuf::from(format!("/{}.png", i)), 256, 10, preview.clone());
+        }
+        assert_eq!(cache.get(Path::new("/a.png"), 256, 10), None);
+        assert!(cache.get(Path::new("/0.png"), 256, 10).is_some());
+    }
+}
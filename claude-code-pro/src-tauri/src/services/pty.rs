@@ -0,0 +1,317 @@
+use crate::error::{AppError, Result};
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// PTY 输出事件
+#[derive(Debug, Clone)]
+pub enum PtyEvent {
+    /// 终端输出（UTF-8，可能包含 ANSI 控制序列）
+    Output(String),
+    /// shell 进程退出
+    Exit(Option<u32>),
+}
+
+/// 终端信息（返回给前端）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalInfo {
+    pub id: String,
+    pub name: String,
+    pub work_dir: String,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// 终端输入端
+///
+/// 每个终端独立加锁，写入时无需持有 [`PtyManager`] 的锁，shell 不读输入时也不会阻塞其他终端。
+#[derive(Clone)]
+pub struct PtyWriter(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl PtyWriter {
+    /// 写入终端输入
+    pub fn write(&self, data: &[u8]) -> Result<()> {
+        let mut writer = self.0.lock()
+            .map_err(|e| AppError::Unknown(e.to_string()))?;
+        writer.write_all(data)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// 单个 PTY 终端
+struct PtyTerminal {
+    info: TerminalInfo,
+    master: Box<dyn MasterPty + Send>,
+    writer: PtyWriter,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+}
+
+/// PTY 终端管理器
+///
+/// 每个终端在独立的伪终端中运行一个 shell，输出由后台线程读取并通过回调推送。
+#[derive(Default)]
+pub struct PtyManager {
+    terminals: HashMap<String, PtyTerminal>,
+}
+
+impl PtyManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 在 work_dir 中启动一个新的 shell
+    ///
+    /// `shell` 为空时使用系统默认 shell。回调的第一个参数为终端 ID。
+    pub fn spawn<F>(
+        &mut self,
+        name: String,
+        work_dir: &Path,
+        shell: Option<&str>,
+        cols: u16,
+        rows: u16,
+        mut on_event: F,
+    ) -> Result<TerminalInfo>
+    where
+        F: FnMut(&str, PtyEvent) + Send + 'static,
+    {
+        if !work_dir.is_dir() {
+            return Err(AppError::InvalidPath(work_dir.to_string_lossy().to_string()));
+        }
+
+        let pair = native_pty_system()
+            .openpty(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })
+            .map_err(|e| AppError::ProcessError(format!("创建 PTY 失败: {}", e)))?;
+
+        let mut cmd = match shell {
+            Some(shell) => CommandBuilder::new(shell),
+            None => CommandBuilder::new_default_prog(),
+        };
+        cmd.cwd(work_dir);
+        cmd.env("TERM", "xterm-256color");
+
+        let mut child = pair.slave
+            .spawn_command(cmd)
+            .map_err(|e| AppError::ProcessError(format!("启动 shell 失败: {}", e)))?;
+        // 释放 slave 端，shell 退出后 reader 才能读到 EOF
+        drop(pair.slave);
+
+        let mut reader = pair.master
+            .try_clone_reader()
+            .map_err(|e| AppError::ProcessError(format!("获取 PTY 输出失败: {}", e)))?;
+        let writer = pair.master
+            .take_writer()
+            .map_err(|e| AppError::ProcessError(format!("获取 PTY 输入失败: {}", e)))?;
+        let killer = child.clone_killer();
+
+        let id = Uuid::new_v4().to_string();
+        let info = TerminalInfo {
+            id: id.clone(),
+            name,
+            work_dir: work_dir.to_string_lossy().to_string(),
+            cols,
+            rows,
+        };
+
+        let reader_id = id.clone();
+        std::thread::spawn(move || {
+            let id = reader_id;
+            let mut buf = [0u8; 8192];
+            let mut pending: Vec<u8> = Vec::new();
+
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        pending.extend_from_slice(&buf[..n]);
+                        let text = take_utf8_prefix(&mut pending);
+                        if !text.is_empty() {
+                            on_event(&id, PtyEvent::Output(text));
+                        }
+                    }
+                }
+            }
+
+            if !pending.is_empty() {
+                on_event(&id, PtyEvent::Output(String::from_utf8_lossy(&pending).to_string()));
+            }
+
+            let code = child.wait().ok().map(|s| s.exit_code());
+            eprintln!("[PtyManager] 终端 {} 退出: {:?}", id, code);
+            on_event(&id, PtyEvent::Exit(code));
+        });
+
+        self.terminals.insert(info.id.clone(), PtyTerminal {
+            info: info.clone(),
+            master: pair.master,
+            writer: PtyWriter(Arc::new(Mutex::new(writer))),
+            killer,
+        });
+
+        Ok(info)
+    }
+
+    /// 获取终端输入端，可在释放管理器的锁后写入
+    pub fn writer(&self, id: &str) -> Result<PtyWriter> {
+        self.terminals
+            .get(id)
+            .map(|t| t.writer.clone())
+            .ok_or_else(|| AppError::SessionNotFound(id.to_string()))
+    }
+
+    /// 写入终端输入
+    pub fn write(&self, id: &str, data: &[u8]) -> Result<()> {
+        self.writer(id)?.write(data)
+    }
+
+    /// 调整终端尺寸
+    pub fn resize(&mut self, id: &str, cols: u16, rows: u16) -> Result<()> {
+        let terminal = self.get_mut(id)?;
+        terminal.master
+            .resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })
+            .map_err(|e| AppError::ProcessError(format!("调整终端尺寸失败: {}", e)))?;
+        terminal.info.cols = cols;
+        terminal.info.rows = rows;
+        Ok(())
+    }
+
+    /// 列出终端，可按工作目录过滤
+    pub fn list(&self, work_dir: Option<&str>) -> Vec<TerminalInfo> {
+        let mut list: Vec<TerminalInfo> = self.terminals
+            .values()
+            .filter(|t| work_dir.is_none_or(|dir| t.info.work_dir == dir))
+            .map(|t| t.info.clone())
+            .collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        list
+    }
+
+    /// 关闭终端
+    pub fn close(&mut self, id: &str) -> Result<()> {
+        let mut terminal = self.terminals
+            .remove(id)
+            .ok_or_else(|| AppError::SessionNotFound(id.to_string()))?;
+        // 进程可能已自行退出，忽略 kill 错误
+        let _ = terminal.killer.kill();
+        Ok(())
+    }
+
+    /// 关闭工作目录下的全部终端
+    pub fn close_workspace(&mut self, work_dir: &str) {
+        let ids: Vec<String> = self.terminals
+            .values()
+            .filter(|t| t.info.work_dir == work_dir)
+            .map(|t| t.info.id.clone())
+            .collect();
+        for id in ids {
+            let _ = self.close(&id);
+        }
+    }
+
+    /// 关闭全部终端
+    pub fn close_all(&mut self) {
+        for (_, mut terminal) in self.terminals.drain() {
+            let _ = terminal.killer.kill();
+        }
+    }
+
+    /// 移除已退出的终端记录
+    pub fn forget(&mut self, id: &str) {
+        self.terminals.remove(id);
+    }
+
+    fn get_mut(&mut self, id: &str) -> Result<&mut PtyTerminal> {
+        self.terminals
+            .get_mut(id)
+            .ok_or_else(|| AppError::SessionNotFound(id.to_string()))
+    }
+}
+
+impl Drop for PtyManager {
+    fn drop(&mut self) {
+        self.close_all();
+    }
+}
+
+/// 取出缓冲区中完整的 UTF-8 前缀，不完整的多字节字符留待下次拼接
+fn take_utf8_prefix(pending: &mut Vec<u8>) -> String {
+    let valid_up_to = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        // error_len 为 None 表示末尾字符被截断
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => {
+            let text = String::from_utf8_lossy(pending).to_string();
+            pending.clear();
+            return text;
+        }
+    };
+
+    let rest = pending.split_off(valid_up_to);
+    String::from_utf8(std::mem::replace(pending, rest)).unwrap_or_default()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn echo_through_pty() {
+        let (tx, rx) = mpsc::channel();
+        let mut manager = PtyManager::new();
+        let info = manager
+            .spawn("test".to_string(), &std::env::temp_dir(), Some("/bin/sh"), 80, 24, move |_, event| {
+                let _ = tx.send(event);
+            })
+            .expect("spawn shell");
+
+        manager.write(&info.id, b"echo pty-$((40 + 2))\n").unwrap();
+        manager.write(&info.id, b"exit 3\n").unwrap();
+
+        let mut output = String::new();
+        let mut exit = None;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while exit.is_none() && Instant::now() < deadline {
+            match rx.recv_timeout(Duration::from_millis(200)) {
+                Ok(PtyEvent::Output(text)) => output.push_str(&text),
+                Ok(PtyEvent::Exit(code)) => exit = Some(code),
+                Err(_) => {}
+            }
+        }
+
+        // 命令回显为 "pty-$((40 + 2))"，只有真正执行后才会出现 "pty-42"
+        assert!(output.contains("pty-42"), "unexpected output: {:?}", output);
+        assert_eq!(exit, Some(Some(3)));
+    }
+
+    #[test]
+    fn resize_and_close() {
+        let mut manager = PtyManager::new();
+        let info = manager
+            .spawn("a".to_string(), &std::env::temp_dir(), Some("/bin/sh"), 80, 24, |_, _| {})
+            .unwrap();
+
+        manager.resize(&info.id, 120, 40).unwrap();
+        let listed = manager.list(Some(&info.work_dir));
+        assert_eq!(listed.len(), 1);
+        assert_eq!((listed[0].cols, listed[0].rows), (120, 40));
+
+        manager.close(&info.id).unwrap();
+        assert!(manager.list(None).is_empty());
+        assert!(manager.write(&info.id, b"x").is_err());
+    }
+
+    #[test]
+    fn utf8_prefix_keeps_partial_chars() {
+        let mut pending = "终端".as_bytes().to_vec();
+        pending.pop();
+        assert_eq!(take_utf8_prefix(&mut pending), "终");
+        assert_eq!(pending.len(), 2);
+    }
+}
@@ -0,0 +1,311 @@
+use crate::error::{AppError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// 录制文件格式版本
+pub const RECORDING_VERSION: u32 = 1;
+
+/// 输出流
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordStream {
+    Stdout,
+    Stderr,
+}
+
+/// 录制文件中的一行（JSONL）
+///
+/// `t` 为相对会话开始的毫秒数。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordEntry {
+    /// 文件头
+    Header {
+        version: u32,
+        session_id: String,
+        started_at: String,
+    },
+    /// CLI 输出的原始行
+    Line {
+        t: u64,
+        stream: RecordStream,
+        line: String,
+    },
+    /// 进程退出，`cancelled` 表示由用户中断
+    Exit {
+        t: u64,
+        code: Option<i32>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        cancelled: bool,
+    },
+}
+
+struct RecorderInner {
+    writer: BufWriter<File>,
+    started: Instant,
+}
+
+/// 会话录制器
+///
+/// 可在 stdout / stderr 读取线程之间共享，每条记录写入后立即刷新，
+/// 进程崩溃时也能保留已录制的内容。
+#[derive(Clone)]
+pub struct SessionRecorder {
+    inner: Arc<Mutex<RecorderInner>>,
+    path: PathBuf,
+}
+
+impl SessionRecorder {
+    /// 在 dir 下创建新的录制文件
+    pub fn create(dir: &Path, session_id: &str) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+
+        let started_at = chrono::Local::now();
+        let file_name = format!("{}-{}.jsonl", started_at.format("%Y%m%d-%H%M%S"), session_id);
+        let path = dir.join(file_name);
+
+        let recorder = Self {
+            inner: Arc::new(Mutex::new(RecorderInner {
+                writer: BufWriter::new(File::create(&path)?),
+                started: Instant::now(),
+            })),
+            path,
+        };
+
+        recorder.write(&RecordEntry::Header {
+            version: RECORDING_VERSION,
+            session_id: session_id.to_string(),
+            started_at: started_at.to_rfc3339(),
+        });
+
+        Ok(recorder)
+    }
+
+    /// 录制文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 记录一行输出（保持原样，不做 trim）
+    pub fn record_line(&self, stream: RecordStream, line: &str) {
+        let t = self.elapsed_ms();
+        self.write(&RecordEntry::Line { t, stream, line: line.to_string() });
+    }
+
+    /// 记录进程退出
+    pub fn record_exit(&self, code: Option<i32>, cancelled: bool) {
+        let t = self.elapsed_ms();
+        self.write(&RecordEntry::Exit { t, code, cancelled });
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.inner
+            .lock()
+            .map(|inner| inner.started.elapsed().as_millis() as u64)
+            .unwrap_or(0)
+    }
+
+    fn write(&self, entry: &RecordEntry) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        let result = serde_json::to_string(entry)
+            .map_err(AppError::from)
+            .and_then(|json| {
+                writeln!(inner.writer, "{}", json)?;
+                inner.writer.flush()?;
+                Ok(())
+            });
+        if let Err(e) = result {
+            eprintln!("[SessionRecorder] 写入录制失败: {}", e);
+        }
+    }
+}
+
+/// 已加载的录制
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub session_id: String,
+    pub started_at: Option<String>,
+    pub entries: Vec<RecordEntry>,
+}
+
+impl Recording {
+    /// 从文件加载录制
+    ///
+    /// 进程崩溃时最后一行可能只写了一半，此时忽略该行。
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut session_id = None;
+        let mut started_at = None;
+        let mut entries = Vec::new();
+        let mut buf = String::new();
+        let mut index = 0;
+
+        while reader.read_line(&mut buf)? > 0 {
+            index += 1;
+            let complete = buf.ends_with('\n');
+            let line = std::mem::take(&mut buf);
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry: RecordEntry = match serde_json::from_str(line.trim_end()) {
+                Ok(entry) => entry,
+                Err(e) if !complete => {
+                    eprintln!("[Recording::load] 忽略不完整的末行 {}: {}", index, e);
+                    break;
+                }
+                Err(e) => {
+                    return Err(AppError::ParseError(format!("录制文件第 {} 行: {}", index, e)));
+                }
+            };
+
+            if let RecordEntry::Header { version, session_id: id, started_at: at } = &entry {
+                if *version > RECORDING_VERSION {
+                    return Err(AppError::ParseError(format!("不支持的录制版本: {}", version)));
+                }
+                session_id = Some(id.clone());
+                started_at = Some(at.clone());
+                continue;
+            }
+
+            entries.push(entry);
+        }
+
+        let session_id = session_id
+            .ok_or_else(|| AppError::ParseError("录制文件缺少文件头".to_string()))?;
+
+        Ok(Self { session_id, started_at, entries })
+    }
+
+    /// 所有 stdout 行
+    pub fn stdout_lines(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().filter_map(|entry| match entry {
+            RecordEntry::Line { stream: RecordStream::Stdout, line, .. } => Some(line.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// 按录制名解析 dir 下的录制文件
+///
+/// 接受文件名或不带扩展名的录制 id，拒绝路径分隔符和 `..`。
+pub fn resolve_recording(dir: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(AppError::InvalidPath(format!("无效的录制名: {}", name)));
+    }
+
+    let file_name = if name.ends_with(".jsonl") {
+        name.to_string()
+    } else {
+        format!("{}.jsonl", name)
+    };
+    let path = dir.join(file_name);
+    if !path.is_file() {
+        return Err(AppError::InvalidPath(format!("录制不存在: {}", name)));
+    }
+    Ok(path)
+}
+
+/// 录制文件信息（返回给前端）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<String>,
+}
+
+/// 列出目录下的录制文件（最新的在前）
+pub fn list_recordings(dir: &Path) -> Result<Vec<RecordingInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut list = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+
+        let metadata = entry.metadata()?;
+        list.push(RecordingInfo {
+            name: path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string(),
+            path: path.to_string_lossy().to_string(),
+            size: metadata.len(),
+            modified: metadata.modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs().to_string()),
+        });
+    }
+
+    list.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn round_trips_recorded_lines() {
+        let tmp = TempDir::with_prefix("recorder-round-trip-").unwrap();
+        let dir = tmp.path();
+        let recorder = SessionRecorder::create(dir, "session-1").unwrap();
+        recorder.record_line(RecordStream::Stdout, "{\"type\":\"system\"}  ");
+        recorder.record_line(RecordStream::Stderr, "warning");
+        recorder.record_exit(Some(0), false);
+
+        let recording = Recording::load(recorder.path()).unwrap();
+        assert_eq!(recording.session_id, "session-1");
+        assert!(recording.started_at.is_some());
+        assert_eq!(recording.stdout_lines().collect::<Vec<_>>(), ["{\"type\":\"system\"}  "]);
+        assert!(matches!(&recording.entries[1],
+            RecordEntry::Line { stream: RecordStream::Stderr, line, .. } if line == "warning"));
+        assert!(matches!(recording.entries[2], RecordEntry::Exit { code: Some(0), .. }));
+    }
+
+    #[test]
+    fn ignores_truncated_last_line() {
+        let tmp = TempDir::with_prefix("recorder-truncated-").unwrap();
+        let dir = tmp.path();
+        let recorder = SessionRecorder::create(dir, "session-2").unwrap();
+        recorder.record_line(RecordStream::Stdout, "first");
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(recorder.path()).unwrap();
+        write!(file, "{{\"kind\":\"line\",\"t\":5,\"str").unwrap();
+
+        let recording = Recording::load(recorder.path()).unwrap();
+        assert_eq!(recording.stdout_lines().collect::<Vec<_>>(), ["first"]);
+
+        // 中间行损坏仍然报错
+        writeln!(file).unwrap();
+        writeln!(file, "{{}}").unwrap();
+        assert!(matches!(Recording::load(recorder.path()), Err(AppError::ParseError(_))));
+    }
+
+    #[test]
+    fn resolves_names_inside_recordings_dir() {
+        let tmp = TempDir::with_prefix("recorder-resolve-").unwrap();
+        let dir = tmp.path();
+        let recorder = SessionRecorder::create(dir, "session-3").unwrap();
+        let name = recorder.path().file_name().unwrap().to_str().unwrap().to_string();
+        let id = name.trim_end_matches(".jsonl");
+
+        assert_eq!(resolve_recording(dir, &name).unwrap(), recorder.path());
+        assert_eq!(resolve_recording(dir, id).unwrap(), recorder.path());
+        for bad in ["", "../secret", "a/b.jsonl", "a\\b", "..", "missing"] {
+            assert!(matches!(resolve_recording(dir, bad), Err(AppError::InvalidPath(_))), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::error::{AppError, Result};
+use crate::models::config::Config;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// 工作区沙箱
+///
+/// 文件浏览器的路径都要经过这里：规范化后（解析符号链接）必须位于工作目录
+/// 或配置的额外目录之内，否则返回 `PermissionDenied`。
+#[derive(Debug, Clone)]
+pub struct WorkspaceSandbox {
+    roots: Vec<PathBuf>,
+}
+
+impl WorkspaceSandbox {
+    /// 使用给定的根目录创建沙箱，不存在的目录被忽略
+    pub fn new(roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        let roots = roots
+            .into_iter()
+            .filter_map(|root| match root.canonicalize() {
+                Ok(root) => Some(root),
+                Err(e) => {
+                    eprintln!("[WorkspaceSandbox::new] 忽略无效目录 {:?}: {}", root, e);
+                    None
+                }
+            })
+            .collect();
+        Self { roots }
+    }
+
+    /// 根据配置创建（工作目录 + extra_allowed_dirs）
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let work_dir = config.work_dir.clone()
+            .ok_or_else(|| AppError::PermissionDenied("未设置工作目录".to_string()))?;
+        let roots = std::iter::once(work_dir).chain(config.extra_allowed_dirs.iter().cloned());
+        Ok(Self::new(roots))
+    }
+
+    /// 解析路径（跟随符号链接），用于读写文件内容
+    ///
+    /// 路径可以尚不存在：此时解析最近的已存在祖先，再拼接剩余部分。
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let path = self.absolute(path)?;
+        let resolved = canonicalize_partial(&path)?;
+        self.check(&resolved, &path)?;
+        Ok(resolved)
+    }
+
+    /// 解析条目本身（不跟随最后一级符号链接），用于删除、重命名
+    ///
+    /// 根目录本身不能作为条目操作。
+    pub fn resolve_entry(&self, path: &str) -> Result<PathBuf> {
+        let path = self.absolute(path)?;
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(AppError::PermissionDenied(format!("不允许操作: {}", path.display())));
+        };
+
+        let entry = canonicalize_partial(parent)?.join(name);
+        self.check(&entry, &path)?;
+        if self.roots.contains(&entry) {
+            return Err(AppError::PermissionDenied(format!("不允许操作工作区根目录: {}", path.display())));
+        }
+        Ok(entry)
+    }
+
+    /// 相对路径按工作目录解析
+    fn absolute(&self, path: &str) -> Result<PathBuf> {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            return Ok(path.to_path_buf());
+        }
+        let root = self.roots.first()
+            .ok_or_else(|| AppError::PermissionDenied("未设置工作目录".to_string()))?;
+        Ok(root.join(path))
+    }
+
+    fn check(&self, resolved: &Path, original: &Path) -> Result<()> {
+        if self.roots.iter().any(|root| resolved.starts_with(root)) {
+            Ok(())
+        } else {
+            eprintln!("[WorkspaceSandbox] 拒绝访问工作区之外的路径: {:?} -> {:?}", original, resolved);
+            Err(AppError::PermissionDenied(format!("路径不在工作区内: {}", original.display())))
+        }
+    }
+}
+
+/// 校验新文件名：不能为空，不能包含路径分隔符或 `..`
+pub fn validate_file_name(name: &str) -> Result<()> {
+    let invalid = name.is_empty()
+        || name == "."
+        || name.contains("..")
+        || name.contains('/')
+        || name.contains('\\')
+        || name.contains('\0');
+    if invalid {
+        return Err(AppError::PermissionDenied(format!("无效的文件名: {}", name)));
+    }
+    Ok(())
+}
+
+/// 符号链接最多跟随的层数（与 Linux 的 `MAXSYMLINKS` 一致）
+const MAX_SYMLINK_DEPTH: usize = 40;
+
+/// 规范化可能尚不存在的路径
+///
+/// 已存在的部分通过 `canonicalize` 解析符号链接；不存在的部分只允许普通组件，
+/// 避免 `new/../../etc` 这类写法绕过检查。悬空的符号链接按其目标继续解析，
+/// 否则写入时会跟随链接落到工作区之外。
+fn canonicalize_partial(path: &Path) -> Result<PathBuf> {
+    canonicalize_with_depth(path, MAX_SYMLINK_DEPTH)
+}
+
+fn canonicalize_with_depth(path: &Path, depth: usize) -> Result<PathBuf> {
+    let mut existing = path.to_path_buf();
+    let mut rest = Vec::new();
+
+    loop {
+        let mut resolved = match existing.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(_) if is_symlink(&existing) => {
+                if depth == 0 {
+                    return Err(AppError::PermissionDenied(format!("符号链接层级过深: {}", path.display())));
+                }
+                let target = fs::read_link(&existing)?;
+                let parent = existing.parent().unwrap_or_else(|| Path::new(""));
+                canonicalize_with_depth(&parent.join(target), depth - 1)?
+            }
+            Err(_) => {
+                let Some(Component::Normal(name)) = existing.components().next_back() else {
+                    return Err(AppError::PermissionDenied(format!("无效路径: {}", path.display())));
+                };
+                rest.push(name.to_os_string());
+                existing = match existing.parent() {
+                    Some(parent) => parent.to_path_buf(),
+                    None => return Err(AppError::InvalidPath(format!("无法解析路径: {}", path.display()))),
+                };
+                continue;
+            }
+        };
+        for component in rest.iter().rev() {
+            resolved.push(component);
+        }
+        return Ok(resolved);
+    }
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn denied(result: Result<PathBuf>) -> bool {
+        matches!(result, Err(AppError::PermissionDenied(_)))
+    }
+
+    #[test]
+    fn confines_paths_to_roots() {
+        let root_dir = TempDir::with_prefix("sandbox-root-").unwrap();
+        let root = root_dir.path();
+        let extra_dir = TempDir::with_prefix("sandbox-extra-").unwrap();
+        let extra = extra_dir.path();
+        let outside_dir = TempDir::with_prefix("sandbox-outside-").unwrap();
+        let outside = outside_dir.path();
+        std::fs::write(root.join("a.txt"), "a").unwrap();
+        let sandbox = WorkspaceSandbox::new([root.to_path_buf(), extra.to_path_buf()]);
+        let s = |p: &Path| p.to_string_lossy().to_string();
+
+        assert!(sandbox.resolve(&s(&root.join("a.txt"))).is_ok());
+        assert!(sandbox.resolve("a.txt").unwrap().ends_with("a.txt"));
+        // 尚不存在的路径
+        assert!(sandbox.resolve(&s(&root.join("new/dir/file.txt"))).is_ok());
+        assert!(sandbox.resolve(&s(&extra.join("b.txt"))).is_ok());
+
+        assert!(denied(sandbox.resolve(&s(&outside.join("x.txt")))));
+        assert!(denied(sandbox.resolve(&s(&root.join("../outside.txt")))));
+        assert!(denied(sandbox.resolve(&s(&root.join("missing/../../x")))));
+        assert!(denied(sandbox.resolve_entry(&s(root))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_escapes() {
+        let root_dir = TempDir::with_prefix("sandbox-root-").unwrap();
+        let root = root_dir.path();
+        let outside_dir = TempDir::with_prefix("sandbox-outside-").unwrap();
+        let outside = outside_dir.path();
+        std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(outside, root.join("link")).unwrap();
+        let sandbox = WorkspaceSandbox::new([root.to_path_buf()]);
+
+        let through_link = root.join("link/secret.txt").to_string_lossy().to_string();
+        assert!(denied(sandbox.resolve(&through_link)));
+        assert!(denied(sandbox.resolve_entry(&through_link)));
+
+        // 链接本身位于工作区内，可以删除或重命名，但不能读取其目标
+        let link = root.join("link").to_string_lossy().to_string();
+        assert_eq!(sandbox.resolve_entry(&link).unwrap(), root.canonicalize().unwrap().join("link"));
+        assert!(denied(sandbox.resolve(&link)));
+
+        // 悬空链接：写入会跟随链接创建目标文件
+        std::os::unix::fs::symlink(outside.join("created.txt"), root.join("dangling")).unwrap();
+        std::os::unix::fs::symlink("../../escaped.txt", root.join("relative")).unwrap();
+        std::os::unix::fs::symlink("loop-b", root.join("loop-a")).unwrap();
+        std::os::unix::fs::symlink("loop-a", root.join("loop-b")).unwrap();
+        for name in ["dangling", "relative", "loop-a"] {
+            let link = root.join(name).to_string_lossy().to_string();
+            assert!(denied(sandbox.resolve(&link)), "{}", name);
+            assert!(sandbox.resolve_entry(&link).is_ok(), "{}", name);
+        }
+
+        // 指向工作区内尚不存在文件的悬空链接按目标解析
+        std::os::unix::fs::symlink("new/file.txt", root.join("inside")).unwrap();
+        let inside = root.join("inside").to_string_lossy().to_string();
+        assert_eq!(sandbox.resolve(&inside).unwrap(), root.canonicalize().unwrap().join("new/file.txt"));
+    }
+
+    #[test]
+    fn validates_new_names() {
+        assert!(validate_file_name("notes.md").is_ok());
+        for name in ["", ".", "..", "a/b", "a\\b", "../x", "x.."] {
+            assert!(validate_file_name(name).is_err(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::models::scheduler::{RunInfo, RunLimits, RunQueueStatus};
+use tokio::sync::oneshot;
+
+/// 调度结果
+pub enum Admission {
+    /// 立即运行
+    Started,
+    /// 进入队列，收到信号后运行；发送端被丢弃表示已取消
+    Queued {
+        position: usize,
+        ready: oneshot::Receiver<()>,
+    },
+}
+
+struct QueuedRun {
+    info: RunInfo,
+    seq: u64,
+    ready: oneshot::Sender<()>,
+}
+
+/// 会话调度器
+///
+/// 限制全局和每个工作区同时运行的 Claude 进程数，
+/// 超出时按优先级（高者优先）和提交顺序排队。
+#[derive(Default)]
+pub struct RunScheduler {
+    limits: RunLimits,
+    running: Vec<RunInfo>,
+    queue: Vec<QueuedRun>,
+    next_seq: u64,
+}
+
+impl RunScheduler {
+    pub fn new(limits: RunLimits) -> Self {
+        Self { limits, ..Self::default() }
+    }
+
+    /// 更新限制，放宽时立即启动可运行的排队会话
+    pub fn set_limits(&mut self, limits: RunLimits) {
+        self.limits = limits;
+        self.dispatch();
+    }
+
+    /// 提交会话
+    pub fn admit(&mut self, session_id: &str, workspace: &str, priority: i32) -> Admission {
+        let info = RunInfo {
+            session_id: session_id.to_string(),
+            workspace: workspace.to_string(),
+            priority,
+            submitted_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        // 已有排队会话时不插队，保证同优先级先进先出
+        if self.queue.is_empty() && self.has_capacity(workspace) {
+            self.running.push(info);
+            return Admission::Started;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        self.next_seq += 1;
+        self.queue.push(QueuedRun { info, seq: self.next_seq, ready: tx });
+        self.sort_queue();
+        self.dispatch();
+
+        match self.queue_position(session_id) {
+            Some(position) => Admission::Queued { position, ready: rx },
+            // dispatch 已经把它启动
+            None => Admission::Started,
+        }
+    }
+
+    /// 会话结束，释放名额并启动后续会话
+    pub fn finish(&mut self, session_id: &str) {
+        if let Some(index) = self.running.iter().position(|r| r.session_id == session_id) {
+            self.running.remove(index);
+        }
+        self.dispatch();
+    }
+
+    /// 取消排队中的会话，返回是否找到
+    pub fn cancel(&mut self, session_id: &str) -> bool {
+        let Some(index) = self.queue.iter().position(|q| q.info.session_id == session_id) else {
+            return false;
+        };
+        // 丢弃发送端即通知等待方已取消
+        self.queue.remove(index);
+        self.dispatch();
+        true
+    }
+
+    /// 取消全部排队中的会话（应用退出时），返回取消的数量
+    pub fn cancel_all(&mut self) -> usize {
+        let count = self.queue.len();
+        self.queue.clear();
+        count
+    }
+
+    /// 会话是否在排队
+    pub fn is_queued(&self, session_id: &str) -> bool {
+        self.queue_position(session_id).is_some()
+    }
+
+    /// 当前状态
+    pub fn status(&self) -> RunQueueStatus {
+        RunQueueStatus {
+            limits: self.limits,
+            running: self.running.clone(),
+            queued: self.queue.iter().map(|q| q.info.clone()).collect(),
+        }
+    }
+
+    /// 排队位置（从 1 开始）
+    fn queue_position(&self, session_id: &str) -> Option<usize> {
+        self.queue
+            .iter()
+            .position(|q| q.info.session_id == session_id)
+            .map(|i| i + 1)
+    }
+
+    fn sort_queue(&mut self) {
+        self.queue.sort_by(|a, b| b.info.priority.cmp(&a.info.priority).then(a.seq.cmp(&b.seq)));
+    }
+
+    fn has_capacity(&self, workspace: &str) -> bool {
+        let global_ok = self.limits.max_concurrent == 0 || self.running.len() < self.limits.max_concurrent;
+        let workspace_ok = self.limits.max_per_workspace == 0
+            || self.running.iter().filter(|r| r.workspace == workspace).count() < self.limits.max_per_workspace;
+        global_ok && workspace_ok
+    }
+
+    /// 按顺序启动可运行的排队会话
+    ///
+    /// 工作区已满的会话不阻塞其他工作区的会话。
+    fn dispatch(&mut self) {
+        let mut index = 0;
+        while index < self.queue.len() {
+            if self.limits.max_concurrent != 0 && self.running.len() >= self.limits.max_concurrent {
+                break;
+            }
+            if !self.has_capacity(&self.queue[index].info.workspace) {
+                index += 1;
+                continue;
+            }
+
+            let run = self.queue.remove(index);
+            // 等待方已放弃（命令任务被丢弃）时跳过
+            if run.ready.send(()).is_ok() {
+                self.running.push(run.info);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_concurrent: usize, max_per_workspace: usize) -> RunLimits {
+        RunLimits { max_concurrent, max_per_workspace }
+    }
+
+    fn queued(admission: Admission) -> (usize, oneshot::Receiver<()>) {
+        match admission {
+            Admission::Queued { position, ready } => (position, ready),
+            Admission::Started => panic!("expected queued"),
+        }
+    }
+
+    #[test]
+    fn queues_beyond_global_limit() {
+        let mut scheduler = RunScheduler::new(limits(1, 0));
+        assert!(matches!(scheduler.admit("a", "/w1", 0), Admission::Started));
+        let (position, mut ready) = queued(scheduler.admit("b", "/w2", 0));
+        assert_eq!(position, 1);
+        assert!(ready.try_recv().is_err());
+
+        scheduler.finish("a");
+        assert!(ready.try_recv().is_ok());
+        assert_eq!(scheduler.status().running[0].session_id, "b");
+    }
+
+    #[test]
+    fn priority_then_fifo() {
+        let mut scheduler = RunScheduler::new(limits(1, 0));
+        scheduler.admit("running", "/w", 0);
+        let (_, mut low) = queued(scheduler.admit("low", "/w", 0));
+        let (_, mut low2) = queued(scheduler.admit("low2", "/w", 0));
+        let (position, mut high) = queued(scheduler.admit("high", "/w", 5));
+        assert_eq!(position, 1);
+
+        scheduler.finish("running");
+        assert!(high.try_recv().is_ok());
+        scheduler.finish("high");
+        assert!(low.try_recv().is_ok());
+        assert!(low2.try_recv().is_err());
+    }
+
+    #[test]
+    fn workspace_limit_does_not_block_other_workspaces() {
+        let mut scheduler = RunScheduler::new(limits(3, 1));
+        assert!(matches!(scheduler.admit("a", "/w1", 0), Admission::Started));
+        let (_, mut b) = queued(scheduler.admit("b", "/w1", 0));
+        // 队列非空时新会话也要排队，但 dispatch 会立即启动其他工作区的会话
+        assert!(matches!(scheduler.admit("c", "/w2", 0), Admission::Started));
+        assert!(b.try_recv().is_err());
+
+        scheduler.finish("a");
+        assert!(b.try_recv().is_ok());
+    }
+
+    #[test]
+    fn cancel_queued_run() {
+        let mut scheduler = RunScheduler::new(limits(1, 0));
+        scheduler.admit("a", "/w", 0);
+        let (_, mut ready) = queued(scheduler.admit("b", "/w", 0));
+
+        assert!(scheduler.cancel("b"));
+        assert!(!scheduler.cancel("a"));
+        assert!(matches!(ready.try_recv(), Err(oneshot::error::TryRecvError::Closed)));
+
+        scheduler.finish("a");
+        assert!(scheduler.status().running.is_empty());
+    }
+
+    #[test]
+    fn raising_limits_starts_queued_runs() {
+        let mut scheduler = RunScheduler::new(limits(1, 0));
+        scheduler.admit("a", "/w", 0);
+        let (_, mut ready) = queued(scheduler.admit("b", "/w", 0));
+
+        scheduler.set_limits(limits(0, 0));
+        assert!(ready.try_recv().is_ok());
+        assert_eq!(scheduler.status().running.len(), 2);
+    }
+}
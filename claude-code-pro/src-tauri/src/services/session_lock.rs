@@ -0,0 +1,274 @@
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 运行中会话的锁文件内容
+///
+/// 应用异常退出时锁文件不会被删除，下次启动据此发现遗留的 Claude 进程。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLock {
+    pub session_id: String,
+    /// Claude 进程 PID（同时是其进程组 ID）
+    pub pid: u32,
+    /// 启动该会话的应用进程 PID
+    pub app_pid: u32,
+    pub work_dir: Option<String>,
+    pub started_at: String,
+    /// Claude 进程的启动时间（由系统报告），用于确认 PID 没有被其他进程复用
+    #[serde(default)]
+    pub process_started: Option<String>,
+}
+
+/// 遗留会话
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleSession {
+    #[serde(flatten)]
+    pub lock: SessionLock,
+    /// Claude 进程是否仍在运行（PID 存在且启动时间与锁文件一致）
+    pub alive: bool,
+}
+
+/// 会话锁目录
+pub struct SessionLocks {
+    dir: PathBuf,
+}
+
+impl SessionLocks {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, session_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.lock", session_id))
+    }
+
+    /// 记录运行中的会话
+    pub fn acquire(&self, session_id: &str, pid: u32, work_dir: Option<&str>) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let lock = SessionLock {
+            session_id: session_id.to_string(),
+            pid,
+            app_pid: std::process::id(),
+            work_dir: work_dir.map(String::from),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            process_started: process_start_time(pid),
+        };
+        std::fs::write(self.path(session_id), serde_json::to_string_pretty(&lock)?)?;
+        Ok(())
+    }
+
+    /// 会话正常结束，删除锁文件
+    pub fn release(&self, session_id: &str) {
+        let path = self.path(session_id);
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                eprintln!("[SessionLocks::release] 删除锁文件失败: {}", e);
+            }
+        }
+    }
+
+    /// 列出遗留会话（启动它的应用进程已不存在）
+    pub fn stale(&self) -> Vec<StaleSession> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        let current = std::process::id();
+        let mut list: Vec<StaleSession> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("lock"))
+            .filter_map(|path| read_lock(&path))
+            .filter(|lock| lock.app_pid != current && !process_alive(lock.app_pid))
+            .map(|lock| StaleSession { alive: same_process(&lock), lock })
+            .collect();
+        list.sort_by(|a, b| a.lock.started_at.cmp(&b.lock.started_at));
+        list
+    }
+
+    /// 清理遗留会话：终止仍在运行的进程组并删除锁文件
+    ///
+    /// 只终止启动时间与锁文件一致的进程；重启或 PID 被复用后只删除锁文件。
+    pub fn cleanup(&self, kill: bool) -> Vec<StaleSession> {
+        let stale = self.stale();
+        for session in &stale {
+            if kill && session.alive {
+                eprintln!("[SessionLocks::cleanup] 终止遗留进程: {}", session.lock.pid);
+                signal_process_group(session.lock.pid, true);
+            }
+            self.release(&session.lock.session_id);
+        }
+        stale
+    }
+}
+
+fn read_lock(path: &Path) -> Option<SessionLock> {
+    let content = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(lock) => Some(lock),
+        Err(e) => {
+            eprintln!("[SessionLocks] 无效的锁文件 {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// 锁文件记录的 Claude 进程是否仍在运行
+///
+/// 仅凭 PID 不可靠（重启后或 PID 被复用时会指向无关的进程），还需启动时间一致；
+/// 旧版本的锁文件没有记录启动时间，视为不一致。
+fn same_process(lock: &SessionLock) -> bool {
+    match &lock.process_started {
+        Some(started) => process_alive(lock.pid) && process_start_time(lock.pid).as_ref() == Some(started),
+        None => false,
+    }
+}
+
+/// 进程的启动时间，进程不存在时返回 None
+pub fn process_start_time(pid: u32) -> Option<String> {
+    #[cfg(windows)]
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", &format!("(Get-Process -Id {}).StartTime.ToFileTimeUtc()", pid)])
+        .output();
+
+    #[cfg(not(windows))]
+    let output = Command::new("ps")
+        .args(["-o", "lstart=", "-p", &pid.to_string()])
+        .output();
+
+    let output = output.ok().filter(|o| o.status.success())?;
+    let started = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!started.is_empty()).then_some(started)
+}
+
+/// 进程是否存在
+pub fn process_alive(pid: u32) -> bool {
+    #[cfg(windows)]
+    {
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).contains(&pid.to_string()))
+            .unwrap_or(false)
+    }
+
+    #[cfg(not(windows))]
+    {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+}
+
+/// 向进程组发送终止信号，`force` 为 true 时强制终止
+///
+/// Claude 进程以独立进程组启动，工具子进程会一并收到信号。
+pub fn signal_process_group(pid: u32, force: bool) -> bool {
+    #[cfg(windows)]
+    let result = {
+        let mut args = vec!["/T".to_string(), "/PID".to_string(), pid.to_string()];
+        if force {
+            args.insert(0, "/F".to_string());
+        }
+        Command::new("taskkill").args(&args).output()
+    };
+
+    #[cfg(not(windows))]
+    let result = Command::new("kill")
+        .args([if force { "-KILL" } else { "-TERM" }, "--", &format!("-{}", pid)])
+        .output();
+
+    match result {
+        Ok(output) => output.status.success(),
+        Err(e) => {
+            eprintln!("[signal_process_group] 发送信号失败: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+
+    #[test]
+    fn finds_and_cleans_stale_sessions() {
+        let tmp = tempfile::TempDir::with_prefix("session-locks-").unwrap();
+        let dir = tmp.path();
+        let locks = SessionLocks::new(dir.to_path_buf());
+
+        // 当前应用的会话不是遗留会话
+        locks.acquire("live", std::process::id(), None).unwrap();
+        assert!(locks.stale().is_empty());
+
+        // 模拟崩溃：应用进程已退出，Claude 进程仍在运行
+        let mut orphan = std::process::Command::new("sleep").arg("30").process_group(0).spawn().unwrap();
+        let mut dead_app = std::process::Command::new("true").spawn().unwrap();
+        let dead_pid = dead_app.id();
+        dead_app.wait().unwrap();
+
+        let lock = SessionLock {
+            session_id: "crashed".to_string(),
+            pid: orphan.id(),
+            app_pid: dead_pid,
+            work_dir: Some("/tmp".to_string()),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            process_started: process_start_time(orphan.id()),
+        };
+        assert!(lock.process_started.is_some());
+        std::fs::write(dir.join("crashed.lock"), serde_json::to_string(&lock).unwrap()).unwrap();
+
+        let stale = locks.stale();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].lock.session_id, "crashed");
+        assert!(stale[0].alive);
+
+        let cleaned = locks.cleanup(true);
+        assert_eq!(cleaned.len(), 1);
+        assert!(!orphan.wait().unwrap().success());
+        assert!(locks.stale().is_empty());
+        assert!(!dir.join("crashed.lock").exists());
+
+        locks.release("live");
+        assert!(!dir.join("live.lock").exists());
+    }
+
+    #[test]
+    fn does_not_kill_reused_pids() {
+        let tmp = tempfile::TempDir::with_prefix("session-locks-").unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir).unwrap();
+        let locks = SessionLocks::new(dir.to_path_buf());
+
+        // PID 指向一个无关的进程（启动时间不同），或锁文件来自旧版本
+        let mut unrelated = std::process::Command::new("sleep").arg("30").process_group(0).spawn().unwrap();
+        let mut dead_app = std::process::Command::new("true").spawn().unwrap();
+        let dead_pid = dead_app.id();
+        dead_app.wait().unwrap();
+        for (id, started) in [("reused", Some("Thu Jan  1 00:00:00 1970".to_string())), ("legacy", None)] {
+            let lock = SessionLock {
+                session_id: id.to_string(),
+                pid: unrelated.id(),
+                app_pid: dead_pid,
+                work_dir: None,
+                started_at: chrono::Utc::now().to_rfc3339(),
+                process_started: started,
+            };
+            std::fs::write(dir.join(format!("{}.lock", id)), serde_json::to_string(&lock).unwrap()).unwrap();
+        }
+
+        assert!(locks.stale().iter().all(|s| !s.alive));
+        assert_eq!(locks.cleanup(true).len(), 2);
+        assert!(locks.stale().is_empty());
+        assert!(unrelated.try_wait().unwrap().is_none(), "无关进程不应被终止");
+
+        unrelated.kill().unwrap();
+        unrelated.wait().unwrap();
+    }
+}
@@ -0,0 +1,419 @@
+use crate::models::events::StreamEvent;
+use crate::models::shell::{ShellJob, ShellJobEvent, ShellJobStatus};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 每个任务保留的输出上限，超出时丢弃最早的部分
+pub const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// 对后台 shell 的辅助工具调用
+enum ShellAction {
+    /// BashOutput：读取新输出
+    ReadOutput(String),
+    /// KillShell：终止后台 shell
+    Kill(String),
+}
+
+/// 单个会话内的 Bash 任务
+#[derive(Default)]
+struct SessionShells {
+    jobs: Vec<ShellJob>,
+    by_tool_use: HashMap<String, usize>,
+    by_shell_id: HashMap<String, usize>,
+    pending_actions: HashMap<String, ShellAction>,
+}
+
+/// Bash 工具调用追踪器
+///
+/// 从 stream-json 事件中把 Bash 的 tool_use 与 tool_result 配对，
+/// 并把 `run_in_background` 启动的 shell 作为长期任务跟踪。
+#[derive(Default)]
+pub struct ShellTracker {
+    sessions: HashMap<String, SessionShells>,
+    /// Claude 会话 ID -> 本地会话 ID
+    aliases: HashMap<String, String>,
+}
+
+impl ShellTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 解析会话别名
+    fn resolve(&self, session_id: &str) -> String {
+        self.aliases
+            .get(session_id)
+            .cloned()
+            .unwrap_or_else(|| session_id.to_string())
+    }
+
+    /// 列出会话中的所有 Bash 任务
+    pub fn list(&self, session_id: &str) -> Vec<ShellJob> {
+        self.sessions
+            .get(&self.resolve(session_id))
+            .map(|s| s.jobs.clone())
+            .unwrap_or_default()
+    }
+
+    /// 移除会话的追踪数据
+    pub fn remove_session(&mut self, session_id: &str) {
+        let key = self.resolve(session_id);
+        self.sessions.remove(&key);
+        self.aliases.retain(|_, v| *v != key);
+    }
+
+    /// 处理一个流事件，返回本次发生变化的任务及其新增输出
+    pub fn observe(&mut self, session_id: &str, cwd: Option<&str>, event: &StreamEvent) -> Vec<ShellJobEvent> {
+        let key = self.resolve(session_id);
+
+        match event {
+            StreamEvent::System { extra, .. } => {
+                // 记录 Claude 真实会话 ID，前端使用它查询
+                if let Some(claude_id) = extra.get("session_id").and_then(|v| v.as_str()) {
+                    if claude_id != key {
+                        self.aliases.insert(claude_id.to_string(), key);
+                    }
+                }
+                Vec::new()
+            }
+            StreamEvent::Assistant { message } => {
+                let shells = self.sessions.entry(key.clone()).or_default();
+                content_items(message)
+                    .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("tool_use"))
+                    .filter_map(|item| shells.on_tool_use(&key, cwd, item))
+                    .collect()
+            }
+            StreamEvent::User { message } => {
+                let Some(shells) = self.sessions.get_mut(&key) else {
+                    return Vec::new();
+                };
+                content_items(message)
+                    .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("tool_result"))
+                    .filter_map(|item| shells.on_tool_result(item))
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl SessionShells {
+    /// 处理 tool_use 块
+    fn on_tool_use(&mut self, session_id: &str, cwd: Option<&str>, item: &Value) -> Option<ShellJobEvent> {
+        let id = item.get("id").and_then(|v| v.as_str())?.to_string();
+        let name = item.get("name").and_then(|v| v.as_str())?;
+        let input = item.get("input").cloned().unwrap_or(Value::Null);
+
+        match name {
+            "Bash" => {
+                let command = input.get("command").and_then(|v| v.as_str())?.to_string();
+                let background = input
+                    .get("run_in_background")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let now = chrono::Utc::now().to_rfc3339();
+
+                let job = ShellJob {
+                    tool_use_id: id.clone(),
+                    session_id: session_id.to_string(),
+                    command,
+                    description: input.get("description").and_then(|v| v.as_str()).map(String::from),
+                    cwd: cwd.map(String::from),
+                    background,
+                    shell_id: None,
+                    status: ShellJobStatus::Running,
+                    exit_code: None,
+                    output: String::new(),
+                    output_bytes: 0,
+                    started_at: now.clone(),
+                    updated_at: now,
+                };
+
+                self.by_tool_use.insert(id, self.jobs.len());
+                self.jobs.push(job);
+                self.jobs.last_mut().map(|job| job_event(job, String::new()))
+            }
+            "BashOutput" => {
+                let shell_id = shell_id_from_input(&input)?;
+                self.pending_actions.insert(id, ShellAction::ReadOutput(shell_id));
+                None
+            }
+            "KillShell" => {
+                let shell_id = shell_id_from_input(&input)?;
+                self.pending_actions.insert(id, ShellAction::Kill(shell_id));
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// 处理 tool_result 块
+    fn on_tool_result(&mut self, item: &Value) -> Option<ShellJobEvent> {
+        let tool_use_id = item.get("tool_use_id").and_then(|v| v.as_str())?;
+        let is_error = item.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false);
+        let text = result_text(item.get("content"));
+
+        if let Some(&index) = self.by_tool_use.get(tool_use_id) {
+            let job = &mut self.jobs[index];
+            let mut added = String::new();
+
+            if job.background && !is_error {
+                // 后台命令的首个结果只包含 shell ID
+                if let Some(shell_id) = parse_background_id(&text) {
+                    job.shell_id = Some(shell_id.clone());
+                    self.by_shell_id.insert(shell_id, index);
+                }
+                job.status = ShellJobStatus::Background;
+            } else {
+                job.exit_code = Some(if is_error { parse_exit_code(&text).unwrap_or(1) } else { 0 });
+                job.status = if is_error { ShellJobStatus::Failed } else { ShellJobStatus::Completed };
+                added = text;
+            }
+
+            job.updated_at = chrono::Utc::now().to_rfc3339();
+            return Some(job_event(job, added));
+        }
+
+        let action = self.pending_actions.remove(tool_use_id)?;
+        match action {
+            ShellAction::ReadOutput(shell_id) => {
+                let job = &mut self.jobs[*self.by_shell_id.get(&shell_id)?];
+
+                // BashOutput 只返回上次读取后的新输出
+                let mut added = String::new();
+                for tag in ["stdout", "stderr"] {
+                    if let Some(chunk) = extract_tag(&text, tag) {
+                        let last = added.chars().next_back().or_else(|| job.output.chars().next_back());
+                        if last.is_some_and(|c| c != '\n') {
+                            added.push('\n');
+                        }
+                        added.push_str(chunk);
+                    }
+                }
+
+                if let Some(code) = extract_tag(&text, "exit_code").and_then(|c| c.trim().parse().ok()) {
+                    job.exit_code = Some(code);
+                }
+
+                job.status = match extract_tag(&text, "status").map(str::trim) {
+                    Some("completed") => ShellJobStatus::Completed,
+                    Some("failed") => ShellJobStatus::Failed,
+                    Some("killed") => ShellJobStatus::Killed,
+                    _ if is_error => ShellJobStatus::Failed,
+                    _ => job.status,
+                };
+                job.updated_at = chrono::Utc::now().to_rfc3339();
+                Some(job_event(job, added))
+            }
+            ShellAction::Kill(shell_id) => {
+                let job = &mut self.jobs[*self.by_shell_id.get(&shell_id)?];
+                if is_error || job.status.is_finished() {
+                    return None;
+                }
+                job.status = ShellJobStatus::Killed;
+                job.updated_at = chrono::Utc::now().to_rfc3339();
+                Some(job_event(job, String::new()))
+            }
+        }
+    }
+}
+
+/// 追加新输出并生成推送事件
+///
+/// 任务只保留末尾的 [`MAX_OUTPUT_BYTES`]，事件只携带新增部分（同样截取末尾）和它的起始偏移，
+/// 反复读取长时间运行的后台 shell 时不会重复推送已有输出。
+fn job_event(job: &mut ShellJob, added: String) -> ShellJobEvent {
+    let chunk = tail(&added, MAX_OUTPUT_BYTES).to_string();
+    let chunk_offset = job.output_bytes + (added.len() - chunk.len()) as u64;
+    job.output_bytes += added.len() as u64;
+    job.output.push_str(&chunk);
+    let dropped = job.output.len() - tail(&job.output, MAX_OUTPUT_BYTES).len();
+    job.output.replace_range(..dropped, "");
+
+    // 事件中的任务不带累计输出
+    let output = std::mem::take(&mut job.output);
+    let snapshot = job.clone();
+    job.output = output;
+    ShellJobEvent { session_id: job.session_id.clone(), job: snapshot, chunk, chunk_offset }
+}
+
+/// 字符串末尾不超过 `max` 字节的部分（从字符边界开始）
+fn tail(text: &str, max: usize) -> &str {
+    let mut start = text.len().saturating_sub(max);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
+/// 遍历 message.content 数组
+fn content_items(message: &Value) -> impl Iterator<Item = &Value> {
+    message
+        .get("content")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+}
+
+/// 后台 shell 的 ID 字段（不同 CLI 版本命名不同）
+fn shell_id_from_input(input: &Value) -> Option<String> {
+    ["bash_id", "shell_id"]
+        .iter()
+        .find_map(|key| input.get(*key).and_then(|v| v.as_str()))
+        .map(String::from)
+}
+
+/// tool_result 的 content 可能是字符串，也可能是文本块数组
+fn result_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// 解析 "Command running in background with ID: xxx"
+fn parse_background_id(text: &str) -> Option<String> {
+    let rest = &text[text.find("with ID:")? + "with ID:".len()..];
+    let id: String = rest
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+        .collect();
+    (!id.is_empty()).then_some(id)
+}
+
+/// 解析 "Exit code N"
+fn parse_exit_code(text: &str) -> Option<i32> {
+    let rest = &text[text.find("Exit code")? + "Exit code".len()..];
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+/// 提取 `<tag>...</tag>` 之间的内容
+fn extract_tag<'a>(text: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = text.find(&open)? + open.len();
+    let end = start + text[start..].find(&close)?;
+    Some(text[start..end].trim_matches('\n'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn system(claude_id: &str) -> StreamEvent {
+        StreamEvent::System {
+            subtype: Some("init".to_string()),
+            extra: HashMap::from([("session_id".to_string(), json!(claude_id))]),
+        }
+    }
+
+    fn tool_use(id: &str, name: &str, input: Value) -> StreamEvent {
+        StreamEvent::Assistant {
+            message: json!({ "content": [{ "type": "tool_use", "id": id, "name": name, "input": input }] }),
+        }
+    }
+
+    fn tool_result(id: &str, content: &str, is_error: bool) -> StreamEvent {
+        StreamEvent::User {
+            message: json!({ "content": [{ "type": "tool_result", "tool_use_id": id, "content": content, "is_error": is_error }] }),
+        }
+    }
+
+    #[test]
+    fn pairs_tool_use_with_result() {
+        let mut tracker = ShellTracker::new();
+        let started = tracker.observe("s1", Some("/work"), &tool_use("t1", "Bash", json!({ "command": "ls", "description": "List" })));
+        assert_eq!(started.len(), 1);
+        assert_eq!((started[0].job.status, started[0].job.cwd.as_deref()), (ShellJobStatus::Running, Some("/work")));
+
+        // 其他工具不追踪
+        assert!(tracker.observe("s1", None, &tool_use("t2", "Read", json!({ "file_path": "a" }))).is_empty());
+
+        let done = tracker.observe("s1", None, &tool_result("t1", "a.txt\nb.txt", false));
+        assert_eq!((done[0].job.status, done[0].job.exit_code), (ShellJobStatus::Completed, Some(0)));
+        assert_eq!((done[0].chunk.as_str(), done[0].chunk_offset), ("a.txt\nb.txt", 0));
+        assert_eq!(tracker.list("s1")[0].output, "a.txt\nb.txt");
+
+        tracker.observe("s1", None, &tool_use("t3", "Bash", json!({ "command": "false" })));
+        let failed = tracker.observe("s1", None, &tool_result("t3", "Exit code 2\nerror", true));
+        assert_eq!((failed[0].job.status, failed[0].job.exit_code), (ShellJobStatus::Failed, Some(2)));
+        assert_eq!(tracker.list("s1").len(), 2);
+    }
+
+    #[test]
+    fn tracks_background_shells_until_killed() {
+        let mut tracker = ShellTracker::new();
+        tracker.observe("s1", None, &tool_use("t1", "Bash", json!({ "command": "npm run dev", "run_in_background": true })));
+        let job = tracker.observe("s1", None, &tool_result("t1", "Command running in background with ID: bash_1", false));
+        assert!(job[0].job.background);
+        assert_eq!((job[0].job.status, job[0].job.shell_id.as_deref()), (ShellJobStatus::Background, Some("bash_1")));
+
+        tracker.observe("s1", None, &tool_use("t2", "BashOutput", json!({ "bash_id": "bash_1" })));
+        let output = tracker.observe("s1", None, &tool_result("t2", "<status>running</status>\n<stdout>\nready\n</stdout>", false));
+        assert_eq!((output[0].job.status, output[0].chunk.as_str()), (ShellJobStatus::Background, "ready"));
+
+        // 之后的读取只推送新增部分
+        tracker.observe("s1", None, &tool_use("t6", "BashOutput", json!({ "bash_id": "bash_1" })));
+        let output = tracker.observe("s1", None, &tool_result("t6", "<stdout>\nGET /\n</stdout>", false));
+        assert_eq!((output[0].chunk.as_str(), output[0].chunk_offset), ("\nGET /", 5));
+        assert!(output[0].job.output.is_empty());
+        assert_eq!(tracker.list("s1")[0].output, "ready\nGET /");
+
+        // KillShell 失败时状态不变，成功后标记为已终止，之后的重复终止不再产生更新
+        tracker.observe("s1", None, &tool_use("t3", "KillShell", json!({ "shell_id": "bash_1" })));
+        assert!(tracker.observe("s1", None, &tool_result("t3", "no such shell", true)).is_empty());
+        assert_eq!(tracker.list("s1")[0].status, ShellJobStatus::Background);
+
+        tracker.observe("s1", None, &tool_use("t4", "KillShell", json!({ "shell_id": "bash_1" })));
+        let killed = tracker.observe("s1", None, &tool_result("t4", "Successfully killed shell: bash_1", false));
+        assert_eq!(killed[0].job.status, ShellJobStatus::Killed);
+
+        tracker.observe("s1", None, &tool_use("t5", "KillShell", json!({ "shell_id": "bash_1" })));
+        assert!(tracker.observe("s1", None, &tool_result("t5", "Successfully killed shell: bash_1", false)).is_empty());
+    }
+
+    #[test]
+    fn keeps_only_the_output_tail() {
+        let mut tracker = ShellTracker::new();
+        tracker.observe("s1", None, &tool_use("t1", "Bash", json!({ "command": "yes", "run_in_background": true })));
+        tracker.observe("s1", None, &tool_result("t1", "Command running in background with ID: bash_1", false));
+
+        let line = format!("{}\n", "y".repeat(MAX_OUTPUT_BYTES / 2));
+        let mut total = 0;
+        for (i, id) in ["t2", "t3", "t4"].into_iter().enumerate() {
+            tracker.observe("s1", None, &tool_use(id, "BashOutput", json!({ "bash_id": "bash_1" })));
+            let update = tracker.observe("s1", None, &tool_result(id, &format!("<stdout>{}é</stdout>", line), false));
+            // 第二次起以换行分隔
+            let added = usize::from(i > 0) + line.len() + "é".len();
+            total += added;
+            assert_eq!((update[0].chunk.len(), update[0].chunk_offset), (added, (total - added) as u64));
+        }
+
+        let job = &tracker.list("s1")[0];
+        assert!(job.output.len() <= MAX_OUTPUT_BYTES);
+        assert!(job.output.ends_with('é'));
+        assert_eq!(job.output_bytes, total as u64);
+    }
+
+    #[test]
+    fn aliases_claude_session_and_removes_state() {
+        let mut tracker = ShellTracker::new();
+        tracker.observe("local", None, &system("claude-1"));
+        tracker.observe("local", None, &tool_use("t1", "Bash", json!({ "command": "ls" })));
+        // 继续会话时使用 Claude 会话 ID
+        tracker.observe("claude-1", None, &tool_use("t2", "Bash", json!({ "command": "pwd" })));
+        assert_eq!(tracker.list("claude-1").len(), 2);
+        assert_eq!(tracker.list("local").len(), 2);
+
+        tracker.remove_session("claude-1");
+        assert!(tracker.list("local").is_empty());
+        assert!(tracker.sessions.is_empty() && tracker.aliases.is_empty());
+    }
+}
@@ -0,0 +1,419 @@
+use crate::error::{AppError, Result};
+use crate::models::trash::TrashItem;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 复制过程中的临时后缀，复制完成后才重命名为正式条目
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// 删除后的宽限期（小时），期间的条目不会因超出总大小而被清理，以便撤销删除
+const SIZE_PURGE_GRACE_HOURS: i64 = 24;
+
+/// 应用回收站
+///
+/// 每个条目由 `<id>` 文件或目录与 `<id>.json` 元数据组成，都位于回收站目录下。
+/// 跨设备无法重命名时先完整复制，成功后再删除源路径。
+pub struct Trash {
+    root: PathBuf,
+}
+
+impl Trash {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// 将文件或目录移入回收站（符号链接只移动链接本身）
+    pub fn move_to_trash(&self, path: &Path) -> Result<TrashItem> {
+        let metadata = path.symlink_metadata()
+            .map_err(|_| AppError::InvalidPath(format!("路径不存在: {}", path.display())))?;
+        let name = path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| AppError::InvalidPath(format!("无效路径: {}", path.display())))?;
+
+        let now = chrono::Utc::now();
+        let uuid = uuid::Uuid::new_v4().simple().to_string();
+        let id = format!("{}-{}", now.format("%Y%m%d%H%M%S"), &uuid[..8]);
+        let item = TrashItem {
+            id: id.clone(),
+            name,
+            original_path: path.to_string_lossy().to_string(),
+            is_dir: metadata.is_dir(),
+            size: entry_size(path),
+            deleted_at: now.to_rfc3339(),
+        };
+
+        fs::create_dir_all(&self.root)?;
+        let payload = self.payload_path(&id);
+        let copied = move_entry(path, &payload)?;
+
+        if let Err(e) = self.write_meta(&item) {
+            // 元数据写入失败时尽量放回原处，避免留下无法识别的条目
+            eprintln!("[Trash::move_to_trash] 写入元数据失败: {}", e);
+            if copied {
+                let _ = remove_entry(&payload);
+            } else {
+                let _ = fs::rename(&payload, path);
+            }
+            return Err(e);
+        }
+
+        if copied {
+            // 已完整复制到回收站，此时才删除源路径
+            remove_entry(path).map_err(|e| {
+                eprintln!("[Trash::move_to_trash] 删除源路径失败: {:?}: {}", path, e);
+                AppError::IoError(io::Error::new(
+                    e.kind(),
+                    format!("已复制到回收站，但删除原路径失败: {}", e),
+                ))
+            })?;
+        }
+
+        eprintln!("[Trash::move_to_trash] {:?} -> {} (复制: {})", path, id, copied);
+        Ok(item)
+    }
+
+    /// 列出回收站条目（最近删除的在前）
+    pub fn list(&self) -> Vec<TrashItem> {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+
+        let mut items: Vec<TrashItem> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let content = fs::read_to_string(&path).ok()?;
+                let item: TrashItem = serde_json::from_str(&content).ok()?;
+                // 只保留内容仍然存在的条目
+                self.payload_path(&item.id).symlink_metadata().ok()?;
+                Some(item)
+            })
+            .collect();
+
+        items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        items
+    }
+
+    /// 获取单个条目
+    pub fn get(&self, id: &str) -> Result<TrashItem> {
+        validate_id(id)?;
+        let content = fs::read_to_string(self.meta_path(id))
+            .map_err(|_| AppError::InvalidPath(format!("回收站中不存在: {}", id)))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// 恢复到原路径，原路径已存在时拒绝覆盖
+    pub fn restore(&self, id: &str) -> Result<TrashItem> {
+        let item = self.get(id)?;
+        let target = PathBuf::from(&item.original_path);
+        if target.symlink_metadata().is_ok() {
+            return Err(AppError::InvalidPath(format!("原位置已存在同名文件: {}", item.original_path)));
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let payload = self.payload_path(id);
+        if move_entry(&payload, &target)? {
+            remove_entry(&payload)?;
+        }
+        fs::remove_file(self.meta_path(id))?;
+
+        eprintln!("[Trash::restore] {} -> {:?}", id, target);
+        Ok(item)
+    }
+
+    /// 永久删除指定条目，`ids` 为空时清空回收站，返回删除的条目数
+    pub fn empty(&self, ids: Option<&[String]>) -> Result<usize> {
+        let ids: Vec<String> = match ids {
+            Some(ids) => ids.to_vec(),
+            None => self.list().into_iter().map(|item| item.id).collect(),
+        };
+
+        let mut removed = 0;
+        for id in &ids {
+            validate_id(id)?;
+            self.remove(id)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// 按保留天数与总大小清理过期条目（0 表示不限制），返回删除的条目数
+    ///
+    /// 宽限期内删除的条目计入总大小但不会被清理：单个条目超过上限时也不会被立即永久删除，
+    /// 之后再删除其他文件时仍可撤销。
+    pub fn purge(&self, max_age_days: u64, max_bytes: u64) -> usize {
+        let now = chrono::Utc::now();
+        let age = |item: &TrashItem| chrono::DateTime::parse_from_rfc3339(&item.deleted_at)
+            .map(|deleted_at| now - deleted_at.with_timezone(&chrono::Utc))
+            .ok();
+        let mut removed = 0;
+        let mut kept = Vec::new();
+
+        for item in self.list() {
+            let expired = max_age_days > 0
+                && age(&item).is_some_and(|age| age.num_days() >= max_age_days as i64);
+            if expired {
+                if self.remove(&item.id).is_ok() {
+                    removed += 1;
+                }
+            } else {
+                kept.push(item);
+            }
+        }
+
+        if max_bytes > 0 {
+            // 从最早删除的条目开始清理，直到总大小不超过上限
+            let mut total: u64 = kept.iter().map(|item| item.size).sum();
+            for item in kept.iter().rev() {
+                if total <= max_bytes {
+                    break;
+                }
+                if age(item).is_none_or(|age| age.num_hours() < SIZE_PURGE_GRACE_HOURS) {
+                    continue;
+                }
+                if self.remove(&item.id).is_ok() {
+                    total = total.saturating_sub(item.size);
+                    removed += 1;
+                }
+            }
+        }
+
+        if removed > 0 {
+            eprintln!("[Trash::purge] 清理了 {} 个条目", removed);
+        }
+        removed
+    }
+
+    fn remove(&self, id: &str) -> Result<()> {
+        let payload = self.payload_path(id);
+        if payload.symlink_metadata().is_ok() {
+            remove_entry(&payload)?;
+        }
+        let meta = self.meta_path(id);
+        if meta.exists() {
+            fs::remove_file(meta)?;
+        }
+        Ok(())
+    }
+
+    fn write_meta(&self, item: &TrashItem) -> Result<()> {
+        let content = serde_json::to_string_pretty(item)?;
+        fs::write(self.meta_path(&item.id), content)?;
+        Ok(())
+    }
+
+    fn payload_path(&self, id: &str) -> PathBuf {
+        self.root.join(id)
+    }
+
+    fn meta_path(&self, id: &str) -> PathBuf {
+        self.root.join(format!("{}.json", id))
+    }
+}
+
+/// 条目 ID 只能是回收站目录下的单个文件名
+fn validate_id(id: &str) -> Result<()> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(AppError::InvalidPath(format!("无效的回收站条目: {}", id)));
+    }
+    Ok(())
+}
+
+/// 移动条目，返回是否通过复制完成（此时源路径尚未删除）
+fn move_entry(from: &Path, to: &Path) -> Result<bool> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(false),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_to(from, to)?;
+            Ok(true)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 先复制到临时路径，完整成功后再重命名，失败时清理临时文件且不影响源路径
+fn copy_to(from: &Path, to: &Path) -> Result<()> {
+    let mut partial = to.as_os_str().to_os_string();
+    partial.push(PARTIAL_SUFFIX);
+    let partial = PathBuf::from(partial);
+
+    if let Err(e) = copy_recursive(from, &partial).and_then(|_| fs::rename(&partial, to)) {
+        eprintln!("[Trash] 复制 {:?} 失败: {}", from, e);
+        if partial.symlink_metadata().is_ok() {
+            let _ = remove_entry(&partial);
+        }
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = from.symlink_metadata()?;
+    if metadata.is_symlink() {
+        copy_symlink(from, to)
+    } else if metadata.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        fs::set_permissions(to, metadata.permissions())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    let target = fs::read_link(from)?;
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(target, to)
+    } else {
+        std::os::windows::fs::symlink_file(target, to)
+    }
+}
+
+/// 删除条目本身（不跟随符号链接）
+fn remove_entry(path: &Path) -> io::Result<()> {
+    if path.symlink_metadata()?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// 条目占用大小（不跟随符号链接）
+fn entry_size(path: &Path) -> u64 {
+    let Ok(metadata) = path.symlink_metadata() else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| entry_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn moves_and_restores_entries() {
+        let work_dir = TempDir::with_prefix("trash-work-").unwrap();
+        let trash_dir = TempDir::with_prefix("trash-trash-").unwrap();
+        let work = work_dir.path();
+        let trash = Trash::new(trash_dir.path().to_path_buf());
+        fs::create_dir_all(work.join("dir/sub")).unwrap();
+        fs::write(work.join("dir/sub/a.txt"), "hello").unwrap();
+        fs::write(work.join("b.txt"), "abc").unwrap();
+
+        let dir_item = trash.move_to_trash(&work.join("dir")).unwrap();
+        let file_item = trash.move_to_trash(&work.join("b.txt")).unwrap();
+        assert!(!work.join("dir").exists());
+        assert!(dir_item.is_dir);
+        assert_eq!(dir_item.size, 5);
+        assert_eq!(file_item.size, 3);
+        assert_eq!(trash.list().len(), 2);
+
+        trash.restore(&dir_item.id).unwrap();
+        assert_eq!(fs::read_to_string(work.join("dir/sub/a.txt")).unwrap(), "hello");
+
+        // 原位置已存在时拒绝恢复
+        fs::write(work.join("b.txt"), "new").unwrap();
+        assert!(trash.restore(&file_item.id).is_err());
+        assert_eq!(fs::read_to_string(work.join("b.txt")).unwrap(), "new");
+
+        assert_eq!(trash.empty(None).unwrap(), 1);
+        assert!(trash.list().is_empty());
+        assert!(trash.restore("../escape").is_err());
+    }
+
+    #[test]
+    fn copy_fallback_keeps_source_until_complete() {
+        let work_dir = TempDir::with_prefix("trash-work-").unwrap();
+        let dest_dir = TempDir::with_prefix("trash-dest-").unwrap();
+        let (work, dest) = (work_dir.path(), dest_dir.path());
+        fs::create_dir_all(work.join("dir/sub")).unwrap();
+        fs::write(work.join("dir/sub/a.txt"), "hello").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("sub/a.txt", work.join("dir/link")).unwrap();
+
+        copy_to(&work.join("dir"), &dest.join("item")).unwrap();
+        assert!(work.join("dir/sub/a.txt").exists());
+        assert_eq!(fs::read_to_string(dest.join("item/sub/a.txt")).unwrap(), "hello");
+        #[cfg(unix)]
+        assert!(dest.join("item/link").symlink_metadata().unwrap().is_symlink());
+
+        // 复制失败时不留下临时文件，源路径不受影响
+        assert!(copy_to(&work.join("missing"), &dest.join("other")).is_err());
+        assert!(!dest.join("other.partial").exists());
+        assert!(!dest.join("other").exists());
+    }
+
+    #[test]
+    fn purges_by_age_and_size() {
+        let work_dir = TempDir::with_prefix("trash-work-").unwrap();
+        let trash_dir = TempDir::with_prefix("trash-trash-").unwrap();
+        let work = work_dir.path();
+        let trash = Trash::new(trash_dir.path().to_path_buf());
+        let mut ids = Vec::new();
+        for (i, size) in [10usize, 20, 30].into_iter().enumerate() {
+            let path = work.join(format!("{}.txt", i));
+            fs::write(&path, "x".repeat(size)).unwrap();
+            let mut item = trash.move_to_trash(&path).unwrap();
+            // 依次设置为 40、20、0 天前删除
+            item.deleted_at = (chrono::Utc::now() - chrono::Duration::days(40 - 20 * i as i64)).to_rfc3339();
+            trash.write_meta(&item).unwrap();
+            ids.push(item.id);
+        }
+
+        assert_eq!(trash.purge(30, 0), 1);
+        assert_eq!(trash.list().len(), 2);
+
+        // 超出大小上限时先清理最早删除的条目
+        assert_eq!(trash.purge(30, 35), 1);
+        let remaining = trash.list();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, ids[2]);
+    }
+
+    #[test]
+    fn keeps_new_item_larger_than_cap() {
+        let work_dir = TempDir::with_prefix("trash-work-").unwrap();
+        let trash_dir = TempDir::with_prefix("trash-trash-").unwrap();
+        let work = work_dir.path();
+        let trash = Trash::new(trash_dir.path().to_path_buf());
+        fs::write(work.join("old.txt"), "x".repeat(10)).unwrap();
+        let mut old = trash.move_to_trash(&work.join("old.txt")).unwrap();
+        old.deleted_at = (chrono::Utc::now() - chrono::Duration::days(2)).to_rfc3339();
+        trash.write_meta(&old).unwrap();
+        fs::create_dir_all(work.join("big")).unwrap();
+        fs::write(work.join("big/data.bin"), "x".repeat(100)).unwrap();
+        let big = trash.move_to_trash(&work.join("big")).unwrap();
+
+        // 刚删除的条目计入总大小，较早的条目被清理；它本身超过上限也保留在回收站中以便撤销
+        assert_eq!(trash.purge(0, 50), 1);
+        let ids: Vec<String> = trash.list().into_iter().map(|item| item.id).collect();
+        assert_eq!(ids, vec![big.id.clone()]);
+
+        // 之后再次清理（列出回收站、删除其他文件）时仍在宽限期内
+        fs::write(work.join("next.txt"), "x").unwrap();
+        trash.move_to_trash(&work.join("next.txt")).unwrap();
+        assert_eq!(trash.purge(0, 50), 0);
+        assert_eq!(trash.list().len(), 2);
+        trash.restore(&big.id).unwrap();
+        assert_eq!(fs::read_to_string(work.join("big/data.bin")).unwrap().len(), 100);
+    }
+}
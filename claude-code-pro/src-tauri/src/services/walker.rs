@@ -0,0 +1,217 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::WalkBuilder;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// 工作区遍历规则
+///
+/// 遵循 `.gitignore`、`.ignore`、`.git/info/exclude` 和全局 git 排除规则（非 git 目录中
+/// `.gitignore` 同样生效），另外排除配置中的 `file_excludes`。隐藏文件（如 `.github`）
+/// 会被包含，`.git` 目录本身始终跳过，不限制深度。
+pub struct WalkRules {
+    overrides: Override,
+}
+
+impl WalkRules {
+    /// `excludes` 为 gitignore 风格的模式，相对 `root` 匹配；无效的模式会被忽略
+    pub fn new(root: &Path, excludes: &[String]) -> Self {
+        Self::with_includes(root, &[], excludes)
+    }
+
+    /// `includes` 非空时只保留匹配其中任一模式的文件（目录照常遍历）
+    pub fn with_includes(root: &Path, includes: &[String], excludes: &[String]) -> Self {
+        let mut builder = OverrideBuilder::new(root);
+        let includes = includes.iter().map(|p| p.trim().to_string());
+        let excludes = excludes.iter().map(|p| format!("!{}", p.trim()));
+        for pattern in includes.chain(excludes).filter(|p| !p.is_empty() && p != "!") {
+            if let Err(e) = builder.add(&pattern) {
+                eprintln!("[WalkRules::new] 忽略无效的模式 {:?}: {}", pattern, e);
+            }
+        }
+        let overrides = builder.build().unwrap_or_else(|e| {
+            eprintln!("[WalkRules::new] 构建排除规则失败: {}", e);
+            Override::empty()
+        });
+        Self { overrides }
+    }
+
+    /// 从 `start` 开始的遍历（按文件名排序，不跟随符号链接）
+    pub fn walk(&self, start: &Path) -> WalkBuilder {
+        let mut builder = WalkBuilder::new(start);
+        builder
+            .hidden(false)
+            .parents(true)
+            .ignore(true)
+            .git_ignore(true)
+            .git_global(true)
+            .git_exclude(true)
+            .require_git(false)
+            .follow_links(false)
+            .overrides(self.overrides.clone())
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .sort_by_file_name(|a, b| a.cmp(b));
+        builder
+    }
+
+    /// `path` 或它在根目录内的上级目录是否命中 `file_excludes`（不检查 ignore 文件）
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        let root = self.overrides.path();
+        path.ancestors()
+            .take_while(|p| p.starts_with(root) && *p != root)
+            .any(|p| p.file_name() == Some(".git".as_ref())
+                || self.overrides.matched(p, p != path || path.is_dir()).is_ignore())
+    }
+
+    /// 逐个判断路径是否被忽略的匹配器，结果与 [`WalkRules::walk`] 一致
+    pub fn matcher(&self) -> IgnoreMatcher<'_> {
+        let root = self.overrides.path();
+        let exclude = root.ancestors()
+            .find(|dir| dir.join(".git").is_dir())
+            .map(|repo| load_ignore_file(repo, &repo.join(".git/info/exclude")))
+            .unwrap_or_else(Gitignore::empty);
+        let (global, error) = GitignoreBuilder::new(root).build_global();
+        if let Some(e) = error {
+            eprintln!("[WalkRules::matcher] 读取全局排除规则失败: {}", e);
+        }
+        IgnoreMatcher { rules: self, dirs: HashMap::new(), exclude, global }
+    }
+
+    /// `dir` 中未被忽略的直接子项
+    pub fn visible_children(&self, dir: &Path) -> HashSet<PathBuf> {
+        self.walk(dir)
+            .max_depth(Some(1))
+            .build()
+            .flatten()
+            .filter(|entry| entry.depth() == 1)
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+}
+
+/// 单个路径的忽略判断（`file_excludes`、`.git` 和各级 ignore 文件）
+///
+/// 读取过的 ignore 文件按目录缓存，适合在一批路径中复用；ignore 文件变化后应重新创建。
+pub struct IgnoreMatcher<'a> {
+    rules: &'a WalkRules,
+    /// 目录 -> (`.ignore`, `.gitignore`)
+    dirs: HashMap<PathBuf, (Gitignore, Gitignore)>,
+    exclude: Gitignore,
+    global: Gitignore,
+}
+
+impl IgnoreMatcher<'_> {
+    /// `path` 或它在根目录内的上级目录是否会被遍历跳过
+    pub fn is_ignored(&mut self, path: &Path) -> bool {
+        let rules = self.rules;
+        if rules.is_excluded(path) {
+            return true;
+        }
+        let root = rules.overrides.path();
+        path.ancestors()
+            .take_while(|p| p.starts_with(root) && *p != root)
+            .any(|p| self.matches(p, p != path || path.is_dir()))
+    }
+
+    /// 与 ignore 的优先级一致：`.ignore` 优先于 `.gitignore`，同类中近的目录优先，
+    /// 然后是 `.git/info/exclude` 和全局规则
+    fn matches(&mut self, path: &Path, is_dir: bool) -> bool {
+        let parents: Vec<&Path> = path.ancestors().skip(1).collect();
+        for dir in &parents {
+            if !self.dirs.contains_key(*dir) {
+                let files = (load_ignore_file(dir, &dir.join(".ignore")), load_ignore_file(dir, &dir.join(".gitignore")));
+                self.dirs.insert(dir.to_path_buf(), files);
+            }
+        }
+        let ignores = parents.iter().map(|dir| &self.dirs[*dir].0);
+        let gitignores = parents.iter().map(|dir| &self.dirs[*dir].1);
+        ignores.chain(gitignores)
+            .chain([&self.exclude, &self.global])
+            .map(|matcher| matcher.matched(path, is_dir))
+            .find(|m| !m.is_none())
+            .is_some_and(|m| m.is_ignore())
+    }
+}
+
+/// 读取 `dir` 下的一个 ignore 文件，不存在或无效时返回空规则
+fn load_ignore_file(dir: &Path, file: &Path) -> Gitignore {
+    if !file.is_file() {
+        return Gitignore::empty();
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(file) {
+        eprintln!("[load_ignore_file] 解析 {:?} 失败: {}", file, e);
+    }
+    builder.build().unwrap_or_else(|e| {
+        eprintln!("[load_ignore_file] 构建 {:?} 失败: {}", file, e);
+        Gitignore::empty()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn relative_files(rules: &WalkRules, root: &Path) -> Vec<String> {
+        rules.walk(root)
+            .build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .map(|entry| entry.path().strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    #[test]
+    fn honours_ignore_files_and_excludes() {
+        let tmp = tempfile::TempDir::with_prefix("walker-").unwrap();
+        let root = tmp.path();
+        let deep = root.join("a/b/c/d/e/f/g");
+        for dir in [&deep, &root.join(".github/workflows"), &root.join("dist"), &root.join(".git"),
+                    &root.join("vendor"), &root.join("node_modules/pkg"), &root.join("src")] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(root.join(".gitignore"), "dist/\n*.log\n").unwrap();
+        fs::write(root.join(".ignore"), "vendor/\n").unwrap();
+        for file in ["a/b/c/d/e/f/g/deep.rs", ".github/workflows/ci.yml", "dist/app.js", ".git/HEAD",
+                     "vendor/lib.rs", "node_modules/pkg/index.js", "src/main.rs", "src/debug.log"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+
+        let rules = WalkRules::new(root, &["node_modules".to_string(), "[".to_string()]);
+        let files = relative_files(&rules, root);
+        assert_eq!(files, vec![
+            ".github/workflows/ci.yml",
+            ".gitignore",
+            ".ignore",
+            "a/b/c/d/e/f/g/deep.rs",
+            "src/main.rs",
+        ]);
+
+        let visible = rules.visible_children(root);
+        assert!(visible.contains(&root.join("src")));
+        assert!(visible.contains(&root.join(".github")));
+        assert!(!visible.contains(&root.join("dist")));
+        assert!(!visible.contains(&root.join("node_modules")));
+        assert!(!visible.contains(&root.join(".git")));
+        assert!(rules.is_excluded(&root.join("node_modules/pkg/index.js")));
+        assert!(rules.is_excluded(&root.join(".git/HEAD")));
+        assert!(!rules.is_excluded(&root.join("src/main.rs")));
+
+        // 子目录也遵循上级目录的规则
+        let src = rules.visible_children(&root.join("src"));
+        assert_eq!(src, HashSet::from([root.join("src/main.rs")]));
+
+        // 逐个路径的判断与遍历结果一致
+        let mut matcher = rules.matcher();
+        for ignored in ["dist", "dist/app.js", "vendor/lib.rs", "src/debug.log", "node_modules/pkg", ".git/HEAD"] {
+            assert!(matcher.is_ignored(&root.join(ignored)), "{} 应被忽略", ignored);
+        }
+        for visible in ["src/main.rs", ".github/workflows/ci.yml", "a/b/c/d/e/f/g/deep.rs", "src/new.rs"] {
+            assert!(!matcher.is_ignored(&root.join(visible)), "{} 不应被忽略", visible);
+        }
+
+        let rules = WalkRules::with_includes(root, &["*.rs".to_string()], &["a/".to_string()]);
+        assert_eq!(relative_files(&rules, root), vec!["src/main.rs"]);
+    }
+}
@@ -0,0 +1,401 @@
+use crate::error::{AppError, Result};
+use crate::models::git::{MergeConflict, MergeReport, SessionWorktree};
+use crate::services::git::{AGENT_EMAIL, AGENT_NAME};
+use git2::build::CheckoutBuilder;
+use git2::{
+    BranchType, Commit, IndexAddOption, Oid, Repository, Signature, StatusOptions,
+    WorktreeAddOptions, WorktreePruneOptions,
+};
+use std::path::{Path, PathBuf};
+
+/// 会话工作树管理
+///
+/// 每个隔离会话在 `root/<session_id>` 下拥有独立的工作树和 `claude/session-*` 分支，
+/// 元数据保存在 `root/<session_id>.json`。
+pub struct WorktreeManager {
+    root: PathBuf,
+}
+
+impl WorktreeManager {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// 会话元数据路径，会话 ID 只允许字母、数字和 `-`
+    fn meta_path(&self, session_id: &str) -> Result<PathBuf> {
+        validate_session_id(session_id)?;
+        Ok(self.root.join(format!("{}.json", session_id)))
+    }
+
+    /// 从 `repo_dir` 的 HEAD 创建会话工作树
+    pub fn create(&self, repo_dir: &Path, session_id: &str) -> Result<SessionWorktree> {
+        let meta_path = self.meta_path(session_id)?;
+        let repo = Repository::discover(repo_dir)?;
+        let repo_path = repo.workdir()
+            .ok_or_else(|| AppError::GitError("不支持裸仓库".to_string()))?
+            .to_path_buf();
+        let head = repo.head()
+            .and_then(|h| h.peel_to_commit())
+            .map_err(|_| AppError::GitError("仓库还没有提交，无法创建工作树".to_string()))?;
+
+        let short: String = session_id.chars().filter(|c| c.is_ascii_alphanumeric()).take(8).collect();
+        let name = format!("claude-{}", short);
+        let branch_name = format!("claude/session-{}", short);
+        let path = self.root.join(session_id);
+        std::fs::create_dir_all(&self.root)?;
+
+        let branch = repo.branch(&branch_name, &head, false)?;
+        let mut opts = WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        if let Err(e) = repo.worktree(&name, &path, Some(&opts)) {
+            // 创建失败时不留下空分支
+            if let Ok(mut branch) = repo.find_branch(&branch_name, BranchType::Local) {
+                let _ = branch.delete();
+            }
+            return Err(e.into());
+        }
+
+        let worktree = SessionWorktree {
+            session_id: session_id.to_string(),
+            repo_path: repo_path.to_string_lossy().to_string(),
+            work_dir: repo_dir.to_string_lossy().to_string(),
+            path: path.to_string_lossy().to_string(),
+            name,
+            branch: branch_name,
+            base_commit: head.id().to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            aliases: Vec::new(),
+        };
+        std::fs::write(meta_path, serde_json::to_string_pretty(&worktree)?)?;
+        eprintln!("[WorktreeManager::create] 会话 {} 工作树: {}", session_id, worktree.path);
+        Ok(worktree)
+    }
+
+    /// 获取会话的工作树（`session_id` 也可以是记录过的 Claude 会话 ID）
+    pub fn get(&self, session_id: &str) -> Result<Option<SessionWorktree>> {
+        let worktree = std::fs::read_to_string(self.meta_path(session_id)?)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .or_else(|| self.list().into_iter().find(|w| w.aliases.iter().any(|a| a == session_id)));
+        Ok(worktree)
+    }
+
+    /// 记录会话的 Claude 会话 ID，之后可用它查找工作树；会话没有工作树时返回 false
+    pub fn alias(&self, session_id: &str, claude_id: &str) -> Result<bool> {
+        let Some(mut worktree) = self.get(session_id)? else {
+            return Ok(false);
+        };
+        if worktree.session_id == claude_id || worktree.aliases.iter().any(|a| a == claude_id) {
+            return Ok(true);
+        }
+        worktree.aliases.push(claude_id.to_string());
+        std::fs::write(self.meta_path(&worktree.session_id)?, serde_json::to_string_pretty(&worktree)?)?;
+        eprintln!("[WorktreeManager::alias] 会话 {} 的 Claude 会话 ID: {}", worktree.session_id, claude_id);
+        Ok(true)
+    }
+
+    /// 列出全部会话工作树（按创建时间）
+    pub fn list(&self) -> Vec<SessionWorktree> {
+        let Ok(entries) = std::fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+
+        let mut list: Vec<SessionWorktree> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("json"))
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .filter_map(|content| serde_json::from_str(&content).ok())
+            .collect();
+        list.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        list
+    }
+
+    /// 清理目录已不存在的工作树记录，返回清理的数量
+    pub fn gc(&self) -> usize {
+        let mut removed = 0;
+        for worktree in self.list() {
+            if Path::new(&worktree.path).exists() {
+                continue;
+            }
+            eprintln!("[WorktreeManager::gc] 工作树目录已删除: {}", worktree.path);
+            if let Ok(repo) = Repository::open(&worktree.repo_path) {
+                prune_worktree(&repo, &worktree.name);
+            }
+            if let Ok(meta_path) = self.meta_path(&worktree.session_id) {
+                let _ = std::fs::remove_file(meta_path);
+            }
+            removed += 1;
+        }
+        removed
+    }
+
+    /// 将会话分支合并到主仓库当前分支
+    ///
+    /// 工作树中未提交的修改先以代理身份自动提交。存在冲突时不修改主工作区，
+    /// 只返回冲突列表。
+    pub fn merge(&self, session_id: &str, message: Option<&str>) -> Result<MergeReport> {
+        let worktree = self.get(session_id)?
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+        let mut report = MergeReport::default();
+
+        let session_repo = Repository::open(&worktree.path)?;
+        let fallback = format!("Claude 会话 {} 的修改", worktree.branch);
+        report.auto_commit = commit_pending(&session_repo, message.unwrap_or(&fallback))?
+            .map(|id| id.to_string());
+
+        let repo = Repository::open(&worktree.repo_path)?;
+        let theirs = repo.find_branch(&worktree.branch, BranchType::Local)?
+            .get()
+            .peel_to_commit()?;
+        let annotated = repo.find_annotated_commit(theirs.id())?;
+        let (analysis, _) = repo.merge_analysis(&[&annotated])?;
+
+        if analysis.is_up_to_date() {
+            report.merged = true;
+            report.up_to_date = true;
+            report.commit = repo.head()?.target().map(|id| id.to_string());
+            return Ok(report);
+        }
+
+        if has_local_changes(&repo)? {
+            return Err(AppError::GitError("主工作区有未提交的修改，请先提交或储藏".to_string()));
+        }
+
+        let ours = repo.head()?.peel_to_commit()?;
+        let target = if analysis.is_fast_forward() {
+            report.fast_forward = true;
+            theirs.id()
+        } else {
+            let mut index = repo.merge_commits(&ours, &theirs, None)?;
+            if index.has_conflicts() {
+                for conflict in index.conflicts()? {
+                    let conflict = conflict?;
+                    let entry = conflict.our.as_ref()
+                        .or(conflict.their.as_ref())
+                        .or(conflict.ancestor.as_ref());
+                    let Some(entry) = entry else { continue };
+                    let kind = match (&conflict.ancestor, &conflict.our, &conflict.their) {
+                        (None, Some(_), Some(_)) => "bothAdded",
+                        (_, None, Some(_)) => "deletedByUs",
+                        (_, Some(_), None) => "deletedByThem",
+                        _ => "bothModified",
+                    };
+                    report.conflicts.push(MergeConflict {
+                        path: String::from_utf8_lossy(&entry.path).to_string(),
+                        kind: kind.to_string(),
+                    });
+                }
+                eprintln!("[WorktreeManager::merge] 会话 {} 合并冲突: {} 个文件", session_id, report.conflicts.len());
+                return Ok(report);
+            }
+
+            let tree = repo.find_tree(index.write_tree_to(&repo)?)?;
+            let signature = user_signature(&repo)?;
+            let message = format!("Merge {} into {}", worktree.branch, repo.head()?.shorthand().unwrap_or("HEAD"));
+            repo.commit(None, &signature, &signature, &message, &tree, &[&ours, &theirs])?
+        };
+
+        // 先更新工作区再移动 HEAD，失败时仓库保持原状
+        let commit = repo.find_commit(target)?;
+        let mut checkout = CheckoutBuilder::new();
+        checkout.safe();
+        repo.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+
+        let mut head = repo.head()?;
+        if head.is_branch() {
+            head.set_target(target, &format!("merge {}", worktree.branch))?;
+        } else {
+            repo.set_head_detached(target)?;
+        }
+
+        report.merged = true;
+        report.commit = Some(target.to_string());
+        eprintln!("[WorktreeManager::merge] 会话 {} 已合并: {}", session_id, target);
+        Ok(report)
+    }
+
+    /// 删除会话工作树及其分支，返回是否存在
+    pub fn discard(&self, session_id: &str) -> Result<bool> {
+        let Some(worktree) = self.get(session_id)? else {
+            return Ok(false);
+        };
+        let meta_path = self.meta_path(&worktree.session_id)?;
+
+        let path = Path::new(&worktree.path);
+        if path.exists() {
+            // 元数据中的路径必须位于工作树目录下，避免删除任意目录
+            let root = self.root.canonicalize()?;
+            if path.canonicalize()?.parent() != Some(root.as_path()) {
+                return Err(AppError::PermissionDenied(format!("工作树不在 {} 下: {}", root.display(), worktree.path)));
+            }
+            std::fs::remove_dir_all(path)?;
+        }
+
+        if let Ok(repo) = Repository::open(&worktree.repo_path) {
+            prune_worktree(&repo, &worktree.name);
+            if let Ok(mut branch) = repo.find_branch(&worktree.branch, BranchType::Local) {
+                if let Err(e) = branch.delete() {
+                    eprintln!("[WorktreeManager::discard] 删除分支失败: {}", e);
+                }
+            }
+        }
+
+        std::fs::remove_file(meta_path)?;
+        eprintln!("[WorktreeManager::discard] 已删除会话 {} 的工作树", worktree.session_id);
+        Ok(true)
+    }
+}
+
+/// 会话 ID 会拼接进路径，只允许字母、数字和 `-`
+fn validate_session_id(session_id: &str) -> Result<()> {
+    let valid = !session_id.is_empty() && session_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if !valid {
+        return Err(AppError::InvalidPath(format!("无效的会话 ID: {}", session_id)));
+    }
+    Ok(())
+}
+
+fn prune_worktree(repo: &Repository, name: &str) {
+    let Ok(worktree) = repo.find_worktree(name) else {
+        return;
+    };
+    let mut opts = WorktreePruneOptions::new();
+    opts.valid(true).working_tree(true);
+    if let Err(e) = worktree.prune(Some(&mut opts)) {
+        eprintln!("[prune_worktree] 清理工作树 {} 失败: {}", name, e);
+    }
+}
+
+/// 用户签名，未配置时使用应用名
+fn user_signature(repo: &Repository) -> Result<Signature<'static>> {
+    Ok(repo.signature().or_else(|_| Signature::now("Claude Code Pro", "noreply@localhost"))?)
+}
+
+/// 主工作区是否有已跟踪文件的修改
+fn has_local_changes(repo: &Repository) -> Result<bool> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    Ok(!repo.statuses(Some(&mut opts))?.is_empty())
+}
+
+/// 以代理身份提交工作树中的全部修改，没有修改时返回 None
+fn commit_pending(repo: &Repository, message: &str) -> Result<Option<Oid>> {
+    let mut index = repo.index()?;
+    index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"], None)?;
+    index.write()?;
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent: Commit = repo.head()?.peel_to_commit()?;
+    if parent.tree_id() == tree.id() {
+        return Ok(None);
+    }
+
+    let author = Signature::now(AGENT_NAME, AGENT_EMAIL)?;
+    let committer = user_signature(repo)?;
+    let id = repo.commit(Some("HEAD"), &author, &committer, message, &tree, &[&parent])?;
+    Ok(Some(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::git::tests::{commit_all, temp_repo};
+    use tempfile::TempDir;
+
+    fn manager() -> (TempDir, WorktreeManager) {
+        let dir = TempDir::with_prefix("worktrees-").unwrap();
+        let manager = WorktreeManager::new(dir.path().to_path_buf());
+        (dir, manager)
+    }
+
+    #[test]
+    fn merges_session_changes_back() {
+        let (tmp, repo) = temp_repo();
+        let dir = tmp.path();
+        std::fs::write(dir.join("a.txt"), "one\n").unwrap();
+        commit_all(&repo, "init");
+
+        let (_manager_dir, manager) = manager();
+        let worktree = manager.create(dir, "session-1").unwrap();
+        assert_eq!(manager.list().len(), 1);
+        assert_eq!(Path::new(&worktree.work_dir), dir);
+        std::fs::write(Path::new(&worktree.path).join("a.txt"), "agent\n").unwrap();
+        std::fs::write(Path::new(&worktree.path).join("new.txt"), "new\n").unwrap();
+        // 主工作区不受影响
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "one\n");
+
+        let report = manager.merge("session-1", None).unwrap();
+        assert!(report.merged && report.fast_forward);
+        assert!(report.auto_commit.is_some());
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "agent\n");
+        assert!(dir.join("new.txt").exists());
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.author().email(), Some(AGENT_EMAIL));
+        assert!(manager.merge("session-1", None).unwrap().up_to_date);
+
+        assert!(manager.discard("session-1").unwrap());
+        assert!(!Path::new(&worktree.path).exists());
+        assert!(repo.find_branch(&worktree.branch, BranchType::Local).is_err());
+        assert!(manager.list().is_empty());
+    }
+
+    #[test]
+    fn reports_conflicts_without_touching_main() {
+        let (tmp, repo) = temp_repo();
+        let dir = tmp.path();
+        std::fs::write(dir.join("a.txt"), "one\n").unwrap();
+        commit_all(&repo, "init");
+
+        let (_manager_dir, manager) = manager();
+        let worktree = manager.create(dir, "session-2").unwrap();
+        std::fs::write(Path::new(&worktree.path).join("a.txt"), "agent\n").unwrap();
+        std::fs::write(dir.join("a.txt"), "user\n").unwrap();
+        commit_all(&repo, "user change");
+        let before = repo.head().unwrap().target();
+
+        let report = manager.merge("session-2", Some("agent change")).unwrap();
+        assert!(!report.merged);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].path, "a.txt");
+        assert_eq!(report.conflicts[0].kind, "bothModified");
+        assert_eq!(repo.head().unwrap().target(), before);
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "user\n");
+
+        // 工作树目录被删除后记录会被回收
+        std::fs::remove_dir_all(&worktree.path).unwrap();
+        assert_eq!(manager.gc(), 1);
+        assert!(manager.get("session-2").unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_bad_ids_and_foreign_paths() {
+        let (_manager_dir, manager) = manager();
+        let (tmp, _repo) = temp_repo();
+        for id in ["../../x", "a/b", "", "a.json"] {
+            assert!(manager.get(id).is_err(), "{}", id);
+            assert!(manager.discard(id).is_err(), "{}", id);
+            assert!(manager.create(tmp.path(), id).is_err(), "{}", id);
+        }
+
+        // 元数据被篡改为工作树目录之外的路径时不删除
+        let outside = TempDir::with_prefix("worktrees-outside-").unwrap();
+        let worktree = SessionWorktree {
+            session_id: "forged".to_string(),
+            repo_path: tmp.path().to_string_lossy().to_string(),
+            work_dir: tmp.path().to_string_lossy().to_string(),
+            path: outside.path().to_string_lossy().to_string(),
+            name: "claude-forged".to_string(),
+            branch: "claude/session-forged".to_string(),
+            base_commit: String::new(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            aliases: Vec::new(),
+        };
+        std::fs::create_dir_all(&manager.root).unwrap();
+        std::fs::write(manager.meta_path("forged").unwrap(), serde_json::to_string(&worktree).unwrap()).unwrap();
+        assert!(matches!(manager.discard("forged"), Err(AppError::PermissionDenied(_))));
+        assert!(outside.path().exists());
+    }
+}
@@ -0,0 +1,293 @@
+//! ChatSession 端到端测试
+//!
+//! 通过 `mock-claude` 模拟 CLI，覆盖启动、继续、中断、崩溃和解析失败。
+//! 需启用 mock-cli：`cargo test --features mock-cli`。
+
+mod common;
+
+use claude_code_pro_lib::commands::chat::ChatSession;
+use claude_code_pro_lib::commands::git::check_idle;
+use claude_code_pro_lib::models::events::StreamEvent;
+use claude_code_pro_lib::models::scheduler::RunLimits;
+use claude_code_pro_lib::services::scheduler::RunScheduler;
+use claude_code_pro_lib::services::worktree::WorktreeManager;
+use common::{collect, config, next_event, recorded_args, spawn_reader, workspace};
+use std::collections::HashMap;
+use std::path::Path;
+
+fn system_session_id(events: &[StreamEvent]) -> Option<String> {
+    events.iter().find_map(|e| match e {
+        StreamEvent::System { extra, .. } => extra.get("session_id")?.as_str().map(String::from),
+        _ => None,
+    })
+}
+
+/// 带一次初始提交的 git 工作目录
+fn git_workspace(fixture: Option<&str>) -> tempfile::TempDir {
+    let dir = workspace(fixture);
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    std::fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("a.txt")).unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("test", "test@localhost").unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[]).unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn start_streams_all_events() {
+    let tmp = workspace(Some("basic.jsonl"));
+    let dir = tmp.path();
+    let session = ChatSession::start(&config(dir), "hello").unwrap();
+    let events = collect(session).await;
+
+    assert!(matches!(events.first(), Some(StreamEvent::System { subtype: Some(s), .. }) if s == "init"));
+    assert_eq!(events.iter().filter(|e| matches!(e, StreamEvent::Assistant { .. })).count(), 2);
+    assert!(events.iter().any(|e| matches!(e, StreamEvent::User { .. })));
+    match events.last() {
+        Some(StreamEvent::Result { subtype, extra }) => {
+            assert_eq!(subtype, "success");
+            assert_eq!(extra["result"], "All tests pass: hello");
+        }
+        other => panic!("expected result event, got {:?}", other),
+    }
+
+    let args = recorded_args(dir);
+    assert!(args.windows(2).any(|w| w == ["--output-format", "stream-json"]));
+    assert!(args.windows(2).any(|w| w == ["--permission-mode", "acceptEdits"]));
+    assert!(args.contains(&"--print".to_string()));
+    assert!(!args.contains(&"--resume".to_string()));
+    assert_eq!(args.last().map(String::as_str), Some("hello"));
+}
+
+#[tokio::test]
+async fn resume_passes_session_id() {
+    let tmp = workspace(None);
+    let dir = tmp.path();
+    let session = ChatSession::resume(&config(dir), "existing-session", "again").unwrap();
+    assert_eq!(session.id, "existing-session");
+
+    let events = collect(session).await;
+    assert_eq!(system_session_id(&events).as_deref(), Some("existing-session"));
+    assert!(matches!(events.last(), Some(StreamEvent::Result { .. })));
+
+    let args = recorded_args(dir);
+    assert!(args.windows(2).any(|w| w == ["--resume", "existing-session"]));
+    assert_eq!(args.last().map(String::as_str), Some("again"));
+}
+
+#[tokio::test]
+async fn isolated_session_continues_with_claude_id() {
+    let tmp = git_workspace(None);
+    let dir = tmp.path();
+
+    let worktrees = workspace(None);
+    let manager = WorktreeManager::new(worktrees.path().join("worktrees"));
+    let worktree = manager.create(dir, "local-id").unwrap();
+    let session = ChatSession::start_with_id(&config(Path::new(&worktree.path)), "local-id", "hello").unwrap();
+    let events = collect(session).await;
+
+    // 前端改用 system 事件中的 Claude 会话 ID
+    let claude_id = system_session_id(&events).expect("system event");
+    assert_ne!(claude_id, "local-id");
+    assert!(manager.alias("local-id", &claude_id).unwrap());
+
+    let found = manager.get(&claude_id).unwrap().expect("worktree by claude id");
+    assert_eq!(found.path, worktree.path);
+    let session = ChatSession::resume(&config(Path::new(&found.path)), &claude_id, "again").unwrap();
+    collect(session).await;
+    assert!(recorded_args(Path::new(&worktree.path)).windows(2).any(|w| w == ["--resume", claude_id.as_str()]));
+    assert!(!dir.join("mock-claude-args.json").exists());
+
+    assert!(manager.discard(&claude_id).unwrap());
+    assert!(manager.get("local-id").unwrap().is_none());
+    assert!(!Path::new(&worktree.path).exists());
+}
+
+#[tokio::test]
+async fn refuses_to_discard_running_worktree_by_alias() {
+    let tmp = git_workspace(Some("hang.jsonl"));
+    let dir = tmp.path();
+    let worktrees = workspace(None);
+    let manager = WorktreeManager::new(worktrees.path().join("worktrees"));
+    let worktree = manager.create(dir, "local-id").unwrap();
+    std::fs::copy(dir.join("mock-claude.jsonl"), Path::new(&worktree.path).join("mock-claude.jsonl")).unwrap();
+
+    // 首轮运行以本地 ID 登记，前端随后只知道 Claude 会话 ID
+    let session = ChatSession::start_with_id(&config(Path::new(&worktree.path)), "local-id", "wait").unwrap();
+    let cancel = session.cancel_token();
+    let mut sessions = HashMap::from([("local-id".to_string(), cancel.clone())]);
+    let mut scheduler = RunScheduler::new(RunLimits { max_concurrent: 1, max_per_workspace: 1 });
+    let mut rx = spawn_reader(session);
+
+    let mut events = Vec::new();
+    while !events.iter().any(|e| matches!(e, StreamEvent::Assistant { .. })) {
+        events.push(next_event(&mut rx).await.expect("assistant event"));
+    }
+    let claude_id = system_session_id(&events).expect("system event");
+    assert!(manager.alias("local-id", &claude_id).unwrap());
+
+    assert!(check_idle(&manager, &sessions, &scheduler, &claude_id).is_err());
+    assert!(Path::new(&worktree.path).exists());
+
+    cancel.cancel();
+    while next_event(&mut rx).await.is_some() {}
+    sessions.remove("local-id");
+    assert!(check_idle(&manager, &sessions, &scheduler, &claude_id).is_ok());
+
+    // 排队中的后续轮次同样视为运行中
+    scheduler.admit("other", "/w", 0);
+    scheduler.admit(&claude_id, &worktree.path, 0);
+    assert!(check_idle(&manager, &sessions, &scheduler, "local-id").is_err());
+    scheduler.cancel(&claude_id);
+
+    check_idle(&manager, &sessions, &scheduler, &claude_id).unwrap();
+    assert!(manager.discard(&claude_id).unwrap());
+    assert!(!Path::new(&worktree.path).exists());
+}
+
+#[tokio::test]
+async fn interrupt_stops_hanging_session() {
+    let tmp = workspace(Some("hang.jsonl"));
+    let dir = tmp.path();
+    let mut config = config(dir);
+
+    // 与真实 CLI 一样，收到 SIGTERM/SIGINT 后以非零退出码结束
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let wrapper = dir.join("claude-wrapper.sh");
+        let script = format!("#!/bin/sh\ntrap 'exit 143' TERM INT\n\"{}\" \"$@\" &\nwait $!\n", config.claude_cmd);
+        std::fs::write(&wrapper, script).unwrap();
+        std::fs::set_permissions(&wrapper, std::fs::Permissions::from_mode(0o755)).unwrap();
+        config.claude_cmd = wrapper.to_string_lossy().to_string();
+    }
+
+    let session = ChatSession::start(&config, "wait").unwrap();
+    let cancel = session.cancel_token();
+    let mut rx = spawn_reader(session);
+
+    // 等到助手开始输出后再中断
+    let mut events = Vec::new();
+    while !events.iter().any(|e| matches!(e, StreamEvent::Assistant { .. })) {
+        events.push(next_event(&mut rx).await.expect("assistant event"));
+    }
+
+    cancel.cancel();
+
+    // 读取任务结束后发送端被释放
+    while let Some(event) = next_event(&mut rx).await {
+        events.push(event);
+    }
+
+    // 被中断的进程没有结果，非零退出码也不报告为异常退出
+    assert!(!events.iter().any(|e| matches!(e, StreamEvent::Result { .. } | StreamEvent::Error { .. })));
+}
+
+#[tokio::test]
+async fn crash_reports_exit_code_and_stderr() {
+    let tmp = workspace(Some("crash.jsonl"));
+    let dir = tmp.path();
+    let session = ChatSession::start(&config(dir), "boom").unwrap();
+    let events = collect(session).await;
+
+    assert!(events.iter().any(|e| matches!(e, StreamEvent::Assistant { .. })));
+    match events.last() {
+        Some(StreamEvent::Error { error }) => {
+            assert!(error.contains("退出码: 3"), "{}", error);
+            assert!(error.contains("socket hang up"), "{}", error);
+        }
+        other => panic!("expected error event, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn malformed_lines_do_not_stop_stream() {
+    let tmp = workspace(Some("malformed.jsonl"));
+    let dir = tmp.path();
+    let session = ChatSession::start(&config(dir), "parse").unwrap();
+    let events = collect(session).await;
+
+    assert!(matches!(events.first(), Some(StreamEvent::System { .. })));
+    assert!(events.iter().any(|e| matches!(e, StreamEvent::Assistant { .. })));
+    assert!(matches!(events.last(), Some(StreamEvent::Result { .. })));
+
+    // 坏行以 ParseError 转发，并保留原始内容
+    let bad_lines: Vec<&str> = events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::ParseError { line, .. } => Some(line.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(bad_lines.len(), 2);
+    assert_eq!(bad_lines[1], "this is not json");
+}
+
+#[tokio::test]
+async fn missing_binary_fails_to_start() {
+    let tmp = workspace(None);
+    let dir = tmp.path();
+    let mut config = config(dir);
+    config.claude_cmd = dir.join("no-such-claude").to_string_lossy().to_string();
+
+    assert!(ChatSession::start(&config, "hi").is_err());
+}
+
+#[tokio::test]
+async fn partial_messages_assemble_to_final_message() {
+    let tmp = workspace(Some("partial.jsonl"));
+    let dir = tmp.path();
+    let session = ChatSession::start(&config(dir), "ls").unwrap();
+    let events = collect(session).await;
+
+    assert!(recorded_args(dir).contains(&"--include-partial-messages".to_string()));
+    assert!(!events.iter().any(|e| matches!(e, StreamEvent::Partial { .. } | StreamEvent::Unknown { .. })));
+
+    let text: String = events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::TextDelta { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let thinking: String = events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::ThinkingDelta { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(thinking, "The user wants a listing.");
+
+    let tool_input: String = events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::ToolInputDelta { tool_use_id, tool_name, partial_json } => {
+                assert_eq!((tool_use_id.as_str(), tool_name.as_str()), ("toolu_01", "Bash"));
+                Some(partial_json.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    let tool_input: serde_json::Value = serde_json::from_str(&tool_input).unwrap();
+
+    assert!(events.iter().any(|e| matches!(
+        e,
+        StreamEvent::MessageDelta { stop_reason: Some(reason), .. } if reason == "tool_use"
+    )));
+
+    // 组装结果与完整消息一致
+    let message = events
+        .iter()
+        .find_map(|e| match e {
+            StreamEvent::Assistant { message } => Some(message),
+            _ => None,
+        })
+        .expect("assistant event");
+    assert_eq!(message["content"][1]["text"], text.as_str());
+    assert_eq!(message["content"][0]["thinking"], thinking.as_str());
+    assert_eq!(message["content"][2]["input"], tool_input);
+}
@@ -0,0 +1,77 @@
+//! 集成测试共用的辅助函数
+
+#![allow(dead_code)]
+
+use claude_code_pro_lib::commands::chat::ChatSession;
+#[cfg(feature = "mock-cli")]
+use claude_code_pro_lib::models::config::Config;
+use claude_code_pro_lib::models::events::StreamEvent;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::sync::mpsc;
+
+/// mock-claude 只在启用 mock-cli 时构建
+#[cfg(feature = "mock-cli")]
+pub const MOCK_CLAUDE: &str = env!("CARGO_BIN_EXE_mock-claude");
+
+/// 测试数据目录
+pub fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures")
+}
+
+/// 为每个测试准备独立的工作目录，并放入对应的脚本（目录在 drop 时删除）
+pub fn workspace(fixture: Option<&str>) -> TempDir {
+    let dir = TempDir::with_prefix("mock-claude-").unwrap();
+    if let Some(fixture) = fixture {
+        std::fs::copy(fixtures_dir().join(fixture), dir.path().join("mock-claude.jsonl")).unwrap();
+    }
+    dir
+}
+
+#[cfg(feature = "mock-cli")]
+pub fn config(work_dir: &Path) -> Config {
+    Config {
+        claude_cmd: MOCK_CLAUDE.to_string(),
+        work_dir: Some(work_dir.to_path_buf()),
+        permission_mode: "acceptEdits".to_string(),
+        ..Config::default()
+    }
+}
+
+/// mock-claude 最近一次运行时收到的参数
+pub fn recorded_args(work_dir: &Path) -> Vec<String> {
+    let content = std::fs::read_to_string(work_dir.join("mock-claude-args.json")).unwrap();
+    serde_json::from_str(&content).unwrap()
+}
+
+/// 在后台任务中读取事件，返回接收端
+pub fn spawn_reader(session: ChatSession) -> mpsc::UnboundedReceiver<StreamEvent> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        session
+            .read_events(move |event| {
+                let _ = tx.send(event);
+                async {}
+            })
+            .await;
+    });
+    rx
+}
+
+/// 接收下一个事件，读取结束返回 None，超时则失败
+pub async fn next_event(rx: &mut mpsc::UnboundedReceiver<StreamEvent>) -> Option<StreamEvent> {
+    tokio::time::timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for event")
+}
+
+/// 收集全部事件直到读取结束
+pub async fn collect(session: ChatSession) -> Vec<StreamEvent> {
+    let mut rx = spawn_reader(session);
+    let mut events = Vec::new();
+    while let Some(event) = next_event(&mut rx).await {
+        events.push(event);
+    }
+    events
+}
@@ -0,0 +1,142 @@
+//! 会话录制与回放测试
+//!
+//! `fixtures/recordings` 中的录制同时作为 `StreamEvent::parse_line` 的回归用例。
+
+mod common;
+
+use claude_code_pro_lib::commands::chat::ChatSession;
+use claude_code_pro_lib::models::events::StreamEvent;
+use claude_code_pro_lib::services::recorder::{RecordEntry, Recording};
+use common::fixtures_dir;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn recordings() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(fixtures_dir().join("recordings"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("jsonl"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty());
+    files
+}
+
+async fn replay(recording: &Recording, speed: f64) -> Vec<StreamEvent> {
+    let mut events = Vec::new();
+    ChatSession::replay(recording, speed, |event| {
+        events.push(event);
+        async {}
+    })
+    .await;
+    events
+}
+
+#[tokio::test]
+async fn recorded_stdout_lines_parse() {
+    for path in recordings() {
+        let recording = Recording::load(&path).unwrap();
+        for line in recording.stdout_lines() {
+            match StreamEvent::parse_line(line) {
+                Some(StreamEvent::ParseError { .. }) | Some(StreamEvent::Unknown { .. }) | None => {
+                    panic!("{}: failed to parse {}", path.display(), line)
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn replay_emits_recorded_events_in_order() {
+    let recording = Recording::load(&fixtures_dir().join("recordings/tool_session.jsonl")).unwrap();
+    assert_eq!(recording.session_id, "7f1c2b9e-3d4a-4c55-9a0e-5b8f2f1d6e21");
+
+    let kinds: Vec<_> = replay(&recording, 0.0).await.iter().map(StreamEvent::kind).collect();
+    assert_eq!(
+        kinds,
+        ["system", "assistant", "user", "assistant", "user", "assistant", "result"]
+    );
+}
+
+#[tokio::test]
+async fn replay_reports_crash_from_exit_code() {
+    let recording = Recording::load(&fixtures_dir().join("recordings/overloaded_crash.jsonl")).unwrap();
+    let events = replay(&recording, 0.0).await;
+
+    match events.last() {
+        Some(StreamEvent::Error { error }) => {
+            assert!(error.contains("退出码: 1"), "{}", error);
+            assert!(error.contains("overloaded_error"), "{}", error);
+        }
+        other => panic!("expected error event, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn replay_skips_exit_error_after_interrupt() {
+    let mut recording = Recording::load(&fixtures_dir().join("recordings/overloaded_crash.jsonl")).unwrap();
+    match recording.entries.last_mut() {
+        Some(RecordEntry::Exit { cancelled, .. }) => *cancelled = true,
+        other => panic!("expected exit entry, got {:?}", other),
+    }
+
+    let events = replay(&recording, 0.0).await;
+    assert!(!events.iter().any(|event| matches!(event, StreamEvent::Error { .. })), "{:?}", events);
+}
+
+#[tokio::test]
+async fn replay_respects_speed() {
+    let recording = Recording::load(&fixtures_dir().join("recordings/overloaded_crash.jsonl")).unwrap();
+
+    // 录制时长约 2.4 秒，50 倍速应在 50ms 左右完成
+    let started = Instant::now();
+    replay(&recording, 50.0).await;
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(40), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(1500), "{:?}", elapsed);
+}
+
+#[tokio::test(start_paused = true)]
+async fn replay_clamps_extreme_speeds() {
+    let recording = Recording::load(&fixtures_dir().join("recordings/overloaded_crash.jsonl")).unwrap();
+    let expected = replay(&recording, 0.0).await.len();
+
+    // 极小的倍速不会让等待时间溢出，非法值按不等待处理
+    for speed in [1e-300, f64::MIN_POSITIVE, f64::INFINITY, f64::NAN, -1.0] {
+        assert_eq!(replay(&recording, speed).await.len(), expected, "{}", speed);
+    }
+}
+
+/// 真实会话需要 mock-claude：cargo test --features mock-cli
+#[cfg(feature = "mock-cli")]
+mod live {
+    use super::*;
+    use claude_code_pro_lib::services::recorder::{RecordEntry, RecordStream, SessionRecorder};
+    use super::common::{collect, config, workspace};
+
+    #[tokio::test]
+    async fn live_session_round_trips_through_recording() {
+        let tmp = workspace(Some("crash.jsonl"));
+        let dir = tmp.path();
+        let recordings_dir = dir.join("recordings");
+
+        let session = ChatSession::start(&config(dir), "record me").unwrap();
+        let recorder = SessionRecorder::create(&recordings_dir, &session.id).unwrap();
+        let path = recorder.path().to_path_buf();
+        let live = collect(session.with_recorder(recorder)).await;
+
+        let recording = Recording::load(&path).unwrap();
+        assert!(recording.entries.iter().any(|e| matches!(
+            e,
+            RecordEntry::Line { stream: RecordStream::Stderr, line, .. } if line.contains("socket hang up")
+        )));
+        assert!(matches!(recording.entries.last(), Some(RecordEntry::Exit { code: Some(3), .. })));
+
+        let replayed = replay(&recording, 0.0).await;
+        assert_eq!(
+            serde_json::to_value(&live).unwrap(),
+            serde_json::to_value(&replayed).unwrap()
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! 模拟 Claude CLI，用于端到端测试
+//!
+//! 支持与真实 CLI 相同的参数（`--print`、`--output-format stream-json`、
+//! `--resume`、`--permission-mode` 等），并按脚本回放 stream-json 输出。
+//! 不带 `--output-format` 时为文本模式：读取 stdin，输出 `draft: <stdin 第一行>`。
+//!
+//! 脚本查找顺序：环境变量 `MOCK_CLAUDE_SCRIPT`，然后是当前目录下的
+//! `mock-claude.jsonl`，都不存在时使用内置的简单对话。
+//!
+//! 脚本每行原样输出到 stdout，`#` 开头的行是指令：
+//! - `#sleep <ms>`：暂停
+//! - `#stderr <text>`：写入 stderr
+//! - `#exit <code>`：立即以指定退出码退出
+//! - `#hang`：一直阻塞，直到被终止
+//!
+//! 输出行中的 `{{session_id}}`、`{{message}}`、`{{permission_mode}}`、`{{cwd}}` 会被替换。
+//! 环境变量 `MOCK_CLAUDE_DELAY_MS` 设置每行之间的默认延迟。
+//! 每次运行的参数会写入当前目录的 `mock-claude-args.json`，便于测试断言。
+
+use std::io::Write;
+use std::time::Duration;
+
+const DEFAULT_SCRIPT: &str = r#"{"type":"system","subtype":"init","cwd":"{{cwd}}","session_id":"{{session_id}}","tools":["Bash","Read"],"model":"mock-model","permissionMode":"{{permission_mode}}"}
+{"type":"assistant","message":{"id":"msg_mock","type":"message","role":"assistant","model":"mock-model","content":[{"type":"text","text":"echo: {{message}}"}],"stop_reason":null},"parent_tool_use_id":null,"session_id":"{{session_id}}"}
+{"type":"result","subtype":"success","is_error":false,"duration_ms":1,"num_turns":1,"result":"echo: {{message}}","session_id":"{{session_id}}"}"#;
+
+struct Args {
+    print: bool,
+    output_format: Option<String>,
+    permission_mode: String,
+    resume: Option<String>,
+    message: Option<String>,
+    raw: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+    let mut args = Args {
+        print: false,
+        output_format: None,
+        permission_mode: "default".to_string(),
+        resume: None,
+        message: None,
+        raw: raw.clone(),
+    };
+
+    let mut iter = raw.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--version" | "-v" => {
+                println!("0.0.0 (Mock Claude Code)");
+                std::process::exit(0);
+            }
+            "--print" | "-p" => args.print = true,
+            "--verbose" | "--include-partial-messages" | "--strict-mcp-config" => {}
+            "--output-format" => args.output_format = iter.next(),
+            "--permission-mode" => {
+                args.permission_mode = iter.next().ok_or("--permission-mode 缺少参数")?;
+            }
+            "--resume" | "-r" => args.resume = Some(iter.next().ok_or("--resume 缺少参数")?),
+            "--max-turns" | "--allowedTools" | "--disallowedTools" | "--tools" => {
+                iter.next().ok_or(format!("{} 缺少参数", arg))?;
+            }
+            other if other.starts_with("--") => return Err(format!("未知参数: {}", other)),
+            _ => args.message = Some(arg),
+        }
+    }
+
+    if !args.print {
+        return Err("mock-claude 只支持 --print 模式".to_string());
+    }
+    if !matches!(args.output_format.as_deref(), None | Some("stream-json")) {
+        return Err("mock-claude 只支持文本或 --output-format stream-json".to_string());
+    }
+
+    Ok(args)
+}
+
+fn load_script() -> String {
+    std::env::var("MOCK_CLAUDE_SCRIPT")
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .or_else(|| std::fs::read_to_string("mock-claude.jsonl").ok())
+        .unwrap_or_else(|| DEFAULT_SCRIPT.to_string())
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let _ = std::fs::write(
+        "mock-claude-args.json",
+        serde_json::to_string(&args.raw).unwrap_or_default(),
+    );
+
+    if args.output_format.is_none() {
+        let mut input = String::new();
+        let _ = std::io::Read::read_to_string(&mut std::io::stdin(), &mut input);
+        println!("draft: {}", input.lines().next().unwrap_or(""));
+        return;
+    }
+
+    let session_id = args
+        .resume
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let cwd = std::env::current_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let delay = std::env::var("MOCK_CLAUDE_DELAY_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_millis);
+
+    // 替换值需转义，保证输出仍是合法 JSON
+    let escape = |s: &str| {
+        let quoted = serde_json::to_string(s).unwrap_or_default();
+        quoted[1..quoted.len() - 1].to_string()
+    };
+    let message = escape(args.message.as_deref().unwrap_or(""));
+    let cwd = escape(&cwd);
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+
+    for line in load_script().lines() {
+        if let Some(directive) = line.strip_prefix('#') {
+            let (name, arg) = directive.split_once(' ').unwrap_or((directive, ""));
+            match name {
+                "sleep" => std::thread::sleep(Duration::from_millis(arg.trim().parse().unwrap_or(0))),
+                "stderr" => eprintln!("{}", arg),
+                "exit" => {
+                    let _ = out.flush();
+                    std::process::exit(arg.trim().parse().unwrap_or(1));
+                }
+                "hang" => loop {
+                    std::thread::sleep(Duration::from_secs(60));
+                },
+                _ => {}
+            }
+            continue;
+        }
+
+        if let Some(delay) = delay {
+            std::thread::sleep(delay);
+        }
+
+        let line = line
+            .replace("{{session_id}}", &session_id)
+            .replace("{{message}}", &message)
+            .replace("{{permission_mode}}", &args.permission_mode)
+            .replace("{{cwd}}", &cwd);
+        let _ = writeln!(out, "{}", line);
+        let _ = out.flush();
+    }
+}
@@ -7,6 +7,7 @@ import { TopMenuBar as TopMenuBarComponent } from './components/TopMenuBar';
 import { CreateWorkspaceModal } from './components/Workspace';
 import { useConfigStore, useChatStore, useViewStore } from './stores';
 import { useChatEvent } from './hooks';
+import { listStaleSessions, cleanupStaleSessions } from './services/tauri';
 import './index.css';
 
 function App() {
@@ -40,6 +41,20 @@ function App() {
     refreshHealth();
   }, []);
 
+  // 检查上次异常退出遗留的 Claude 进程
+  useEffect(() => {
+    listStaleSessions()
+      .then((stale) => {
+        if (stale.length === 0) return;
+        const alive = stale.filter((s) => s.alive).length;
+        const kill = alive > 0 && window.confirm(
+          `检测到上次异常退出遗留的 ${alive} 个 Claude 进程，是否终止？`
+        );
+        return cleanupStaleSessions(kill);
+      })
+      .catch((e) => console.error('检查遗留会话失败:', e));
+  }, []);
+
   // 监听聊天流事件
   useChatEvent(handleStreamEvent);
 
@@ -2,7 +2,7 @@
  * 文件建议下拉组件 - 用于 @file 引用
  */
 
-import { useEffect, useRef } from 'react';
+import { useEffect, useRef, ReactNode } from 'react';
 import type { FileMatch } from '../../services/fileSearch';
 
 interface FileSuggestionProps {
@@ -21,6 +21,22 @@ function splitPath(relativePath: string): { dir: string; name: string } {
   return { dir, name };
 }
 
+// 按命中区间拆分文本，offset 为 text 在 relativePath 中的起始位置
+function renderHighlighted(text: string, offset: number, highlights: [number, number][]) {
+  const parts: ReactNode[] = [];
+  let cursor = 0;
+  for (const [start, end] of highlights) {
+    const from = Math.max(start - offset, cursor);
+    const to = Math.min(end - offset, text.length);
+    if (to <= from) continue;
+    if (from > cursor) parts.push(text.slice(cursor, from));
+    parts.push(<mark key={from} className="bg-transparent text-primary">{text.slice(from, to)}</mark>);
+    cursor = to;
+  }
+  if (cursor < text.length) parts.push(text.slice(cursor));
+  return parts;
+}
+
 export function FileSuggestion({
   files,
   selectedIndex,
@@ -87,12 +103,12 @@ export function FileSuggestion({
               {/* 目录路径（浅色） */}
               {dir && (
                 <span className="text-text-tertiary text-xs truncate" title={dir}>
-                  {dir}/
+                  {renderHighlighted(dir, 0, file.highlights)}/
                 </span>
               )}
               {/* 文件名（深色） */}
               <span className="font-medium truncate" title={name}>
-                {name}
+                {renderHighlighted(name, file.relativePath.length - name.length, file.highlights)}
               </span>
             </div>
 
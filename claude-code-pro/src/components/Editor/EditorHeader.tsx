@@ -45,6 +45,13 @@ export function EditorHeader({ className = '' }: EditorHeaderProps) {
           {currentFile.name}
           {isModified && <span className="text-warning ml-1">●</span>}
         </span>
+        {currentFile.encoding && (
+          <span className="text-xs text-text-tertiary shrink-0">
+            {currentFile.encoding}
+            {currentFile.lineEnding && currentFile.lineEnding !== 'none' && ` · ${currentFile.lineEnding.toUpperCase()}`}
+            {currentFile.readOnly && ' · 只读'}
+          </span>
+        )}
       </div>
 
       {/* 操作按钮 */}
@@ -46,6 +46,7 @@ export function EditorPanel({ className = '' }: EditorPanelProps) {
           language={currentFile.language}
           onChange={setContent}
           onSave={saveFile}
+          readOnly={currentFile.readOnly}
         />
       </div>
     </div>
@@ -2,6 +2,8 @@ import { useEffect, useCallback } from 'react';
 import { useFileExplorerStore, useWorkspaceStore, useCommandStore } from '../../stores';
 import { FileTree } from './FileTree';
 import { SearchBar } from './SearchBar';
+import { useFsChanged } from '../../hooks';
+import type { FsChangeBatch } from '../../types';
 
 export function FileExplorer() {
   const {
@@ -11,6 +13,7 @@ export function FileExplorer() {
     error,
     load_directory,
     refresh_directory,
+    apply_fs_changes,
     clear_error
   } = useFileExplorerStore();
 
@@ -40,6 +43,12 @@ export function FileExplorer() {
     };
   }, [load_directory, getCurrentWorkspace]);
 
+  // 工作区文件被外部修改（Claude、git checkout 等）时更新文件树
+  const handleFsChanged = useCallback((batch: FsChangeBatch) => {
+    apply_fs_changes(batch.changes);
+  }, [apply_fs_changes]);
+  useFsChanged(handleFsChanged);
+
   // 快捷键支持
   useEffect(() => {
     const handleKeyDown = (event: KeyboardEvent) => {
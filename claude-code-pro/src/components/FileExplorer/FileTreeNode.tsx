@@ -102,9 +102,9 @@ export const FileTreeNode = memo<FileTreeNodeProps>(({
           className="mr-2 w-4 h-4 flex-shrink-0"
         />
 
-        {/* 文件名 */}
+        {/* 文件名（被 .gitignore 等规则忽略的条目淡化显示） */}
         <span
-          className="text-sm text-text-primary truncate flex-1 min-w-0"
+          className={`text-sm truncate flex-1 min-w-0 ${file.ignored ? 'text-text-tertiary' : 'text-text-primary'}`}
           title={file.name}
         >
           {file.name}
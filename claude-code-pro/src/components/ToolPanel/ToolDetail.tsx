@@ -2,6 +2,7 @@
  * 工具详情组件
  */
 
+import { useState } from 'react';
 import { useToolPanelStore } from '../../stores';
 import type { ToolCall } from '../../types';
 import { clsx } from 'clsx';
@@ -77,7 +78,22 @@ function getStatusInfo(status: ToolCall['status']) {
 
 export function ToolDetail({ toolId, onBack }: ToolDetailProps) {
   const tools = useToolPanelStore((state) => state.tools);
+  const loadFullOutput = useToolPanelStore((state) => state.loadFullOutput);
   const tool = tools.find(t => t.id === toolId);
+  const [loadError, setLoadError] = useState<string | null>(null);
+  const [loading, setLoading] = useState(false);
+
+  const handleLoadFull = async () => {
+    setLoading(true);
+    setLoadError(null);
+    try {
+      await loadFullOutput(toolId);
+    } catch (e) {
+      setLoadError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setLoading(false);
+    }
+  };
 
   if (!tool) {
     return (
@@ -176,6 +192,19 @@ export function ToolDetail({ toolId, onBack }: ToolDetailProps) {
             )}>
               {tool.output}
             </pre>
+            {tool.truncated && (
+              <div className="flex items-center gap-2 mt-2 text-xs text-warning">
+                <span>输出过大，已截断显示</span>
+                <button
+                  onClick={handleLoadFull}
+                  disabled={loading}
+                  className="text-primary hover:underline disabled:opacity-50"
+                >
+                  {loading ? '加载中...' : '加载完整内容'}
+                </button>
+                {loadError && <span className="text-danger">{loadError}</span>}
+              </div>
+            )}
           </div>
         )}
 
@@ -3,3 +3,5 @@
  */
 
 export { useChatEvent } from './useChat';
+export { useFsChanged } from './useFsChanged';
+export { useContentSearchEvents } from './useContentSearch';
@@ -4,21 +4,38 @@
 
 import { useEffect } from 'react';
 import { listen } from '@tauri-apps/api/event';
-import type { StreamEvent } from '../types';
+import * as tauri from '../services/tauri';
+import type { EventBatch, EventFrame, StreamEvent, TruncatedRef } from '../types';
+
+/** 截断后仍过大的事件只推送占位内容，需要取回完整事件 */
+async function resolveFrame(sessionId: string, frame: EventFrame): Promise<StreamEvent> {
+  if (frame.truncated && 'omitted_bytes' in frame.event) {
+    return tauri.fetchEventPayload(sessionId, frame.seq);
+  }
+  return frame.event;
+}
 
 /** 监听聊天流式事件 */
 export function useChatEvent(
-  onEvent: (event: StreamEvent) => void,
+  onEvent: (event: StreamEvent, truncated?: TruncatedRef) => void,
   onError?: (error: string) => void
 ) {
   useEffect(() => {
-    const unlistenPromise = listen<string>('chat-event', (event) => {
-      try {
-        const data = JSON.parse(event.payload) as StreamEvent;
-        onEvent(data);
-      } catch (e) {
-        console.error('Failed to parse chat event:', e);
-        onError?.(e instanceof Error ? e.message : '解析事件失败');
+    // 后端按帧批量推送，超大事件已截断，可通过 fetchEventPayload 获取完整内容；
+    // 占位事件需要先取回，按顺序串行处理以保持事件顺序
+    let pending = Promise.resolve();
+    const unlistenPromise = listen<EventBatch>('chat-events', (event) => {
+      const { sessionId, frames } = event.payload;
+      for (const frame of frames) {
+        pending = pending.then(async () => {
+          try {
+            const streamEvent = await resolveFrame(sessionId, frame);
+            onEvent(streamEvent, frame.truncated ? { sessionId, seq: frame.seq } : undefined);
+          } catch (e) {
+            console.error('Failed to handle chat event:', e);
+            onError?.(e instanceof Error ? e.message : '处理事件失败');
+          }
+        });
       }
     });
 
@@ -0,0 +1,20 @@
+/**
+ * 内容搜索事件 Hook
+ */
+
+import { useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import type { ContentSearchEvent } from '../types';
+
+/** 监听后端分批推送的内容搜索结果（按 searchId 区分不同的搜索） */
+export function useContentSearchEvents(onEvent: (event: ContentSearchEvent) => void) {
+  useEffect(() => {
+    const unlistenPromise = listen<ContentSearchEvent>('content-search', (event) => {
+      onEvent(event.payload);
+    });
+
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, [onEvent]);
+}
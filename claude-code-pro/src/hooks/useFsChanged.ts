@@ -0,0 +1,20 @@
+/**
+ * 文件系统变更 Hook
+ */
+
+import { useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import type { FsChangeBatch } from '../types';
+
+/** 监听后端推送的工作区文件变更（已防抖并过滤 .gitignore 忽略的路径） */
+export function useFsChanged(onBatch: (batch: FsChangeBatch) => void) {
+  useEffect(() => {
+    const unlistenPromise = listen<FsChangeBatch>('fs-changed', (event) => {
+      onBatch(event.payload);
+    });
+
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, [onBatch]);
+}
@@ -10,6 +10,8 @@ export interface FileMatch {
   fullPath: string;      // 完整路径
   is_dir: boolean;
   extension?: string;
+  score: number;                    // 匹配得分（越高越靠前）
+  highlights: [number, number][];   // relativePath 中命中的区间 [start, end)
 }
 
 /**
@@ -18,9 +20,12 @@ export interface FileMatch {
  * @param workDir 工作目录
  * @param maxResults 最大结果数
  *
+ * 模糊匹配（字符按顺序出现即可），结果已按得分排序
+ *
  * 示例:
- * - "app" -> 搜索所有包含 "app" 的文件
- * - "src/app" -> 在 src 目录下搜索包含 "app" 的文件
+ * - "app" -> 文件名匹配 "app" 的文件优先，如 App.tsx
+ * - "src/app" -> 路径中依次包含 "src/" 和 "app" 的文件
+ * - "fst" -> 匹配 fileSearch.ts（分段边界和驼峰处的字符优先）
  */
 export async function searchFiles(
   query: string,
@@ -44,6 +49,8 @@ export async function searchFiles(
       fullPath: r.fullPath,
       is_dir: r.is_dir,
       extension: r.extension,
+      score: r.score,
+      highlights: r.highlights ?? [],
     }));
   } catch (error) {
     console.error('Failed to search files:', error);
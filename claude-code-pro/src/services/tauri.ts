@@ -3,7 +3,35 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
-import type { Config, HealthStatus } from '../types';
+import type {
+  BranchInfo,
+  CommitInfo,
+  CommitPage,
+  Config,
+  ContentSearchOptions,
+  DeliveryMetrics,
+  FileBlame,
+  FileContent,
+  FilePreview,
+  FileDiff,
+  FileOp,
+  FileOpHistory,
+  FileVersion,
+  FileHistoryPage,
+  GitStatus,
+  HealthStatus,
+  MergeReport,
+  ReadRange,
+  ReplacePreviewResult,
+  ReplaceResult,
+  ReplaceTarget,
+  RunQueueStatus,
+  SessionWorktree,
+  StaleSession,
+  StashInfo,
+  StreamEvent,
+  TrashItem,
+} from '../types';
 
 // ============================================================================
 // 配置相关命令
@@ -47,14 +75,34 @@ export async function healthCheck(): Promise<HealthStatus> {
 // 聊天相关命令
 // ============================================================================
 
-/** 启动聊天会话 */
-export async function startChat(message: string): Promise<string> {
-  return invoke<string>('start_chat', { message });
+/** 启动聊天会话（达到并发上限时排队，priority 越大越先启动；isolated 时在独立工作树中运行） */
+export async function startChat(message: string, priority?: number, isolated?: boolean): Promise<string> {
+  return invoke<string>('start_chat', { message, priority, isolated });
 }
 
 /** 继续聊天会话 */
-export async function continueChat(sessionId: string, message: string): Promise<void> {
-  return invoke('continue_chat', { sessionId, message });
+export async function continueChat(sessionId: string, message: string, priority?: number): Promise<void> {
+  return invoke('continue_chat', { sessionId, message, priority });
+}
+
+/** 获取会话调度状态 */
+export async function getRunQueue(): Promise<RunQueueStatus> {
+  return invoke<RunQueueStatus>('get_run_queue');
+}
+
+/** 删除会话的后端状态（同时回收其工作树） */
+export async function deleteSession(sessionId: string): Promise<void> {
+  return invoke('delete_session', { sessionId });
+}
+
+/** 列出上次异常退出遗留的会话 */
+export async function listStaleSessions(): Promise<StaleSession[]> {
+  return invoke<StaleSession[]>('list_stale_sessions');
+}
+
+/** 清理遗留会话，kill 为 true 时终止仍在运行的进程 */
+export async function cleanupStaleSessions(kill: boolean): Promise<StaleSession[]> {
+  return invoke<StaleSession[]>('cleanup_stale_sessions', { kill });
 }
 
 /** 中断聊天 */
@@ -62,6 +110,184 @@ export async function interruptChat(sessionId: string): Promise<void> {
   return invoke('interrupt_chat', { sessionId });
 }
 
+/** 回放录制的会话，name 为录制 id 或文件名 */
+export async function replaySession(name: string, speed?: number): Promise<string> {
+  return invoke<string>('replay_session', { name, speed });
+}
+
+/** 获取会话的事件解析统计 */
+export async function getParseStats(sessionId: string) {
+  return invoke('get_parse_stats', { sessionId });
+}
+
+/** 获取被截断事件的完整内容 */
+export async function fetchEventPayload(sessionId: string, seq: number): Promise<StreamEvent> {
+  const json = await invoke<string>('fetch_event_payload', { sessionId, seq });
+  return JSON.parse(json) as StreamEvent;
+}
+
+/** 获取事件推送统计 */
+export async function getDeliveryMetrics(sessionId: string): Promise<DeliveryMetrics> {
+  return invoke<DeliveryMetrics>('get_delivery_metrics', { sessionId });
+}
+
+/** 列出已录制的会话 */
+export async function listRecordings() {
+  return invoke('list_recordings');
+}
+
+// ============================================================================
+// Bash 任务相关命令
+// ============================================================================
+
+/** 列出会话中的 Bash 任务 */
+export async function listShellJobs(sessionId: string) {
+  return invoke('list_shell_jobs', { sessionId });
+}
+
+// ============================================================================
+// 终端相关命令
+// ============================================================================
+
+/** 在当前工作目录创建终端 */
+export async function createTerminal(name?: string, cols?: number, rows?: number) {
+  return invoke('create_terminal', { name, cols, rows });
+}
+
+/** 写入终端输入 */
+export async function writeTerminal(terminalId: string, data: string): Promise<void> {
+  return invoke('write_terminal', { terminalId, data });
+}
+
+/** 调整终端尺寸 */
+export async function resizeTerminal(terminalId: string, cols: number, rows: number): Promise<void> {
+  return invoke('resize_terminal', { terminalId, cols, rows });
+}
+
+/** 关闭终端 */
+export async function closeTerminal(terminalId: string): Promise<void> {
+  return invoke('close_terminal', { terminalId });
+}
+
+/** 列出终端 */
+export async function listTerminals(workDir?: string) {
+  return invoke('list_terminals', { workDir });
+}
+
+// ============================================================================
+// Git 相关命令
+// ============================================================================
+
+/** 获取工作区 Git 状态 */
+export async function gitStatus(): Promise<GitStatus> {
+  return invoke<GitStatus>('git_status');
+}
+
+/** 获取差异，staged 为 true 时返回已暂存的差异 */
+export async function gitDiff(staged: boolean, path?: string): Promise<FileDiff[]> {
+  return invoke<FileDiff[]>('git_diff', { staged, path });
+}
+
+/** 列出分支 */
+export async function gitBranches(): Promise<BranchInfo[]> {
+  return invoke<BranchInfo[]>('git_branches');
+}
+
+/** 获取当前分支 */
+export async function gitCurrentBranch(): Promise<string | null> {
+  return invoke<string | null>('git_current_branch');
+}
+
+/** 获取提交历史 */
+export async function gitLog(skip?: number, limit?: number, path?: string): Promise<CommitPage> {
+  return invoke<CommitPage>('git_log', { skip, limit, path });
+}
+
+/** 逐行追溯文件（工作区版本） */
+export async function gitBlame(path: string): Promise<FileBlame> {
+  return invoke<FileBlame>('git_blame', { path });
+}
+
+/** 获取修改过文件的提交（跟随重命名） */
+export async function gitFileHistory(path: string, skip?: number, limit?: number): Promise<FileHistoryPage> {
+  return invoke<FileHistoryPage>('git_file_history', { path, skip, limit });
+}
+
+/** 暂存文件或目录 */
+export async function gitStage(paths: string[]): Promise<void> {
+  return invoke('git_stage', { paths });
+}
+
+/** 取消暂存文件或目录 */
+export async function gitUnstage(paths: string[]): Promise<void> {
+  return invoke('git_unstage', { paths });
+}
+
+/** 暂存单个差异块，hunkIndex 为 gitDiff(false, path) 中的序号 */
+export async function gitStageHunk(path: string, hunkIndex: number): Promise<void> {
+  return invoke('git_stage_hunk', { path, hunkIndex });
+}
+
+/** 取消暂存单个差异块，hunkIndex 为 gitDiff(true, path) 中的序号 */
+export async function gitUnstageHunk(path: string, hunkIndex: number): Promise<void> {
+  return invoke('git_unstage_hunk', { path, hunkIndex });
+}
+
+/** 提交已暂存的变更 */
+export async function gitCommit(message: string): Promise<CommitInfo> {
+  return invoke<CommitInfo>('git_commit', { message });
+}
+
+/** 创建分支 */
+export async function gitCreateBranch(name: string, checkout: boolean, startPoint?: string): Promise<void> {
+  return invoke('git_create_branch', { name, startPoint, checkout });
+}
+
+/** 切换分支 */
+export async function gitCheckoutBranch(name: string): Promise<void> {
+  return invoke('git_checkout_branch', { name });
+}
+
+/** 储藏本地修改 */
+export async function gitStashSave(message?: string, includeUntracked: boolean = false): Promise<string> {
+  return invoke<string>('git_stash_save', { message, includeUntracked });
+}
+
+/** 储藏列表 */
+export async function gitStashList(): Promise<StashInfo[]> {
+  return invoke<StashInfo[]>('git_stash_list');
+}
+
+/** 应用储藏，pop 为 true 时应用后删除 */
+export async function gitStashApply(index: number, pop: boolean = false): Promise<void> {
+  return invoke('git_stash_apply', { index, pop });
+}
+
+/** 删除储藏 */
+export async function gitStashDrop(index: number): Promise<void> {
+  return invoke('git_stash_drop', { index });
+}
+
+/** 列出会话工作树 */
+export async function listSessionWorktrees(): Promise<SessionWorktree[]> {
+  return invoke<SessionWorktree[]>('list_session_worktrees');
+}
+
+/** 将会话工作树的修改合并回主工作区 */
+export async function mergeSessionWorktree(sessionId: string, message?: string): Promise<MergeReport> {
+  return invoke<MergeReport>('merge_session_worktree', { sessionId, message });
+}
+
+/** 丢弃会话工作树及其分支 */
+export async function discardSessionWorktree(sessionId: string): Promise<boolean> {
+  return invoke<boolean>('discard_session_worktree', { sessionId });
+}
+
+/** 根据已暂存的差异生成提交说明（不会提交） */
+export async function draftCommitMessage(): Promise<string> {
+  return invoke<string>('draft_commit_message');
+}
+
 // ============================================================================
 // 工作区相关命令
 // ============================================================================
@@ -85,9 +311,32 @@ export async function readDirectory(path: string) {
   return invoke('read_directory', { path });
 }
 
-/** 获取文件内容 */
-export async function getFileContent(path: string) {
-  return invoke('get_file_content', { path });
+/**
+ * 获取文件内容及版本
+ *
+ * 自动检测编码和二进制文件；不传 range 时完整读取，超过 10MB 的文件需按范围读取。
+ */
+export async function getFileContent(path: string, range?: ReadRange): Promise<FileContent> {
+  return invoke<FileContent>('get_file_content', { path, range });
+}
+
+/** 获取图片预览（缩略图最长边不超过 maxDim，默认 256） */
+export async function getFilePreview(path: string, maxDim?: number): Promise<FilePreview> {
+  return invoke<FilePreview>('get_file_preview', { path, maxDim });
+}
+
+/**
+ * 保存文件（原子写入）
+ *
+ * 传入读取时的版本，磁盘上的文件已被修改时抛出 FileConflictError。
+ */
+export async function writeFile(path: string, content: string, expected?: Partial<FileVersion>): Promise<FileVersion> {
+  return invoke<FileVersion>('write_file', {
+    path,
+    content,
+    expectedHash: expected?.hash,
+    expectedMtime: expected?.mtime,
+  });
 }
 
 /** 创建文件 */
@@ -100,7 +349,7 @@ export async function createDirectory(path: string) {
   return invoke('create_directory', { path });
 }
 
-/** 删除文件或目录 */
+/** 删除文件或目录（移入回收站） */
 export async function deleteFile(path: string) {
   return invoke('delete_file', { path });
 }
@@ -115,6 +364,74 @@ export async function pathExists(path: string) {
   return invoke('path_exists', { path });
 }
 
+/** 获取当前工作区的撤销 / 重做栈 */
+export async function listFileOps(): Promise<FileOpHistory> {
+  return invoke<FileOpHistory>('list_file_ops');
+}
+
+/** 撤销最近的文件操作 */
+export async function undoFileOp(): Promise<FileOp | null> {
+  return invoke<FileOp | null>('undo_file_op');
+}
+
+/** 重做最近撤销的文件操作 */
+export async function redoFileOp(): Promise<FileOp | null> {
+  return invoke<FileOp | null>('redo_file_op');
+}
+
+// ============================================================================
+// 内容搜索相关命令
+// ============================================================================
+
+/** 在工作目录中搜索文件内容，返回搜索 ID，结果通过 content-search 事件推送 */
+export async function searchContent(query: string, options?: ContentSearchOptions): Promise<string> {
+  return invoke<string>('search_content', { query, options });
+}
+
+/** 取消内容搜索，返回搜索是否仍在进行 */
+export async function cancelContentSearch(searchId: string): Promise<boolean> {
+  return invoke<boolean>('cancel_content_search', { searchId });
+}
+
+/** 预览替换（不写入），正则模式下 replacement 支持 $1、${name} */
+export async function previewReplace(
+  query: string,
+  replacement: string,
+  targets: ReplaceTarget[],
+  options?: ContentSearchOptions
+): Promise<ReplacePreviewResult> {
+  return invoke<ReplacePreviewResult>('preview_replace', { query, replacement, options, targets });
+}
+
+/** 应用替换（targets 需带预览返回的 expectedHash），整体记为一次可撤销的操作 */
+export async function applyReplace(
+  query: string,
+  replacement: string,
+  targets: ReplaceTarget[],
+  options?: ContentSearchOptions
+): Promise<ReplaceResult> {
+  return invoke<ReplaceResult>('apply_replace', { query, replacement, options, targets });
+}
+
+// ============================================================================
+// 回收站相关命令
+// ============================================================================
+
+/** 列出回收站条目 */
+export async function listTrash(): Promise<TrashItem[]> {
+  return invoke<TrashItem[]>('list_trash');
+}
+
+/** 恢复回收站条目到原路径 */
+export async function restoreFromTrash(id: string): Promise<TrashItem> {
+  return invoke<TrashItem>('restore_from_trash', { id });
+}
+
+/** 永久删除回收站条目，不传 ids 时清空回收站 */
+export async function emptyTrash(ids?: string[]): Promise<number> {
+  return invoke<number>('empty_trash', { ids });
+}
+
 // ============================================================================
 // 日志相关命令
 // ============================================================================
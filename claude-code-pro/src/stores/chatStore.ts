@@ -3,7 +3,7 @@
  */
 
 import { create } from 'zustand';
-import type { Message, PermissionRequest, StreamEvent } from '../types';
+import type { Message, PermissionRequest, StreamEvent, TruncatedRef } from '../types';
 import * as tauri from '../services/tauri';
 import { useToolPanelStore } from './toolPanelStore';
 
@@ -26,6 +26,8 @@ interface ChatState {
   isStreaming: boolean;
   /** 当前正在输入的内容（用于流式追加） */
   currentContent: string;
+  /** 当前消息的文本是否已通过增量收到（避免完整消息重复追加） */
+  hasStreamedText: boolean;
   /** 待处理的权限请求 */
   pendingPermission: PermissionRequest | null;
   /** 错误 */
@@ -49,8 +51,8 @@ interface ChatState {
   setPermissionRequest: (request: PermissionRequest | null) => void;
   /** 设置错误 */
   setError: (error: string | null) => void;
-  /** 处理流事件 */
-  handleStreamEvent: (event: StreamEvent) => void;
+  /** 处理流事件，truncated 表示事件内容已被截断 */
+  handleStreamEvent: (event: StreamEvent, truncated?: TruncatedRef) => void;
 
   /** 发送消息 */
   sendMessage: (content: string) => Promise<void>;
@@ -73,6 +75,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
   conversationId: null,
   isStreaming: false,
   currentContent: '',
+  hasStreamedText: false,
   pendingPermission: null,
   error: null,
   maxMessages: MAX_MESSAGES,
@@ -105,6 +108,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
       archivedMessages: [],
       isArchiveExpanded: false,
       currentContent: '',
+      hasStreamedText: false,
       conversationId: null
     });
     // 同时清空工具面板
@@ -162,7 +166,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
     set({ error });
   },
 
-  handleStreamEvent: (event) => {
+  handleStreamEvent: (event, truncated) => {
     const state = get();
     const toolPanelStore = useToolPanelStore.getState();
 
@@ -182,7 +186,10 @@ export const useChatStore = create<ChatState>((set, get) => ({
           .map((item) => item.text)
           .join('');
 
-        if (textContent) {
+        // 文本已通过 text_delta 收到时只做对齐，不重复追加
+        if (state.hasStreamedText) {
+          set({ hasStreamedText: false });
+        } else if (textContent) {
           set((state) => ({
             currentContent: state.currentContent + textContent
           }));
@@ -224,6 +231,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
             toolPanelStore.updateTool(result.tool_use_id, {
               status: result.is_error ? 'failed' : 'completed',
               output: result.content || '',
+              truncated,
               completedAt: new Date().toISOString(),
             });
           }
@@ -240,10 +248,28 @@ export const useChatStore = create<ChatState>((set, get) => ({
 
       case 'text_delta':
         set((state) => ({
-          currentContent: state.currentContent + event.text
+          currentContent: state.currentContent + event.text,
+          hasStreamedText: true
         }));
         break;
 
+      case 'thinking_delta':
+      case 'tool_input_delta':
+      case 'message_delta':
+        // 思考过程与工具输入暂不在消息中显示，完整内容随 assistant 事件到达
+        break;
+
+      case 'queued':
+        console.log('[handleStreamEvent] 会话排队中，位置:', event.position);
+        break;
+
+      case 'dequeued':
+        // 启动前被取消的会话不会再有后续事件
+        if (event.cancelled) {
+          set({ isStreaming: false });
+        }
+        break;
+
       case 'result':
       case 'session_end':
         // 会话结束，完成消息
@@ -311,7 +337,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
     get().addMessage(userMessage);
 
     // 清空当前内容，开始流式传输
-    set({ currentContent: '', isStreaming: true, error: null });
+    set({ currentContent: '', hasStreamedText: false, isStreaming: true, error: null });
 
     // 清空工具面板
     useToolPanelStore.getState().clearTools();
@@ -339,7 +365,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
       return;
     }
 
-    set({ isStreaming: true, error: null, currentContent: '' });
+    set({ isStreaming: true, error: null, currentContent: '', hasStreamedText: false });
 
     try {
       await tauri.continueChat(conversationId, '');
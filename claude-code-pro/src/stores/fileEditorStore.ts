@@ -4,6 +4,7 @@
 
 import { create } from 'zustand';
 import type { FileEditorStore } from '../types';
+import { isFileConflict } from '../types';
 import * as tauri from '../services/tauri';
 
 /** 根据文件扩展名获取语言类型 */
@@ -62,9 +63,12 @@ export const useFileEditorStore = create<FileEditorStore>((set, get) => ({
     set({ isOpen: true, status: 'loading', error: null });
 
     try {
-      const content = await tauri.getFileContent(path) as string;
-      console.log('[Editor] 文件内容长度:', content?.length);
-      const language = getLanguageFromPath(path);
+      const file = await tauri.getFileContent(path);
+      const { hash, mtime, encoding, lineEnding } = file;
+      // 二进制文件显示十六进制预览
+      const content = file.binary ? file.hexPreview ?? '' : file.content;
+      console.log('[Editor] 文件内容长度:', content.length, encoding);
+      const language = file.binary ? 'text' : getLanguageFromPath(path);
 
       set({
         isOpen: true,
@@ -75,6 +79,10 @@ export const useFileEditorStore = create<FileEditorStore>((set, get) => ({
           originalContent: content,
           isModified: false,
           language,
+          version: { hash, mtime },
+          readOnly: file.partial || file.binary,
+          encoding,
+          lineEnding,
         },
         status: 'idle',
         error: null,
@@ -121,13 +129,13 @@ export const useFileEditorStore = create<FileEditorStore>((set, get) => ({
   // 保存文件
   saveFile: async () => {
     const { currentFile } = get();
-    if (!currentFile) return;
+    if (!currentFile || currentFile.readOnly) return;
 
     set({ status: 'saving', error: null });
 
     try {
-      // 先写入文件
-      await tauri.createFile(currentFile.path, currentFile.content);
+      // 先写入文件（磁盘上的文件被外部修改时会冲突，不会覆盖）
+      const version = await tauri.writeFile(currentFile.path, currentFile.content, currentFile.version);
 
       // 更新状态
       set({
@@ -135,12 +143,15 @@ export const useFileEditorStore = create<FileEditorStore>((set, get) => ({
           ...currentFile,
           originalContent: currentFile.content,
           isModified: false,
+          version,
         },
         status: 'idle',
         error: null,
       });
     } catch (error) {
-      const errorMessage = error instanceof Error ? error.message : '保存文件失败';
+      const errorMessage = isFileConflict(error)
+        ? '文件已在磁盘上被修改，请重新打开后再保存'
+        : error instanceof Error ? error.message : '保存文件失败';
       set({
         status: 'error',
         error: errorMessage,
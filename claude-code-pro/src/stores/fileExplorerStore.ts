@@ -3,7 +3,7 @@
  */
 
 import { create } from 'zustand';
-import type { FileExplorerStore, FileInfo } from '../types';
+import type { FileExplorerStore, FileInfo, FsChange } from '../types';
 import * as tauri from '../services/tauri';
 
 // 搜索取消令牌（用于取消正在进行的搜索）
@@ -27,6 +27,16 @@ function updateFolderChildren(tree: FileInfo[], folderPath: string, children: Fi
   });
 }
 
+// 辅助函数：获取父目录路径
+function parentPath(path: string): string {
+  return path.replace(/[\\/][^\\/]*$/, '');
+}
+
+// 辅助函数：路径是否为 base 本身或位于其下
+function isWithin(path: string, base: string): boolean {
+  return path === base || path.startsWith(base + '/') || path.startsWith(base + '\\');
+}
+
 // 辅助函数：递归过滤文件树
 function filterFiles(files: FileInfo[], query: string): FileInfo[] {
   if (!query.trim()) return files;
@@ -197,6 +207,59 @@ export const useFileExplorerStore = create<FileExplorerStore>((set, get) => ({
     }
   },
 
+  // 应用文件系统变更
+  apply_fs_changes: async (changes: FsChange[]) => {
+    const { current_path } = get();
+    if (!current_path || changes.length === 0) {
+      return;
+    }
+
+    // 受影响的父目录，以及已被删除或移走的路径
+    const parents = new Set<string>();
+    const gone: string[] = [];
+    for (const change of changes) {
+      parents.add(parentPath(change.path));
+      if (change.oldPath) {
+        parents.add(parentPath(change.oldPath));
+        gone.push(change.oldPath);
+      }
+      if (change.kind === 'removed') {
+        gone.push(change.path);
+      }
+    }
+
+    // 丢弃已不存在目录的缓存
+    const cache = new Map(get().folder_cache);
+    for (const key of [...cache.keys()]) {
+      if (gone.some((path) => isWithin(key, path))) {
+        cache.delete(key);
+      }
+    }
+
+    try {
+      let tree = get().file_tree;
+      if (parents.has(current_path)) {
+        tree = await tauri.readDirectory(current_path) as FileInfo[];
+      }
+      for (const parent of parents) {
+        if (parent !== current_path && cache.has(parent)) {
+          cache.set(parent, await tauri.readDirectory(parent) as FileInfo[]);
+        }
+      }
+      // 重新挂载已加载的子目录
+      for (const [folder, children] of cache) {
+        tree = updateFolderChildren(tree, folder, children);
+      }
+
+      // 期间切换了工作区时丢弃结果
+      if (get().current_path === current_path) {
+        set({ file_tree: tree, folder_cache: cache });
+      }
+    } catch (error) {
+      console.error('[FileExplorer] 应用文件变更失败:', error);
+    }
+  },
+
   // 选择文件
   select_file: (file: FileInfo) => {
     set({ selected_file: file });
@@ -388,7 +451,8 @@ export const useFileExplorerStore = create<FileExplorerStore>((set, get) => ({
   // 获取文件内容
   get_file_content: async (path: string) => {
     try {
-      return await tauri.getFileContent(path) as string;
+      const { content } = await tauri.getFileContent(path);
+      return content;
     } catch (error) {
       set({ error: error instanceof Error ? error.message : '读取文件内容失败' });
       throw error;
@@ -4,6 +4,7 @@
 
 import { create } from 'zustand';
 import type { ToolCall } from '../types';
+import * as tauri from '../services/tauri';
 
 interface ToolPanelState {
   /** 面板是否展开 */
@@ -25,9 +26,11 @@ interface ToolPanelState {
   updateTool: (id: string, updates: Partial<ToolCall>) => void;
   /** 清空工具列表 */
   clearTools: () => void;
+  /** 加载被截断的完整输出 */
+  loadFullOutput: (id: string) => Promise<void>;
 }
 
-export const useToolPanelStore = create<ToolPanelState>((set) => ({
+export const useToolPanelStore = create<ToolPanelState>((set, get) => ({
   isOpen: true,  // 默认展开
   selectedToolId: null,
   tools: [],
@@ -70,6 +73,19 @@ export const useToolPanelStore = create<ToolPanelState>((set) => ({
   clearTools: () => {
     set({ tools: [], selectedToolId: null });
   },
+
+  loadFullOutput: async (id) => {
+    const tool = get().tools.find(t => t.id === id);
+    if (!tool?.truncated) return;
+
+    const event = await tauri.fetchEventPayload(tool.truncated.sessionId, tool.truncated.seq);
+    if (event.type !== 'user') return;
+
+    const result = event.message.content.find(
+      (item) => item.type === 'tool_result' && item.tool_use_id === id
+    );
+    get().updateTool(id, { output: result?.content || '', truncated: undefined });
+  },
 }));
 
 /** 根据名称查找并更新工具（用于 tool_end 事件） */
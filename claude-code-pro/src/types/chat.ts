@@ -15,10 +15,18 @@ export interface ToolCall {
   status: ToolStatus;
   input?: Record<string, unknown>;
   output?: string;
+  /** 输出在推送时被截断，可按序号获取完整内容 */
+  truncated?: TruncatedRef;
   startedAt: string;
   completedAt?: string;
 }
 
+/** 被截断事件的引用（用于 fetchEventPayload） */
+export interface TruncatedRef {
+  sessionId: string;
+  seq: number;
+}
+
 /** 聊天消息 */
 export interface Message {
   id: string;
@@ -84,10 +92,72 @@ export type StreamEvent =
   | { type: 'assistant'; message: AssistantMessage }
   | { type: 'user'; message: UserMessage }
   | { type: 'session_start'; sessionId: string }
+  | { type: 'stream_event'; event: Record<string, unknown>; [key: string]: unknown }
   | { type: 'text_delta'; text: string }
+  | { type: 'thinking_delta'; text: string }
+  | { type: 'tool_input_delta'; tool_use_id: string; tool_name: string; partial_json: string }
+  | { type: 'message_delta'; stop_reason: string | null; usage: unknown }
   | { type: 'tool_start'; toolName: string; input: Record<string, unknown> }
   | { type: 'tool_end'; toolName: string; output?: string }
   | { type: 'permission_request'; sessionId: string; denials: PermissionDenial[] }
   | { type: 'result'; subtype: string; [key: string]: unknown }
   | { type: 'error'; error: string }
-  | { type: 'session_end' };
+  | { type: 'session_end' }
+  | { type: 'queued'; session_id: string; position: number }
+  | { type: 'dequeued'; session_id: string; cancelled: boolean }
+  | { type: 'unknown'; raw: Record<string, unknown> }
+  | { type: 'parse_error'; line: string; error: string };
+
+/** 事件帧 */
+export interface EventFrame {
+  seq: number;
+  event: StreamEvent;
+  /** 内容过大已截断 */
+  truncated: boolean;
+}
+
+/** 批量推送的事件（chat-events） */
+export interface EventBatch {
+  sessionId: string;
+  frames: EventFrame[];
+}
+
+/** 事件推送统计 */
+export interface DeliveryMetrics {
+  framesSent: number;
+  batchesSent: number;
+  framesDropped: number;
+  framesDelayed: number;
+  framesTruncated: number;
+  backpressureWaits: number;
+  maxLatencyMs: number;
+  maxBatchSize: number;
+}
+
+/** 调度中的会话 */
+export interface RunInfo {
+  sessionId: string;
+  workspace: string;
+  priority: number;
+  submittedAt: string;
+}
+
+/** 会话调度状态 */
+export interface RunQueueStatus {
+  limits: { maxConcurrent: number; maxPerWorkspace: number };
+  running: RunInfo[];
+  queued: RunInfo[];
+}
+
+/** 上次异常退出遗留的会话 */
+export interface StaleSession {
+  sessionId: string;
+  pid: number;
+  appPid: number;
+  workDir?: string | null;
+  startedAt: string;
+  /** Claude 进程的启动时间，用于确认 PID 没有被复用 */
+  processStarted?: string | null;
+  /** Claude 进程是否仍在运行（PID 存在且启动时间一致） */
+  alive: boolean;
+}
@@ -19,6 +19,20 @@ export interface Config {
   gitBinPath?: string;
   /** 是否启用日志 */
   enableLogging?: boolean;
+  /** 是否录制会话原始输出 */
+  recordSessions?: boolean;
+  /** 同时运行的会话上限（0 表示不限制） */
+  maxConcurrentSessions?: number;
+  /** 每个工作区同时运行的会话上限（0 表示不限制） */
+  maxSessionsPerWorkspace?: number;
+  /** 文件浏览器除工作目录外还允许访问的目录 */
+  extraAllowedDirs?: string[];
+  /** 回收站条目保留天数（0 表示不按时间清理） */
+  trashMaxAgeDays?: number;
+  /** 回收站总大小上限（字节，0 表示不限制） */
+  trashMaxBytes?: number;
+  /** 文件搜索和列表额外排除的模式（gitignore 语法） */
+  fileExcludes?: string[];
 }
 
 /** 健康状态 */
@@ -19,6 +19,85 @@ export interface OpenFile {
   isModified: boolean;
   /** 语言类型（用于语法高亮） */
   language: string;
+  /** 读取或上次保存时的磁盘版本（用于检测外部修改） */
+  version?: FileVersion;
+  /** 只读（二进制文件或只读取了部分内容） */
+  readOnly?: boolean;
+  /** 检测到的编码 */
+  encoding?: string;
+  /** 换行符风格 */
+  lineEnding?: LineEnding;
+}
+
+/** 文件在磁盘上的版本 */
+export interface FileVersion {
+  /** 内容的 SHA-256 */
+  hash: string;
+  /** 修改时间（Unix 毫秒） */
+  mtime: number;
+}
+
+/** 换行符风格（mixed 表示同时包含 LF 和 CRLF） */
+export type LineEnding = 'lf' | 'crlf' | 'mixed' | 'none';
+
+/** 读取范围：字节范围会对齐到字符边界并在最后一个换行处截断；行号从 1 开始，含两端 */
+export type ReadRange =
+  | { type: 'bytes'; offset: number; length: number }
+  | { type: 'lines'; start: number; end?: number | null };
+
+/** 文件内容及其版本（超过 10MB 的文件按范围读取时 hash 为空） */
+export interface FileContent extends FileVersion {
+  /** 解码后的文本（二进制文件为空） */
+  content: string;
+  /** 检测到的编码（UTF-8、UTF-16LE、GBK、Shift_JIS 等） */
+  encoding: string;
+  /** 是否带 BOM */
+  bom: boolean;
+  lineEnding: LineEnding;
+  binary: boolean;
+  /** 二进制文件的十六进制预览（最多 4KB） */
+  hexPreview: string | null;
+  /** 文件大小（字节） */
+  size: number;
+  /** 返回内容在文件中的字节范围 */
+  offset: number;
+  length: number;
+  /** 按行读取时实际返回的行范围 */
+  startLine: number | null;
+  endLine: number | null;
+  /** 总行数（按字节范围读取时未知） */
+  totalLines: number | null;
+  /** 只包含文件的一部分，不能直接保存 */
+  partial: boolean;
+}
+
+/** 图片预览（PNG/JPEG/GIF/WebP/SVG） */
+export interface FilePreview {
+  /** MIME 类型 */
+  mimeType: string;
+  /** 原图尺寸（像素） */
+  width: number;
+  height: number;
+  /** 缩略图的 data URL（格式不一定与原图相同） */
+  thumbnail: string;
+  /** 缩略图尺寸（SVG 为建议的显示尺寸） */
+  thumbnailWidth: number;
+  thumbnailHeight: number;
+  /** 文件大小（字节） */
+  size: number;
+}
+
+/** 保存冲突：磁盘上的文件已被修改（current 为当前版本，文件已删除时为空） */
+export interface FileConflictError {
+  kind: 'conflict';
+  message: string;
+  path: string;
+  current: FileContent | null;
+}
+
+/** 判断错误是否为保存冲突 */
+export function isFileConflict(error: unknown): error is FileConflictError {
+  return typeof error === 'object' && error !== null && (error as FileConflictError).kind === 'conflict';
 }
 
 /** 文件编辑器状态 */
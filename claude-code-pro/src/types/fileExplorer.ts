@@ -17,6 +17,8 @@ export interface FileInfo {
   extension?: string;
   /** 子文件列表（目录） */
   children?: FileInfo[];
+  /** 是否被 .gitignore 等规则或排除列表忽略 */
+  ignored?: boolean;
   /** 文件类型 */
   file_type?: FileType;
 }
@@ -96,6 +98,8 @@ export interface FileExplorerActions {
   rename_file: (old_path: string, new_name: string) => Promise<void>;
   /** 获取文件内容 */
   get_file_content: (path: string) => Promise<string>;
+  /** 应用后端推送的文件系统变更（只重新加载受影响的已加载目录） */
+  apply_fs_changes: (changes: FsChange[]) => Promise<void>;
   /** 清除错误 */
   clear_error: () => void;
 }
@@ -107,4 +111,56 @@ export interface FileSystemEvent {
   kind: string;
   /** 受影响的文件路径列表 */
   paths: string[];
-}
\ No newline at end of file
+}
+/** 回收站条目 */
+export interface TrashItem {
+  id: string;
+  /** 文件或目录名 */
+  name: string;
+  /** 删除前的完整路径 */
+  originalPath: string;
+  isDir: boolean;
+  /** 占用大小（字节） */
+  size: number;
+  /** 删除时间（RFC 3339） */
+  deletedAt: string;
+}
+
+/** 可撤销的文件操作 */
+export type FileOpKind =
+  | { type: 'create'; path: string; isDir: boolean; trashId?: string | null }
+  | { type: 'write'; path: string }
+  | { type: 'rename'; from: string; to: string }
+  | { type: 'delete'; path: string; trashId: string }
+  | { type: 'replace'; paths: string[] };
+
+/** 操作日志中的一条记录 */
+export interface FileOp {
+  id: string;
+  op: FileOpKind;
+  createdAt: string;
+}
+
+/** 工作区的撤销 / 重做栈（末尾为最近的操作） */
+export interface FileOpHistory {
+  undo: FileOp[];
+  redo: FileOp[];
+}
+
+/** 文件系统变更类型 */
+export type FsChangeKind = 'created' | 'modified' | 'removed' | 'renamed';
+
+/** 单个文件系统变更 */
+export interface FsChange {
+  kind: FsChangeKind;
+  path: string;
+  /** 重命名前的路径 */
+  oldPath?: string | null;
+}
+
+/** 后端推送的变更批次（fs-changed 事件） */
+export interface FsChangeBatch {
+  /** 被监听的工作目录 */
+  root: string;
+  changes: FsChange[];
+}
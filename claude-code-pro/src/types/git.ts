@@ -0,0 +1,171 @@
+/**
+ * Git 相关类型定义
+ */
+
+/** 文件变更类型 */
+export type GitChangeKind =
+  | 'added'
+  | 'modified'
+  | 'deleted'
+  | 'renamed'
+  | 'copied'
+  | 'typeChange'
+  | 'untracked'
+  | 'conflicted';
+
+/** 单个文件的状态 */
+export interface GitFileStatus {
+  path: string;
+  /** 重命名前的路径 */
+  oldPath?: string | null;
+  kind: GitChangeKind;
+}
+
+/** 工作区状态 */
+export interface GitStatus {
+  branch: string | null;
+  head: string | null;
+  staged: GitFileStatus[];
+  unstaged: GitFileStatus[];
+  untracked: GitFileStatus[];
+  conflicted: GitFileStatus[];
+}
+
+/** 差异行 */
+export interface DiffLine {
+  kind: 'context' | 'addition' | 'deletion';
+  content: string;
+  oldLineno: number | null;
+  newLineno: number | null;
+  /** 文件末尾没有换行符 */
+  noNewline: boolean;
+}
+
+/** 差异块 */
+export interface DiffHunk {
+  header: string;
+  oldStart: number;
+  oldLines: number;
+  newStart: number;
+  newLines: number;
+  lines: DiffLine[];
+}
+
+/** 单个文件的差异 */
+export interface FileDiff {
+  path: string;
+  oldPath?: string | null;
+  kind: GitChangeKind;
+  binary: boolean;
+  additions: number;
+  deletions: number;
+  hunks: DiffHunk[];
+}
+
+/** 分支信息 */
+export interface BranchInfo {
+  name: string;
+  isHead: boolean;
+  isRemote: boolean;
+  upstream: string | null;
+  commit: string | null;
+}
+
+/** 提交信息 */
+export interface CommitInfo {
+  id: string;
+  shortId: string;
+  summary: string;
+  message: string;
+  authorName: string;
+  authorEmail: string;
+  time: string;
+  parents: string[];
+  /** 是否由 Claude 会话提交 */
+  agent: boolean;
+}
+
+/** 分页的提交历史 */
+export interface CommitPage {
+  commits: CommitInfo[];
+  hasMore: boolean;
+}
+
+/** 储藏条目 */
+export interface StashInfo {
+  /** 储藏序号（0 为最新） */
+  index: number;
+  id: string;
+  message: string;
+}
+
+/** 会话专用的 Git 工作树 */
+export interface SessionWorktree {
+  sessionId: string;
+  repoPath: string;
+  /** 创建会话时的工作目录 */
+  workDir: string;
+  path: string;
+  name: string;
+  branch: string;
+  baseCommit: string;
+  createdAt: string;
+  /** Claude 会话 ID（前端收到 system 事件后改用它引用会话） */
+  aliases: string[];
+}
+
+/** 合并冲突的文件 */
+export interface MergeConflict {
+  path: string;
+  kind: 'bothModified' | 'bothAdded' | 'deletedByUs' | 'deletedByThem';
+}
+
+/** 工作树合并结果 */
+export interface MergeReport {
+  /** 存在冲突时为 false，主工作区不受影响 */
+  merged: boolean;
+  upToDate: boolean;
+  fastForward: boolean;
+  commit: string | null;
+  /** 合并前自动提交的工作树修改 */
+  autoCommit: string | null;
+  conflicts: MergeConflict[];
+}
+
+/** 逐行追溯结果中的一行 */
+export interface BlameLine {
+  line: number;
+  content: string;
+  /** 未提交的行为空 */
+  commit: string | null;
+  shortId: string | null;
+  summary: string | null;
+  authorName: string | null;
+  authorEmail: string | null;
+  time: string | null;
+  /** 是否来自 Claude 会话的提交 */
+  agent: boolean;
+  uncommitted: boolean;
+}
+
+/** 文件的逐行追溯 */
+export interface FileBlame {
+  path: string;
+  lines: BlameLine[];
+}
+
+/** 文件历史中的一次提交 */
+export interface FileHistoryEntry {
+  commit: CommitInfo;
+  /** 该提交中的文件路径 */
+  path: string;
+  /** 被重命名时的原路径 */
+  oldPath: string | null;
+  kind: GitChangeKind;
+}
+
+/** 分页的文件历史 */
+export interface FileHistoryPage {
+  entries: FileHistoryEntry[];
+  hasMore: boolean;
+}
@@ -7,3 +7,5 @@ export * from './config';
 export * from './workspace';
 export * from './fileExplorer';
 export * from './fileEditor';
+export * from './git';
+export * from './search';
@@ -0,0 +1,97 @@
+/**
+ * 内容搜索相关类型定义
+ */
+
+import type { FileVersion } from './fileEditor';
+import type { FileDiff } from './git';
+
+/** 全文搜索选项 */
+export interface ContentSearchOptions {
+  /** 按正则表达式匹配（否则按字面量） */
+  regex?: boolean;
+  caseSensitive?: boolean;
+  wholeWord?: boolean;
+  /** 只搜索匹配这些 glob 的文件（gitignore 语法，如 *.rs、src/**） */
+  include?: string[];
+  /** 额外排除的 glob */
+  exclude?: string[];
+  /** 最多返回的匹配行数（默认 2000） */
+  maxResults?: number;
+  maxResultsPerFile?: number;
+  /** 匹配行前后附带的上下文行数（最多 10） */
+  contextLines?: number;
+}
+
+/** 一行中的匹配 */
+export interface ContentMatch {
+  path: string;
+  relativePath: string;
+  /** 行号（从 1 开始） */
+  line: number;
+  /** 第一个匹配的列号（从 1 开始） */
+  column: number;
+  text: string;
+  /** text 中全部匹配的区间 [start, end) */
+  ranges: [number, number][];
+  before: string[];
+  after: string[];
+}
+
+/** 搜索结束时的统计 */
+export interface ContentSearchSummary {
+  filesSearched: number;
+  filesMatched: number;
+  matchCount: number;
+  /** 跳过的二进制或过大的文件数 */
+  filesSkipped: number;
+  /** 是否因达到结果上限而提前结束 */
+  truncated: boolean;
+  cancelled: boolean;
+  elapsedMs: number;
+}
+
+/** content-search 事件 */
+export type ContentSearchEvent =
+  | { type: 'matches'; searchId: string; matches: ContentMatch[] }
+  | { type: 'done'; searchId: string; summary: ContentSearchSummary; error: string | null };
+
+/** 替换的目标文件（通常来自搜索结果） */
+export interface ReplaceTarget {
+  path: string;
+  /** 只替换这些行（从 1 开始），不传时替换文件中的全部匹配 */
+  lines?: number[];
+  /** 预览时的内容哈希（applyReplace 必填） */
+  expectedHash?: string;
+}
+
+/** 单个文件的替换预览 */
+export interface ReplacePreview {
+  path: string;
+  /** 预览时的文件版本，应用时传回 hash */
+  version: FileVersion;
+  replacements: number;
+  diff: FileDiff;
+}
+
+/** 被跳过的文件 */
+export interface ReplaceSkip {
+  path: string;
+  reason: string;
+}
+
+export interface ReplacePreviewResult {
+  files: ReplacePreview[];
+  skipped: ReplaceSkip[];
+}
+
+export interface ReplaceApplied {
+  path: string;
+  replacements: number;
+  /** 写入后的版本 */
+  version: FileVersion;
+}
+
+export interface ReplaceResult {
+  applied: ReplaceApplied[];
+  skipped: ReplaceSkip[];
+}